@@ -0,0 +1,80 @@
+use flintlock::application::ecs::components::{
+    ColorShape, DirectionalLight, PointLight, Transform,
+};
+use flintlock::application::renderer::renderables::vertices::ColorVertex2D;
+use flintlock::{Application, Context, Entity, Game, WindowConfig};
+
+/// Spinning triangle lit by a directional and a point light.
+#[derive(Default)]
+struct Demo {
+    triangle: Option<Entity>,
+}
+
+impl Game for Demo {
+    fn init(&mut self, context: &mut Context) {
+        let world = &mut *context.world;
+
+        let triangle = world.spawn();
+        world.insert(triangle, Transform::default());
+        world.insert(
+            triangle,
+            ColorShape {
+                vertices: vec![
+                    ColorVertex2D {
+                        position: [-0.5, 0.5, 0.0],
+                        color: [1.0, 0.0, 0.0, 1.0],
+                    },
+                    ColorVertex2D {
+                        position: [0.5, 0.5, 0.0],
+                        color: [0.0, 1.0, 0.0, 1.0],
+                    },
+                    ColorVertex2D {
+                        position: [0.0, -0.5, 0.0],
+                        color: [0.0, 0.0, 1.0, 1.0],
+                    },
+                ],
+                layer: 0,
+            },
+        );
+        self.triangle = Some(triangle);
+
+        let dir_light = world.spawn();
+        world.insert(
+            dir_light,
+            DirectionalLight {
+                direction: [-1.0, 1.0],
+                color: [1.0, 1.0, 1.0],
+                intensity: 0.5,
+            },
+        );
+
+        let point_light = world.spawn();
+        world.insert(
+            point_light,
+            PointLight {
+                position: [0.0, 0.0, 0.0],
+                color: [1.0, 1.0, 1.0],
+                intensity: 0.5,
+                radius: 1.5,
+            },
+        );
+    }
+
+    fn update(&mut self, context: &mut Context, dt: f32) {
+        if let Some(transform) = self
+            .triangle
+            .and_then(|triangle| context.world.get_mut::<Transform>(triangle))
+        {
+            transform.rotation += dt;
+        }
+    }
+}
+
+fn main() {
+    let window = WindowConfig {
+        title: "Flintlock demo".to_string(),
+        ..Default::default()
+    };
+
+    Application::new(window).run_game(Demo::default());
+}
@@ -0,0 +1,227 @@
+use serde::{Deserialize, Serialize};
+
+use std::f32::consts::PI;
+
+/// Easing curve, mapping the linear progress of a tween to the progress of
+/// its value. "In" curves start slow, "out" curves end slow.
+/// * Elastic and back curves overshoot below 0 and above 1.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Ease {
+    #[default]
+    Linear,
+    QuadIn,
+    QuadOut,
+    QuadInOut,
+    CubicIn,
+    CubicOut,
+    CubicInOut,
+    ElasticIn,
+    ElasticOut,
+    ElasticInOut,
+    BounceIn,
+    BounceOut,
+    BounceInOut,
+    BackIn,
+    BackOut,
+    BackInOut,
+}
+
+impl Ease {
+    /// Eased progress for a progress `t` between 0 and 1. Maps 0 to 0 and 1
+    /// to 1.
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+
+        match self {
+            Ease::Linear => t,
+            Ease::QuadIn => t * t,
+            Ease::QuadOut => 1.0 - (1.0 - t).powi(2),
+            Ease::QuadInOut => match t < 0.5 {
+                true => 2.0 * t * t,
+                false => 1.0 - (-2.0 * t + 2.0).powi(2) / 2.0,
+            },
+            Ease::CubicIn => t.powi(3),
+            Ease::CubicOut => 1.0 - (1.0 - t).powi(3),
+            Ease::CubicInOut => match t < 0.5 {
+                true => 4.0 * t.powi(3),
+                false => 1.0 - (-2.0 * t + 2.0).powi(3) / 2.0,
+            },
+            Ease::ElasticIn => elastic(t, |t| {
+                -(2.0f32.powf(10.0 * t - 10.0)) * ((10.0 * t - 10.75) * ELASTIC).sin()
+            }),
+            Ease::ElasticOut => elastic(t, |t| {
+                2.0f32.powf(-10.0 * t) * ((10.0 * t - 0.75) * ELASTIC).sin() + 1.0
+            }),
+            Ease::ElasticInOut => elastic(t, |t| {
+                let wave = ((20.0 * t - 11.125) * ELASTIC_IN_OUT).sin();
+                match t < 0.5 {
+                    true => -(2.0f32.powf(20.0 * t - 10.0) * wave) / 2.0,
+                    false => 2.0f32.powf(-20.0 * t + 10.0) * wave / 2.0 + 1.0,
+                }
+            }),
+            Ease::BounceIn => 1.0 - bounce_out(1.0 - t),
+            Ease::BounceOut => bounce_out(t),
+            Ease::BounceInOut => match t < 0.5 {
+                true => (1.0 - bounce_out(1.0 - 2.0 * t)) / 2.0,
+                false => (1.0 + bounce_out(2.0 * t - 1.0)) / 2.0,
+            },
+            Ease::BackIn => (BACK + 1.0) * t.powi(3) - BACK * t * t,
+            Ease::BackOut => 1.0 + (BACK + 1.0) * (t - 1.0).powi(3) + BACK * (t - 1.0).powi(2),
+            Ease::BackInOut => {
+                let back = BACK * 1.525;
+                match t < 0.5 {
+                    true => (2.0 * t).powi(2) * ((back + 1.0) * 2.0 * t - back) / 2.0,
+                    false => {
+                        ((2.0 * t - 2.0).powi(2) * ((back + 1.0) * (2.0 * t - 2.0) + back) + 2.0)
+                            / 2.0
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Overshoot of the back curves, about 10%.
+const BACK: f32 = 1.70158;
+
+const ELASTIC: f32 = 2.0 * PI / 3.0;
+const ELASTIC_IN_OUT: f32 = 2.0 * PI / 4.5;
+
+/// Elastic curves only reach 0 and 1 asymptotically, so pin the ends.
+fn elastic(t: f32, curve: impl Fn(f32) -> f32) -> f32 {
+    match t {
+        t if t <= 0.0 => 0.0,
+        t if t >= 1.0 => 1.0,
+        t => curve(t),
+    }
+}
+
+fn bounce_out(t: f32) -> f32 {
+    const N: f32 = 7.5625;
+    const D: f32 = 2.75;
+
+    if t < 1.0 / D {
+        N * t * t
+    } else if t < 2.0 / D {
+        let t = t - 1.5 / D;
+        N * t * t + 0.75
+    } else if t < 2.5 / D {
+        let t = t - 2.25 / D;
+        N * t * t + 0.9375
+    } else {
+        let t = t - 2.625 / D;
+        N * t * t + 0.984375
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EASES: [Ease; 16] = [
+        Ease::Linear,
+        Ease::QuadIn,
+        Ease::QuadOut,
+        Ease::QuadInOut,
+        Ease::CubicIn,
+        Ease::CubicOut,
+        Ease::CubicInOut,
+        Ease::ElasticIn,
+        Ease::ElasticOut,
+        Ease::ElasticInOut,
+        Ease::BounceIn,
+        Ease::BounceOut,
+        Ease::BounceInOut,
+        Ease::BackIn,
+        Ease::BackOut,
+        Ease::BackInOut,
+    ];
+
+    fn samples(ease: Ease) -> impl Iterator<Item = f32> {
+        (0..=100).map(move |i| ease.apply(i as f32 / 100.0))
+    }
+
+    #[test]
+    fn every_curve_starts_at_0_and_ends_at_1() {
+        for ease in EASES {
+            assert!(
+                ease.apply(0.0).abs() < 1e-5,
+                "{:?} starts at {}",
+                ease,
+                ease.apply(0.0)
+            );
+            assert!(
+                (ease.apply(1.0) - 1.0).abs() < 1e-5,
+                "{:?} ends at {}",
+                ease,
+                ease.apply(1.0)
+            );
+        }
+    }
+
+    #[test]
+    fn progress_outside_0_to_1_is_clamped() {
+        for ease in EASES {
+            assert_eq!(ease.apply(-1.0), ease.apply(0.0), "{:?}", ease);
+            assert_eq!(ease.apply(2.0), ease.apply(1.0), "{:?}", ease);
+        }
+    }
+
+    #[test]
+    fn in_out_curves_pass_through_the_middle() {
+        for ease in [
+            Ease::Linear,
+            Ease::QuadInOut,
+            Ease::CubicInOut,
+            Ease::ElasticInOut,
+            Ease::BounceInOut,
+            Ease::BackInOut,
+        ] {
+            assert!((ease.apply(0.5) - 0.5).abs() < 1e-5, "{:?}", ease);
+        }
+    }
+
+    #[test]
+    fn elastic_and_back_curves_overshoot() {
+        for ease in [Ease::ElasticIn, Ease::BackIn] {
+            assert!(
+                samples(ease).any(|v| v < 0.0),
+                "{:?} never dips below 0",
+                ease
+            );
+        }
+        for ease in [Ease::ElasticOut, Ease::BackOut] {
+            assert!(samples(ease).any(|v| v > 1.0), "{:?} never passes 1", ease);
+        }
+        for ease in [Ease::ElasticInOut, Ease::BackInOut] {
+            assert!(
+                samples(ease).any(|v| v < 0.0),
+                "{:?} never dips below 0",
+                ease
+            );
+            assert!(samples(ease).any(|v| v > 1.0), "{:?} never passes 1", ease);
+        }
+    }
+
+    #[test]
+    fn other_curves_stay_within_0_to_1() {
+        for ease in [
+            Ease::Linear,
+            Ease::QuadIn,
+            Ease::QuadOut,
+            Ease::QuadInOut,
+            Ease::CubicIn,
+            Ease::CubicOut,
+            Ease::CubicInOut,
+            Ease::BounceIn,
+            Ease::BounceOut,
+            Ease::BounceInOut,
+        ] {
+            assert!(
+                samples(ease).all(|v| (-1e-5..=1.0 + 1e-5).contains(&v)),
+                "{:?}",
+                ease
+            );
+        }
+    }
+}
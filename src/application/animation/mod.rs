@@ -0,0 +1,15 @@
+//! Things that change over time, advanced by the fixed update.
+
+pub mod easing;
+pub use easing::Ease;
+
+pub mod skeleton;
+pub use skeleton::{Skeleton, SkeletonData, SkeletonError};
+
+pub mod sprite;
+pub use sprite::{
+    AnimatedSprite, AnimationClip, AnimationEvent, AnimationFrame, PlayMode, SpriteSheet,
+};
+
+pub mod tween;
+pub use tween::{Animation, Call, Delay, Parallel, Sequence, Tween, TweenId, Tweenable, Tweens};
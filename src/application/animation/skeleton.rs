@@ -0,0 +1,849 @@
+//! Cutout animation: sprites attached to a hierarchy of bones, posed by
+//! keyframed rotation, translation and scale.
+//!
+//! Skeletons are loaded from JSON:
+//! ```json
+//! {
+//!   "atlas": "hero.json",
+//!   "bones": [
+//!     { "name": "hip" },
+//!     { "name": "torso", "parent": "hip", "y": -12, "rotation": 5 }
+//!   ],
+//!   "slots": [
+//!     { "name": "body", "bone": "torso", "region": "torso", "size": [16, 24], "y": -8 }
+//!   ],
+//!   "animations": {
+//!     "walk": {
+//!       "loop": true,
+//!       "bones": {
+//!         "torso": {
+//!           "rotate": [
+//!             { "time": 0.0, "value": -5, "ease": "QuadInOut" },
+//!             { "time": 0.4, "value": 5, "ease": "QuadInOut" },
+//!             { "time": 0.8, "value": -5 }
+//!           ]
+//!         }
+//!       }
+//!     }
+//!   }
+//! }
+//! ```
+//! * `atlas` is a sprite sheet as read by `TextureAtlas::load`, relative to
+//!   the skeleton file. Slots show its regions.
+//! * Bones list their parent before themselves. `x`, `y`, `rotation` (in
+//!   degrees) and `scale` default to the identity.
+//! * Slots are drawn in order, later ones in front, unless given a `layer`.
+//!   Their `x`, `y` and `rotation` place the image's center on the bone.
+//! * Keyframes of `rotate` (degrees) and `translate` are added to a bone's
+//!   setup pose and keyframes of `scale` multiply it. `ease` is the curve
+//!   towards the next keyframe, linear by default.
+//! * An animation lasts until its last keyframe unless it has a `duration`,
+//!   and loops unless `loop` is false.
+
+use nalgebra_glm::TMat4;
+use serde::Deserialize;
+
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+
+use super::easing::Ease;
+use super::sprite::AnimationEvent;
+use super::tween::Tweenable;
+use crate::application::renderer::atlas::{AtlasError, TextureAtlas};
+use crate::application::renderer::renderables::quad::Quad;
+use crate::application::renderer::{Frame, Renderer};
+use crate::application::scene::{
+    world_directional_light, world_point_light, Attachment, NodeId, Scene, Transform2D,
+};
+
+#[derive(Deserialize)]
+struct SkeletonJson {
+    atlas: Option<String>,
+    bones: Vec<BoneJson>,
+    #[serde(default)]
+    slots: Vec<SlotJson>,
+    #[serde(default)]
+    animations: HashMap<String, AnimationJson>,
+}
+
+#[derive(Deserialize)]
+struct BoneJson {
+    name: String,
+    parent: Option<String>,
+    #[serde(default)]
+    x: f32,
+    #[serde(default)]
+    y: f32,
+    #[serde(default)]
+    rotation: f32,
+    #[serde(default = "unit_scale")]
+    scale: [f32; 2],
+}
+
+#[derive(Deserialize)]
+struct SlotJson {
+    name: String,
+    bone: String,
+    region: String,
+    size: [f32; 2],
+    #[serde(default)]
+    x: f32,
+    #[serde(default)]
+    y: f32,
+    #[serde(default)]
+    rotation: f32,
+    layer: Option<i32>,
+}
+
+#[derive(Deserialize)]
+struct AnimationJson {
+    duration: Option<f32>,
+    #[serde(rename = "loop", default = "default_loop")]
+    looping: bool,
+    #[serde(default)]
+    bones: HashMap<String, TimelinesJson>,
+}
+
+#[derive(Deserialize)]
+struct TimelinesJson {
+    #[serde(default)]
+    rotate: Vec<Keyframe<f32>>,
+    #[serde(default)]
+    translate: Vec<Keyframe<[f32; 2]>>,
+    #[serde(default)]
+    scale: Vec<Keyframe<[f32; 2]>>,
+}
+
+fn unit_scale() -> [f32; 2] {
+    [1.0, 1.0]
+}
+
+fn default_loop() -> bool {
+    true
+}
+
+/// A value a timeline passes through at a point in time.
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub struct Keyframe<T> {
+    pub time: f32,
+    pub value: T,
+    /// Curve towards the next keyframe.
+    #[serde(default)]
+    pub ease: Ease,
+}
+
+/// Samples keyframes sorted by time. Holds the first and last values before
+/// and after them.
+fn sample<T: Tweenable>(keys: &[Keyframe<T>], time: f32) -> Option<T> {
+    let next = keys.iter().position(|key| key.time > time);
+
+    match next {
+        None => keys.last().map(|key| key.value),
+        Some(0) => Some(keys[0].value),
+        Some(next) => {
+            let (from, to) = (&keys[next - 1], &keys[next]);
+            let t = (time - from.time) / (to.time - from.time);
+            Some(from.value.tween(&to.value, from.ease.apply(t)))
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Bone {
+    pub name: String,
+    /// Index of the parent bone, which always comes first.
+    pub parent: Option<usize>,
+    /// Setup pose, relative to the parent.
+    pub setup: Transform2D,
+}
+
+/// An image shown on a bone.
+#[derive(Clone, Debug)]
+pub struct Slot {
+    pub name: String,
+    pub bone: usize,
+    /// Index of the shown region in the skeleton's atlas.
+    pub region: usize,
+    /// Width and height in world units.
+    pub size: [f32; 2],
+    /// Placement of the image's center relative to the bone.
+    pub offset: Transform2D,
+    pub layer: i32,
+}
+
+/// Keyframes of one bone, as offsets from its setup pose. Rotations are in
+/// radians.
+#[derive(Clone, Debug, Default)]
+pub struct BoneTimelines {
+    pub rotate: Vec<Keyframe<f32>>,
+    pub translate: Vec<Keyframe<[f32; 2]>>,
+    pub scale: Vec<Keyframe<[f32; 2]>>,
+}
+
+#[derive(Clone, Debug)]
+pub struct SkeletonAnimation {
+    pub duration: f32,
+    pub looping: bool,
+    /// Timelines by bone index.
+    pub bones: HashMap<usize, BoneTimelines>,
+}
+
+impl SkeletonAnimation {
+    /// Pose of a bone at `time`.
+    pub fn pose(&self, bone: usize, setup: &Transform2D, time: f32) -> Transform2D {
+        let timelines = match self.bones.get(&bone) {
+            Some(timelines) => timelines,
+            None => return *setup,
+        };
+
+        let mut pose = *setup;
+        if let Some(rotation) = sample(&timelines.rotate, time) {
+            pose.rotation += rotation;
+        }
+        if let Some([x, y]) = sample(&timelines.translate, time) {
+            pose.position = [pose.position[0] + x, pose.position[1] + y];
+        }
+        if let Some([x, y]) = sample(&timelines.scale, time) {
+            pose.scale = [pose.scale[0] * x, pose.scale[1] * y];
+        }
+        pose
+    }
+}
+
+/// Bones, slots and animations of a rig, shared by the Skeletons posing it.
+pub struct SkeletonData {
+    pub atlas: Arc<TextureAtlas>,
+    pub bones: Vec<Bone>,
+    pub slots: Vec<Slot>,
+    pub animations: HashMap<String, SkeletonAnimation>,
+}
+
+impl SkeletonData {
+    /// Loads a skeleton file, along with the atlas it names.
+    pub fn load(renderer: &Renderer, path: &str) -> Result<SkeletonData, SkeletonError> {
+        let json: SkeletonJson = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+
+        let atlas = match &json.atlas {
+            Some(atlas) => {
+                let atlas = Path::new(path)
+                    .parent()
+                    .unwrap_or_else(|| Path::new(""))
+                    .join(atlas);
+                TextureAtlas::load(renderer, &atlas.to_string_lossy())?
+            }
+            None => {
+                return Err(SkeletonError::Invalid(
+                    "no atlas given; use SkeletonData::from_json with an atlas".to_string(),
+                ))
+            }
+        };
+
+        SkeletonData::from_parsed(json, Arc::new(atlas))
+    }
+
+    /// Reads skeleton JSON whose slots show regions of the given atlas. Its
+    /// own `atlas` field is ignored.
+    pub fn from_json(json: &str, atlas: Arc<TextureAtlas>) -> Result<SkeletonData, SkeletonError> {
+        SkeletonData::from_parsed(serde_json::from_str(json)?, atlas)
+    }
+
+    fn from_parsed(
+        json: SkeletonJson,
+        atlas: Arc<TextureAtlas>,
+    ) -> Result<SkeletonData, SkeletonError> {
+        let mut indices = HashMap::new();
+        let mut bones = Vec::new();
+
+        for bone in json.bones {
+            let parent = match &bone.parent {
+                Some(parent) => Some(*indices.get(parent).ok_or_else(|| {
+                    SkeletonError::Invalid(format!(
+                        "bone {} comes before its parent {}",
+                        bone.name, parent
+                    ))
+                })?),
+                None => None,
+            };
+
+            if indices.insert(bone.name.clone(), bones.len()).is_some() {
+                return Err(SkeletonError::Invalid(format!(
+                    "bone {} is listed twice",
+                    bone.name
+                )));
+            }
+
+            bones.push(Bone {
+                name: bone.name,
+                parent,
+                setup: Transform2D {
+                    position: [bone.x, bone.y],
+                    rotation: bone.rotation.to_radians(),
+                    scale: bone.scale,
+                    ..Default::default()
+                },
+            });
+        }
+
+        let bone_index = |name: &str| {
+            indices
+                .get(name)
+                .copied()
+                .ok_or_else(|| SkeletonError::Invalid(format!("no bone named {}", name)))
+        };
+
+        let slots = json
+            .slots
+            .into_iter()
+            .enumerate()
+            .map(|(i, slot)| {
+                Ok(Slot {
+                    bone: bone_index(&slot.bone)?,
+                    region: atlas.index(&slot.region).ok_or_else(|| {
+                        SkeletonError::Invalid(format!("no atlas region named {}", slot.region))
+                    })?,
+                    size: slot.size,
+                    offset: Transform2D {
+                        position: [slot.x, slot.y],
+                        rotation: slot.rotation.to_radians(),
+                        ..Default::default()
+                    },
+                    layer: slot.layer.unwrap_or(i as i32),
+                    name: slot.name,
+                })
+            })
+            .collect::<Result<Vec<_>, SkeletonError>>()?;
+
+        let mut animations = HashMap::new();
+        for (name, animation) in json.animations {
+            let mut end: f32 = 0.0;
+            let mut timelines = HashMap::new();
+
+            for (bone, mut timeline) in animation.bones {
+                timeline
+                    .rotate
+                    .iter_mut()
+                    .for_each(|key| key.value = key.value.to_radians());
+                timeline.rotate.sort_by(|a, b| a.time.total_cmp(&b.time));
+                timeline.translate.sort_by(|a, b| a.time.total_cmp(&b.time));
+                timeline.scale.sort_by(|a, b| a.time.total_cmp(&b.time));
+
+                end = (timeline.rotate.iter().map(|key| key.time))
+                    .chain(timeline.translate.iter().map(|key| key.time))
+                    .chain(timeline.scale.iter().map(|key| key.time))
+                    .fold(end, f32::max);
+
+                timelines.insert(
+                    bone_index(&bone)?,
+                    BoneTimelines {
+                        rotate: timeline.rotate,
+                        translate: timeline.translate,
+                        scale: timeline.scale,
+                    },
+                );
+            }
+
+            animations.insert(
+                name,
+                SkeletonAnimation {
+                    duration: animation.duration.unwrap_or(end),
+                    looping: animation.looping,
+                    bones: timelines,
+                },
+            );
+        }
+
+        Ok(SkeletonData {
+            atlas,
+            bones,
+            slots,
+            animations,
+        })
+    }
+
+    pub fn bone_index(&self, name: &str) -> Option<usize> {
+        self.bones.iter().position(|bone| bone.name == name)
+    }
+}
+
+/// A posed instance of a rig. Each bone is a node of the skeleton's own
+/// Scene, under a root node placing the whole skeleton, and each slot is a
+/// Quad attached to its bone.
+/// * Draw it with `draw`, or add it to an entity to have the application
+///   advance and draw it, placed by the entity's Transform. Its events go to
+///   the `AnimationEvents` resource.
+pub struct Skeleton {
+    data: Arc<SkeletonData>,
+    scene: Scene,
+    root: NodeId,
+    bones: Vec<NodeId>,
+    animation: Option<String>,
+    time: f32,
+    finished: bool,
+    /// Multiplies time passing: 2 plays twice as fast, 0 holds the pose.
+    pub speed: f32,
+    pub paused: bool,
+}
+
+impl Skeleton {
+    /// Skeleton in its setup pose, with slots drawn on `layer` plus their
+    /// own layer.
+    pub fn new(data: Arc<SkeletonData>, layer: i32) -> Skeleton {
+        let mut scene = Scene::new();
+        let root = scene.add(Transform2D::default());
+
+        let mut bones: Vec<NodeId> = Vec::with_capacity(data.bones.len());
+        for bone in &data.bones {
+            let parent = bone.parent.map_or(root, |parent| bones[parent]);
+            bones.push(scene.add_child(parent, bone.setup));
+        }
+
+        for slot in &data.slots {
+            let region = data.atlas.region_at(slot.region).unwrap();
+
+            let quad = Quad {
+                vertices: Quad::rect(slot.size, region.uv),
+                matrix: slot.offset.matrix(),
+                layer: layer + slot.layer,
+                texture: data.atlas.texture(region),
+            };
+            scene.attach(bones[slot.bone], Attachment::Renderable(Box::new(quad)));
+        }
+
+        scene.update();
+
+        Skeleton {
+            data,
+            scene,
+            root,
+            bones,
+            animation: None,
+            time: 0.0,
+            finished: false,
+            speed: 1.0,
+            paused: false,
+        }
+    }
+
+    pub fn data(&self) -> &Arc<SkeletonData> {
+        &self.data
+    }
+
+    /// Places the whole skeleton. Entities' skeletons are placed by their
+    /// Transform on top of this.
+    pub fn set_transform(&mut self, transform: Transform2D) {
+        self.scene.set_transform(self.root, transform);
+        self.scene.update();
+    }
+
+    /// Node of the named bone, e.g. to attach a weapon to a hand.
+    pub fn bone(&self, name: &str) -> Option<NodeId> {
+        self.data.bone_index(name).map(|index| self.bones[index])
+    }
+
+    /// The skeleton's scene. Call `Scene::update` after changing it, or the
+    /// changes show from the next `update` of the skeleton on.
+    pub fn scene_mut(&mut self) -> &mut Scene {
+        &mut self.scene
+    }
+
+    pub fn scene(&self) -> &Scene {
+        &self.scene
+    }
+
+    /// Plays the named animation from its start, unless it is already
+    /// playing. Returns false if there is no such animation.
+    pub fn play(&mut self, animation: &str) -> bool {
+        if !self.data.animations.contains_key(animation) {
+            return false;
+        }
+
+        if self.animation.as_deref() != Some(animation) || self.finished {
+            self.animation = Some(animation.to_string());
+            self.time = 0.0;
+            self.finished = false;
+            self.apply_pose();
+        }
+
+        true
+    }
+
+    /// Stops animating and goes back to the setup pose.
+    pub fn stop(&mut self) {
+        self.animation = None;
+        self.time = 0.0;
+        self.finished = false;
+        self.apply_pose();
+    }
+
+    pub fn animation(&self) -> Option<&str> {
+        self.animation.as_deref()
+    }
+
+    /// Seconds into the current animation.
+    pub fn time(&self) -> f32 {
+        self.time
+    }
+
+    /// Jumps to a point in the current animation.
+    pub fn seek(&mut self, time: f32) {
+        self.time = time.max(0.0);
+        self.apply_pose();
+    }
+
+    /// Whether an animation that does not loop has ended.
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Advances the current animation by `dt` seconds, times the speed, and
+    /// poses the bones. Returns Looped each time the animation wraps around
+    /// and Finished when one that does not loop ends.
+    pub fn update(&mut self, dt: f32) -> Vec<AnimationEvent> {
+        let mut events = Vec::new();
+        if self.paused || self.finished {
+            return events;
+        }
+
+        let data = self.data.clone();
+        let animation = match self
+            .animation
+            .as_ref()
+            .and_then(|name| data.animations.get(name))
+        {
+            Some(animation) => animation,
+            None => return events,
+        };
+
+        self.time += dt * self.speed.max(0.0);
+
+        if self.time >= animation.duration {
+            match animation.looping && animation.duration > 0.0 {
+                true => {
+                    while self.time >= animation.duration {
+                        self.time -= animation.duration;
+                        events.push(AnimationEvent::Looped);
+                    }
+                }
+                false => {
+                    self.time = animation.duration;
+                    self.finished = true;
+                    events.push(AnimationEvent::Finished);
+                }
+            }
+        }
+
+        self.apply_pose();
+        events
+    }
+
+    /// Sets every bone to the current animation's pose, or the setup pose.
+    fn apply_pose(&mut self) {
+        let animation = self
+            .animation
+            .as_ref()
+            .and_then(|name| self.data.animations.get(name));
+
+        for (index, bone) in self.data.bones.iter().enumerate() {
+            let pose = match animation {
+                Some(animation) => animation.pose(index, &bone.setup, self.time),
+                None => bone.setup,
+            };
+            self.scene.set_transform(self.bones[index], pose);
+        }
+
+        self.scene.update();
+    }
+
+    /// Queues the posed slots, and anything else attached to the skeleton's
+    /// nodes, in a frame.
+    pub fn draw(&self, frame: &mut Frame) {
+        self.draw_with_matrix(frame, nalgebra_glm::identity());
+    }
+
+    /// Queues the posed skeleton placed by `matrix`.
+    pub fn draw_with_matrix(&self, frame: &mut Frame, matrix: TMat4<f32>) {
+        for id in self.scene.visible_nodes() {
+            let world = matrix * self.scene.world_matrix(id);
+
+            for attachment in self.scene.attachments(id) {
+                match attachment {
+                    Attachment::Renderable(model) => {
+                        frame.draw_with_matrix(model.as_ref(), world * model.matrix())
+                    }
+                    Attachment::ColorRenderable(model) => {
+                        frame.color_draw_with_matrix(model.as_ref(), world * model.matrix())
+                    }
+                    Attachment::PointLight(light) => frame.point(world_point_light(&world, light)),
+                    Attachment::DirectionalLight(light) => {
+                        frame.directional(world_directional_light(&world, light))
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum SkeletonError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    Atlas(AtlasError),
+    /// The skeleton refers to something that does not exist, or its bones
+    /// are out of order.
+    Invalid(String),
+}
+
+impl fmt::Display for SkeletonError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SkeletonError::Io(err) => write!(f, "Failed to read skeleton: {}", err),
+            SkeletonError::Json(err) => write!(f, "Invalid skeleton JSON: {}", err),
+            SkeletonError::Atlas(err) => write!(f, "Failed to load skeleton atlas: {}", err),
+            SkeletonError::Invalid(reason) => write!(f, "Invalid skeleton: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for SkeletonError {}
+
+impl From<std::io::Error> for SkeletonError {
+    fn from(err: std::io::Error) -> SkeletonError {
+        SkeletonError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for SkeletonError {
+    fn from(err: serde_json::Error) -> SkeletonError {
+        SkeletonError::Json(err)
+    }
+}
+
+impl From<AtlasError> for SkeletonError {
+    fn from(err: AtlasError) -> SkeletonError {
+        SkeletonError::Atlas(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::application::renderer::atlas::AtlasRegion;
+
+    const RIG: &str = r#"{
+        "bones": [
+            { "name": "hip", "x": 10 },
+            { "name": "arm", "parent": "hip", "rotation": 90, "scale": [2, 2] }
+        ],
+        "slots": [{ "name": "sleeve", "bone": "arm", "region": "sleeve", "size": [4, 8] }],
+        "animations": {
+            "wave": {
+                "bones": {
+                    "arm": {
+                        "rotate": [{ "time": 1, "value": 90 }, { "time": 0, "value": 0 }],
+                        "translate": [{ "time": 0, "value": [0, 0] }, { "time": 1, "value": [4, 2] }],
+                        "scale": [{ "time": 0, "value": [1, 1] }, { "time": 1, "value": [0.5, 2] }]
+                    }
+                }
+            },
+            "wave_once": { "loop": false, "duration": 2, "bones": {} }
+        }
+    }"#;
+
+    fn atlas(regions: &[&str]) -> Arc<TextureAtlas> {
+        let regions = regions
+            .iter()
+            .map(|name| {
+                let region = AtlasRegion {
+                    page: 0,
+                    uv: [[0.0, 0.0], [1.0, 1.0]],
+                    size: [1, 1],
+                };
+                (name.to_string(), region)
+            })
+            .collect();
+        Arc::new(TextureAtlas::new(Vec::new(), regions))
+    }
+
+    fn parse(json: &str) -> Result<SkeletonData, SkeletonError> {
+        SkeletonData::from_json(json, atlas(&["sleeve"]))
+    }
+
+    fn invalid(json: &str) -> String {
+        match parse(json) {
+            Err(SkeletonError::Invalid(reason)) => reason,
+            Err(err) => panic!("expected an invalid skeleton, got {}", err),
+            Ok(_) => panic!("expected an invalid skeleton"),
+        }
+    }
+
+    fn key<T>(time: f32, value: T, ease: Ease) -> Keyframe<T> {
+        Keyframe { time, value, ease }
+    }
+
+    fn assert_close(a: f32, b: f32) {
+        assert!((a - b).abs() < 1e-5, "{} is not {}", a, b);
+    }
+
+    /// Skeleton of a rig without slots, which need no textures.
+    fn skeleton(animations: &str) -> Skeleton {
+        let json = format!(
+            r#"{{ "bones": [{{ "name": "hip" }}], "animations": {} }}"#,
+            animations
+        );
+        Skeleton::new(Arc::new(parse(&json).unwrap()), 0)
+    }
+
+    #[test]
+    fn sample_holds_the_ends_and_eases_between_keyframes() {
+        let keys = [
+            key(1.0, 10.0, Ease::Linear),
+            key(2.0, 20.0, Ease::QuadIn),
+            key(3.0, 30.0, Ease::Linear),
+        ];
+
+        assert_eq!(sample::<f32>(&[], 1.0), None);
+        assert_eq!(sample(&keys, 0.0), Some(10.0));
+        assert_eq!(sample(&keys, 1.0), Some(10.0));
+        assert_eq!(sample(&keys, 1.5), Some(15.0));
+        assert_eq!(sample(&keys, 2.5), Some(22.5));
+        assert_eq!(sample(&keys, 3.0), Some(30.0));
+        assert_eq!(sample(&keys, 9.0), Some(30.0));
+    }
+
+    #[test]
+    fn pose_offsets_rotation_and_translation_and_multiplies_scale() {
+        let setup = Transform2D {
+            position: [1.0, 2.0],
+            rotation: 0.5,
+            scale: [2.0, 3.0],
+            ..Default::default()
+        };
+        let timelines = BoneTimelines {
+            rotate: vec![key(0.0, 0.25, Ease::Linear)],
+            translate: vec![key(0.0, [1.0, -1.0], Ease::Linear)],
+            scale: vec![key(0.0, [0.5, 2.0], Ease::Linear)],
+        };
+        let animation = SkeletonAnimation {
+            duration: 1.0,
+            looping: true,
+            bones: HashMap::from([(0, timelines)]),
+        };
+
+        let pose = animation.pose(0, &setup, 0.0);
+        assert_eq!(pose.position, [2.0, 1.0]);
+        assert_eq!(pose.rotation, 0.75);
+        assert_eq!(pose.scale, [1.0, 6.0]);
+
+        // bones without timelines keep their setup pose
+        assert_eq!(animation.pose(1, &setup, 0.0), setup);
+    }
+
+    #[test]
+    fn from_json_reads_bones_slots_and_animations() {
+        let data = parse(RIG).unwrap();
+
+        assert_eq!(data.bones.len(), 2);
+        assert_eq!(data.bones[0].parent, None);
+        assert_eq!(data.bones[0].setup.position, [10.0, 0.0]);
+        assert_eq!(data.bones[1].parent, Some(0));
+        assert_close(data.bones[1].setup.rotation, std::f32::consts::FRAC_PI_2);
+        assert_eq!(data.bones[1].setup.scale, [2.0, 2.0]);
+        assert_eq!(data.bone_index("arm"), Some(1));
+
+        assert_eq!(data.slots.len(), 1);
+        assert_eq!(data.slots[0].bone, 1);
+        assert_eq!(data.slots[0].region, 0);
+        assert_eq!(data.slots[0].layer, 0);
+
+        let wave = &data.animations["wave"];
+        assert!(wave.looping);
+        assert_eq!(wave.duration, 1.0);
+        // keyframes are sorted by time and rotations turned into radians
+        let rotate = &wave.bones[&1].rotate;
+        assert_eq!(rotate[0].time, 0.0);
+        assert_close(rotate[1].value, std::f32::consts::FRAC_PI_2);
+
+        let wave_once = &data.animations["wave_once"];
+        assert!(!wave_once.looping);
+        assert_eq!(wave_once.duration, 2.0);
+    }
+
+    #[test]
+    fn from_json_refuses_a_parent_listed_after_its_child() {
+        let reason =
+            invalid(r#"{ "bones": [{ "name": "arm", "parent": "hip" }, { "name": "hip" }] }"#);
+        assert!(reason.contains("before its parent"), "{}", reason);
+    }
+
+    #[test]
+    fn from_json_refuses_duplicate_bones() {
+        let reason = invalid(r#"{ "bones": [{ "name": "hip" }, { "name": "hip" }] }"#);
+        assert!(reason.contains("listed twice"), "{}", reason);
+    }
+
+    #[test]
+    fn from_json_refuses_unknown_bones_and_regions() {
+        let reason = invalid(
+            r#"{ "bones": [{ "name": "hip" }],
+                 "slots": [{ "name": "s", "bone": "arm", "region": "sleeve", "size": [1, 1] }] }"#,
+        );
+        assert!(reason.contains("no bone named arm"), "{}", reason);
+
+        let reason = invalid(
+            r#"{ "bones": [{ "name": "hip" }],
+                 "animations": { "wave": { "bones": { "arm": {} } } } }"#,
+        );
+        assert!(reason.contains("no bone named arm"), "{}", reason);
+
+        let reason = invalid(
+            r#"{ "bones": [{ "name": "hip" }],
+                 "slots": [{ "name": "s", "bone": "hip", "region": "glove", "size": [1, 1] }] }"#,
+        );
+        assert!(reason.contains("no atlas region named glove"), "{}", reason);
+    }
+
+    #[test]
+    fn looping_animation_wraps_once_per_lap() {
+        let mut skeleton = skeleton(
+            r#"{ "walk": { "bones": { "hip": { "translate": [
+                { "time": 0, "value": [0, 0] }, { "time": 1, "value": [10, 0] }
+            ] } } } }"#,
+        );
+        assert!(skeleton.play("walk"));
+        let hip = skeleton.bone("hip").unwrap();
+
+        assert!(skeleton.update(0.5).is_empty());
+        assert_eq!(skeleton.scene().transform(hip).position, [5.0, 0.0]);
+
+        assert_eq!(
+            skeleton.update(2.75),
+            [
+                AnimationEvent::Looped,
+                AnimationEvent::Looped,
+                AnimationEvent::Looped
+            ]
+        );
+        assert_close(skeleton.time(), 0.25);
+        assert!(!skeleton.is_finished());
+        assert_eq!(skeleton.scene().transform(hip).position, [2.5, 0.0]);
+    }
+
+    #[test]
+    fn animation_that_does_not_loop_finishes_once() {
+        let mut skeleton = skeleton(r#"{ "wave": { "loop": false, "duration": 1 } }"#);
+        assert!(skeleton.play("wave"));
+
+        assert_eq!(skeleton.update(2.5), [AnimationEvent::Finished]);
+        assert_eq!(skeleton.time(), 1.0);
+        assert!(skeleton.is_finished());
+        assert!(skeleton.update(1.0).is_empty());
+
+        // playing it again restarts it
+        assert!(skeleton.play("wave"));
+        assert!(!skeleton.is_finished());
+        assert_eq!(skeleton.time(), 0.0);
+        assert!(!skeleton.play("run"));
+    }
+}
@@ -0,0 +1,490 @@
+use nalgebra_glm::{self as glm, TMat4};
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::application::renderer::atlas::sheet::{SheetJson, SheetTag};
+use crate::application::renderer::atlas::{AtlasError, TextureAtlas};
+use crate::application::renderer::renderables::quad::Quad;
+use crate::application::renderer::renderables::vertices::Vertex2D;
+use crate::application::renderer::renderables::Renderable;
+use crate::application::renderer::texture::Texture;
+use crate::application::renderer::Renderer;
+
+/// Frames shorter than this are treated as this long, so a zero duration can
+/// not stall an update.
+const MIN_FRAME_DURATION: f32 = 0.001;
+
+/// Aseprite's default frame duration, for sheets without durations.
+const DEFAULT_FRAME_DURATION: f32 = 0.1;
+
+/// What a clip does after its last frame.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PlayMode {
+    /// Starts over from the first frame.
+    Loop,
+    /// Plays backwards to the first frame, then forwards again.
+    PingPong,
+    /// Stops on the last frame.
+    Once,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AnimationFrame {
+    /// Index of the frame's region in the atlas.
+    pub region: usize,
+    /// Seconds the frame is shown for, at speed 1.
+    pub duration: f32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AnimationClip {
+    pub frames: Vec<AnimationFrame>,
+    pub mode: PlayMode,
+}
+
+impl AnimationClip {
+    pub fn new(frames: Vec<AnimationFrame>, mode: PlayMode) -> AnimationClip {
+        AnimationClip { frames, mode }
+    }
+
+    /// Clip showing the named atlas regions in order, each for `duration`
+    /// seconds. None if a name is not in the atlas.
+    pub fn from_names(
+        atlas: &TextureAtlas,
+        names: &[&str],
+        duration: f32,
+        mode: PlayMode,
+    ) -> Option<AnimationClip> {
+        let frames = names
+            .iter()
+            .map(|name| {
+                Some(AnimationFrame {
+                    region: atlas.index(name)?,
+                    duration,
+                })
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        Some(AnimationClip::new(frames, mode))
+    }
+
+    /// Seconds one pass through the frames takes, at speed 1.
+    pub fn duration(&self) -> f32 {
+        self.frames.iter().map(|frame| frame.duration).sum()
+    }
+}
+
+/// An atlas together with named clips over its regions. Shared between the
+/// AnimatedSprites showing it.
+pub struct SpriteSheet {
+    atlas: Arc<TextureAtlas>,
+    clips: HashMap<String, AnimationClip>,
+}
+
+impl SpriteSheet {
+    pub fn new(atlas: Arc<TextureAtlas>) -> SpriteSheet {
+        SpriteSheet {
+            atlas,
+            clips: HashMap::new(),
+        }
+    }
+
+    /// Loads a sprite sheet exported by Aseprite as JSON, with a clip per
+    /// tag and the frame durations set in Aseprite.
+    /// * "reverse" tags play their frames backwards and "pingpong" tags
+    ///   ping-pong.
+    /// * Tags with a repeat count play that many times and stop, a ping-pong
+    ///   tag going there and back each time.
+    /// * A sheet without tags gets a single looping clip named "default" over
+    ///   every frame.
+    pub fn load_aseprite(renderer: &Renderer, path: &str) -> Result<SpriteSheet, AtlasError> {
+        let sheet = SheetJson::load(path)?;
+        let atlas = TextureAtlas::from_sheet(renderer, path, &sheet)?;
+
+        let frames = sheet
+            .frames
+            .0
+            .iter()
+            .enumerate()
+            .map(|(region, (_, frame))| AnimationFrame {
+                region,
+                duration: frame
+                    .duration
+                    .map_or(DEFAULT_FRAME_DURATION, |ms| ms as f32 / 1000.0),
+            })
+            .collect::<Vec<_>>();
+
+        let mut sprite_sheet = SpriteSheet::new(Arc::new(atlas));
+
+        for tag in &sheet.meta.frame_tags {
+            sprite_sheet.add_clip(&tag.name, tag_clip(tag, &frames)?);
+        }
+
+        if sheet.meta.frame_tags.is_empty() {
+            sprite_sheet.add_clip("default", AnimationClip::new(frames, PlayMode::Loop));
+        }
+
+        Ok(sprite_sheet)
+    }
+
+    /// Adds a clip, replacing any clip of the same name.
+    pub fn add_clip(&mut self, name: &str, clip: AnimationClip) {
+        self.clips.insert(name.to_string(), clip);
+    }
+
+    pub fn clip(&self, name: &str) -> Option<&AnimationClip> {
+        self.clips.get(name)
+    }
+
+    pub fn clip_names(&self) -> impl Iterator<Item = &str> {
+        self.clips.keys().map(|name| name.as_str())
+    }
+
+    pub fn atlas(&self) -> &Arc<TextureAtlas> {
+        &self.atlas
+    }
+}
+
+/// Clip for an Aseprite tag over the sheet's frames.
+fn tag_clip(tag: &SheetTag, frames: &[AnimationFrame]) -> Result<AnimationClip, AtlasError> {
+    if tag.from > tag.to || tag.to >= frames.len() {
+        return Err(AtlasError::Unsupported(format!(
+            "tag {} covers frames {} to {} of {}",
+            tag.name,
+            tag.from,
+            tag.to,
+            frames.len()
+        )));
+    }
+
+    let mut clip_frames = frames[tag.from..=tag.to].to_vec();
+
+    let mode = match tag.direction.as_str() {
+        "" | "forward" => PlayMode::Loop,
+        "reverse" => {
+            clip_frames.reverse();
+            PlayMode::Loop
+        }
+        "pingpong" => PlayMode::PingPong,
+        "pingpong_reverse" => {
+            clip_frames.reverse();
+            PlayMode::PingPong
+        }
+        direction => {
+            return Err(AtlasError::Unsupported(format!(
+                "tag {} has direction {}",
+                tag.name, direction
+            )))
+        }
+    };
+
+    // a tag played a set number of times becomes one pass over every frame
+    // it shows, a ping-pong pass going there and back
+    let repeat = tag
+        .repeat
+        .as_deref()
+        .and_then(|repeat| repeat.trim().parse::<usize>().ok())
+        .filter(|&repeat| repeat > 0);
+
+    let clip = match (repeat, mode) {
+        (None, mode) => AnimationClip::new(clip_frames, mode),
+        (Some(repeat), PlayMode::PingPong) if clip_frames.len() > 1 => {
+            let last = clip_frames.len() - 1;
+            let round_trip = clip_frames
+                .iter()
+                .chain(clip_frames[1..last].iter().rev())
+                .copied()
+                .collect::<Vec<_>>();
+
+            let mut frames = round_trip.repeat(repeat);
+            frames.push(clip_frames[0]);
+            AnimationClip::new(frames, PlayMode::Once)
+        }
+        (Some(repeat), _) => AnimationClip::new(clip_frames.repeat(repeat), PlayMode::Once),
+    };
+
+    Ok(clip)
+}
+
+/// Something that happened while an AnimatedSprite was updated.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AnimationEvent {
+    /// The clip moved on to the frame with this index in the clip.
+    Frame(usize),
+    /// A looping clip started over, or a ping-pong clip got back to its
+    /// first frame.
+    Looped,
+    /// A clip that plays once reached the end of its last frame.
+    Finished,
+}
+
+/// Sprite flipping through the clips of a SpriteSheet. Draw it like any
+/// Renderable, or add it to an entity to have the application advance and
+/// draw it.
+/// * Update it with `update` once per fixed update. Entities' sprites are
+///   updated before `Game::update`, and their events are in the
+///   `AnimationEvents` resource.
+pub struct AnimatedSprite {
+    sheet: Arc<SpriteSheet>,
+    clip: Option<String>,
+    frame: usize,
+    elapsed: f32,
+    backwards: bool,
+    finished: bool,
+    /// Multiplies time passing: 2 plays twice as fast, 0 holds the frame.
+    pub speed: f32,
+    pub paused: bool,
+    /// Width and height in world units.
+    pub size: [f32; 2],
+    /// Model matrix when drawn as a Renderable. Entities use their Transform
+    /// instead.
+    pub matrix: TMat4<f32>,
+    pub layer: i32,
+}
+
+impl AnimatedSprite {
+    /// Sprite showing the first region of the sheet's atlas until a clip is
+    /// played.
+    pub fn new(sheet: Arc<SpriteSheet>, size: [f32; 2]) -> AnimatedSprite {
+        AnimatedSprite {
+            sheet,
+            clip: None,
+            frame: 0,
+            elapsed: 0.0,
+            backwards: false,
+            finished: false,
+            speed: 1.0,
+            paused: false,
+            size,
+            matrix: glm::identity(),
+            layer: 0,
+        }
+    }
+
+    /// Plays the named clip from its first frame, unless it is already
+    /// playing, so it can be called every update. Returns false if the sheet
+    /// has no such clip.
+    pub fn play(&mut self, clip: &str) -> bool {
+        if self.sheet.clip(clip).is_none() {
+            return false;
+        }
+
+        if self.clip.as_deref() != Some(clip) || self.finished {
+            self.clip = Some(clip.to_string());
+            self.restart();
+        }
+
+        true
+    }
+
+    /// Goes back to the first frame of the current clip.
+    pub fn restart(&mut self) {
+        self.frame = 0;
+        self.elapsed = 0.0;
+        self.backwards = false;
+        self.finished = false;
+    }
+
+    pub fn clip(&self) -> Option<&str> {
+        self.clip.as_deref()
+    }
+
+    /// Index of the shown frame in the current clip.
+    pub fn frame(&self) -> usize {
+        self.frame
+    }
+
+    /// Whether a clip that plays once has ended.
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    pub fn sheet(&self) -> &Arc<SpriteSheet> {
+        &self.sheet
+    }
+
+    /// Index of the shown region in the sheet's atlas.
+    pub fn region(&self) -> usize {
+        self.current_clip()
+            .and_then(|clip| clip.frames.get(self.frame))
+            .map_or(0, |frame| frame.region)
+    }
+
+    fn current_clip(&self) -> Option<&AnimationClip> {
+        self.sheet.clip(self.clip.as_deref()?)
+    }
+
+    /// Advances the clip by `dt` seconds, times the speed, and returns what
+    /// happened in order. Long steps can pass several frames.
+    pub fn update(&mut self, dt: f32) -> Vec<AnimationEvent> {
+        let mut events = Vec::new();
+        if self.paused || self.finished {
+            return events;
+        }
+
+        let sheet = self.sheet.clone();
+        let clip = match self.clip.as_deref().and_then(|name| sheet.clip(name)) {
+            Some(clip) if !clip.frames.is_empty() => clip,
+            _ => return events,
+        };
+        let last = clip.frames.len() - 1;
+
+        self.elapsed += dt * self.speed.max(0.0);
+
+        loop {
+            let duration = clip.frames[self.frame].duration.max(MIN_FRAME_DURATION);
+            if self.elapsed < duration {
+                break;
+            }
+            self.elapsed -= duration;
+
+            match clip.mode {
+                PlayMode::Once if self.frame == last => {
+                    self.finished = true;
+                    self.elapsed = 0.0;
+                    events.push(AnimationEvent::Finished);
+                    break;
+                }
+                PlayMode::Loop if self.frame == last => {
+                    self.frame = 0;
+                    events.push(AnimationEvent::Looped);
+                }
+                PlayMode::PingPong if last == 0 => {
+                    events.push(AnimationEvent::Looped);
+                }
+                PlayMode::PingPong if self.backwards => {
+                    self.frame -= 1;
+                    if self.frame == 0 {
+                        self.backwards = false;
+                        events.push(AnimationEvent::Looped);
+                    }
+                }
+                PlayMode::PingPong if self.frame == last => {
+                    self.frame -= 1;
+                    self.backwards = self.frame > 0;
+                    if self.frame == 0 {
+                        events.push(AnimationEvent::Looped);
+                    }
+                }
+                _ => self.frame += 1,
+            }
+
+            events.push(AnimationEvent::Frame(self.frame));
+        }
+
+        events
+    }
+}
+
+impl Renderable for AnimatedSprite {
+    fn vertices(&self) -> Vec<Vertex2D> {
+        let uv = self
+            .sheet
+            .atlas
+            .region_at(self.region())
+            .map_or([[0.0, 0.0], [1.0, 1.0]], |region| region.uv);
+
+        Quad::rect(self.size, uv).into()
+    }
+
+    fn matrix(&self) -> TMat4<f32> {
+        self.matrix
+    }
+
+    fn layer(&self) -> i32 {
+        self.layer
+    }
+
+    fn texture(&self) -> Arc<Texture> {
+        let atlas = &self.sheet.atlas;
+
+        match atlas.region_at(self.region()) {
+            Some(region) => atlas.texture(region),
+            None => atlas.pages()[0].clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frames() -> Vec<AnimationFrame> {
+        (0..5)
+            .map(|region| AnimationFrame {
+                region,
+                duration: 0.1,
+            })
+            .collect()
+    }
+
+    fn tag(direction: &str, repeat: Option<&str>) -> SheetTag {
+        SheetTag {
+            name: "walk".to_string(),
+            from: 1,
+            to: 3,
+            direction: direction.to_string(),
+            repeat: repeat.map(str::to_string),
+        }
+    }
+
+    fn regions(clip: &AnimationClip) -> Vec<usize> {
+        clip.frames.iter().map(|frame| frame.region).collect()
+    }
+
+    #[test]
+    fn tags_without_repeat_keep_playing() {
+        let clip = tag_clip(&tag("forward", None), &frames()).unwrap();
+        assert_eq!(regions(&clip), [1, 2, 3]);
+        assert_eq!(clip.mode, PlayMode::Loop);
+
+        let clip = tag_clip(&tag("reverse", None), &frames()).unwrap();
+        assert_eq!(regions(&clip), [3, 2, 1]);
+        assert_eq!(clip.mode, PlayMode::Loop);
+
+        let clip = tag_clip(&tag("pingpong_reverse", None), &frames()).unwrap();
+        assert_eq!(regions(&clip), [3, 2, 1]);
+        assert_eq!(clip.mode, PlayMode::PingPong);
+    }
+
+    #[test]
+    fn repeated_tags_play_every_pass_once() {
+        let clip = tag_clip(&tag("forward", Some("2")), &frames()).unwrap();
+        assert_eq!(regions(&clip), [1, 2, 3, 1, 2, 3]);
+        assert_eq!(clip.mode, PlayMode::Once);
+
+        let clip = tag_clip(&tag("reverse", Some("1")), &frames()).unwrap();
+        assert_eq!(regions(&clip), [3, 2, 1]);
+        assert_eq!(clip.mode, PlayMode::Once);
+    }
+
+    #[test]
+    fn repeated_ping_pong_tags_go_there_and_back() {
+        let clip = tag_clip(&tag("pingpong", Some("1")), &frames()).unwrap();
+        assert_eq!(regions(&clip), [1, 2, 3, 2, 1]);
+        assert_eq!(clip.mode, PlayMode::Once);
+
+        let clip = tag_clip(&tag("pingpong_reverse", Some("2")), &frames()).unwrap();
+        assert_eq!(regions(&clip), [3, 2, 1, 2, 3, 2, 1, 2, 3]);
+        assert_eq!(clip.mode, PlayMode::Once);
+
+        let single = SheetTag {
+            to: 1,
+            ..tag("pingpong", Some("2"))
+        };
+        assert_eq!(regions(&tag_clip(&single, &frames()).unwrap()), [1, 1]);
+    }
+
+    #[test]
+    fn refuses_tags_outside_the_sheet() {
+        let tag = SheetTag {
+            to: 5,
+            ..tag("forward", None)
+        };
+        assert!(matches!(
+            tag_clip(&tag, &frames()),
+            Err(AtlasError::Unsupported(_))
+        ));
+    }
+}
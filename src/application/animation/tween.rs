@@ -0,0 +1,553 @@
+use nalgebra_glm::TMat4;
+
+use super::easing::Ease;
+use crate::application::ecs::components::{PointLight, Transform};
+use crate::application::ecs::{Entity, World};
+use crate::application::renderer::Renderer;
+use crate::application::scene::Transform2D;
+
+/// A value that can be blended towards another one.
+/// * `t` can leave 0 to 1 with overshooting easing curves, so values should
+///   extrapolate.
+pub trait Tweenable: Copy + 'static {
+    fn tween(&self, to: &Self, t: f32) -> Self;
+}
+
+impl Tweenable for f32 {
+    fn tween(&self, to: &f32, t: f32) -> f32 {
+        self + (to - self) * t
+    }
+}
+
+impl<const N: usize> Tweenable for [f32; N] {
+    fn tween(&self, to: &[f32; N], t: f32) -> [f32; N] {
+        let mut value = *self;
+        for (v, to) in value.iter_mut().zip(to) {
+            *v = v.tween(to, t);
+        }
+        value
+    }
+}
+
+/// Rotation takes the shorter way round, so tweening a spin of half a turn
+/// or more needs several steps.
+impl Tweenable for Transform2D {
+    fn tween(&self, to: &Transform2D, t: f32) -> Transform2D {
+        self.lerp(to, t)
+    }
+}
+
+/// Blends matrices element by element. Fine for translation and scale, but
+/// rotations shrink halfway; tween a Transform2D and take its matrix to
+/// rotate.
+impl Tweenable for TMat4<f32> {
+    fn tween(&self, to: &TMat4<f32>, t: f32) -> TMat4<f32> {
+        self + (to - self) * t
+    }
+}
+
+/// Applies an eased progress to a target.
+type Setter<C> = Box<dyn FnMut(&mut C, f32)>;
+
+type Callback<C> = Box<dyn FnOnce(&mut C)>;
+
+/// Something that plays out over time, changing a target of type `C`.
+pub trait Animation<C> {
+    /// Advances by `dt` seconds. Returns the part of `dt` left over once
+    /// finished, so what follows in a sequence starts on time.
+    fn advance(&mut self, target: &mut C, dt: f32) -> f32;
+
+    fn is_finished(&self) -> bool;
+}
+
+/// Moves a value from one point to another along an easing curve, handing
+/// each step's value to a setter.
+pub struct Tween<C> {
+    duration: f32,
+    elapsed: f32,
+    ease: Ease,
+    apply: Setter<C>,
+    on_complete: Option<Callback<C>>,
+    finished: bool,
+}
+
+impl<C> Tween<C> {
+    /// Tween from `from` to `to` over `duration` seconds, passing the target
+    /// and each value to `apply`, e.g.
+    /// `Tween::new(0.0, 2.0, 1.0, Ease::QuadOut, |light: &mut PointLight, v| light.intensity = v)`.
+    pub fn new<T: Tweenable>(
+        from: T,
+        to: T,
+        duration: f32,
+        ease: Ease,
+        mut apply: impl FnMut(&mut C, T) + 'static,
+    ) -> Tween<C> {
+        Tween {
+            duration,
+            elapsed: 0.0,
+            ease,
+            apply: Box::new(move |target, t| apply(target, from.tween(&to, t))),
+            on_complete: None,
+            finished: false,
+        }
+    }
+
+    /// Calls `callback` once the tween reaches its end value.
+    pub fn on_complete(mut self, callback: impl FnOnce(&mut C) + 'static) -> Tween<C> {
+        self.on_complete = Some(Box::new(callback));
+        self
+    }
+
+    /// Progress between 0 and 1, before easing.
+    pub fn progress(&self) -> f32 {
+        match self.duration > 0.0 {
+            true => (self.elapsed / self.duration).min(1.0),
+            false => 1.0,
+        }
+    }
+}
+
+impl<C> Animation<C> for Tween<C> {
+    fn advance(&mut self, target: &mut C, dt: f32) -> f32 {
+        if self.finished {
+            return dt;
+        }
+
+        self.elapsed += dt;
+        let t = self.ease.apply(self.progress());
+        (self.apply)(target, t);
+
+        if self.elapsed < self.duration {
+            return 0.0;
+        }
+
+        self.finished = true;
+        if let Some(callback) = self.on_complete.take() {
+            callback(target);
+        }
+
+        self.elapsed - self.duration.max(0.0)
+    }
+
+    fn is_finished(&self) -> bool {
+        self.finished
+    }
+}
+
+impl Tween<Renderer> {
+    /// Fades the ambient light between two colors and intensities.
+    pub fn ambient(
+        from: ([f32; 3], f32),
+        to: ([f32; 3], f32),
+        duration: f32,
+        ease: Ease,
+    ) -> Tween<Renderer> {
+        let pack = |(color, intensity): ([f32; 3], f32)| [color[0], color[1], color[2], intensity];
+
+        Tween::new(
+            pack(from),
+            pack(to),
+            duration,
+            ease,
+            |renderer: &mut Renderer, [r, g, b, intensity]: [f32; 4]| {
+                renderer.set_ambient([r, g, b], intensity)
+            },
+        )
+    }
+}
+
+impl Tween<PointLight> {
+    pub fn intensity(from: f32, to: f32, duration: f32, ease: Ease) -> Tween<PointLight> {
+        Tween::new(from, to, duration, ease, |light: &mut PointLight, v| {
+            light.intensity = v
+        })
+    }
+}
+
+impl Tween<World> {
+    /// Moves an entity's Transform. Does nothing once the entity is gone.
+    pub fn transform(
+        entity: Entity,
+        from: Transform,
+        to: Transform,
+        duration: f32,
+        ease: Ease,
+    ) -> Tween<World> {
+        Tween::new(from, to, duration, ease, move |world: &mut World, v| {
+            if let Some(transform) = world.get_mut::<Transform>(entity) {
+                *transform = v;
+            }
+        })
+    }
+
+    /// Changes the intensity of an entity's PointLight. Does nothing once the
+    /// entity is gone.
+    pub fn point_light_intensity(
+        entity: Entity,
+        from: f32,
+        to: f32,
+        duration: f32,
+        ease: Ease,
+    ) -> Tween<World> {
+        Tween::new(from, to, duration, ease, move |world: &mut World, v| {
+            if let Some(light) = world.get_mut::<PointLight>(entity) {
+                light.intensity = v;
+            }
+        })
+    }
+}
+
+/// Waits, e.g. between the steps of a sequence.
+pub struct Delay {
+    remaining: f32,
+}
+
+impl Delay {
+    pub fn new(duration: f32) -> Delay {
+        Delay {
+            remaining: duration,
+        }
+    }
+}
+
+impl<C> Animation<C> for Delay {
+    fn advance(&mut self, _target: &mut C, dt: f32) -> f32 {
+        self.remaining -= dt;
+        (-self.remaining).max(0.0)
+    }
+
+    fn is_finished(&self) -> bool {
+        self.remaining <= 0.0
+    }
+}
+
+/// Calls a function once, taking no time.
+pub struct Call<C> {
+    callback: Option<Callback<C>>,
+}
+
+impl<C> Call<C> {
+    pub fn new(callback: impl FnOnce(&mut C) + 'static) -> Call<C> {
+        Call {
+            callback: Some(Box::new(callback)),
+        }
+    }
+}
+
+impl<C> Animation<C> for Call<C> {
+    fn advance(&mut self, target: &mut C, dt: f32) -> f32 {
+        if let Some(callback) = self.callback.take() {
+            callback(target);
+        }
+        dt
+    }
+
+    fn is_finished(&self) -> bool {
+        self.callback.is_none()
+    }
+}
+
+/// Plays animations one after the other.
+pub struct Sequence<C> {
+    steps: Vec<Box<dyn Animation<C>>>,
+    current: usize,
+}
+
+impl<C: 'static> Default for Sequence<C> {
+    fn default() -> Sequence<C> {
+        Sequence::new()
+    }
+}
+
+impl<C: 'static> Sequence<C> {
+    pub fn new() -> Sequence<C> {
+        Sequence {
+            steps: Vec::new(),
+            current: 0,
+        }
+    }
+
+    pub fn then(mut self, step: impl Animation<C> + 'static) -> Sequence<C> {
+        self.steps.push(Box::new(step));
+        self
+    }
+
+    pub fn then_wait(self, duration: f32) -> Sequence<C> {
+        self.then(Delay::new(duration))
+    }
+
+    pub fn then_call(self, callback: impl FnOnce(&mut C) + 'static) -> Sequence<C> {
+        self.then(Call::new(callback))
+    }
+}
+
+impl<C> Animation<C> for Sequence<C> {
+    fn advance(&mut self, target: &mut C, mut dt: f32) -> f32 {
+        while let Some(step) = self.steps.get_mut(self.current) {
+            dt = step.advance(target, dt);
+            if !step.is_finished() {
+                return 0.0;
+            }
+            self.current += 1;
+        }
+        dt
+    }
+
+    fn is_finished(&self) -> bool {
+        self.current >= self.steps.len()
+    }
+}
+
+/// Plays animations at the same time, finishing with the longest.
+pub struct Parallel<C> {
+    animations: Vec<Box<dyn Animation<C>>>,
+}
+
+impl<C: 'static> Default for Parallel<C> {
+    fn default() -> Parallel<C> {
+        Parallel::new()
+    }
+}
+
+impl<C: 'static> Parallel<C> {
+    pub fn new() -> Parallel<C> {
+        Parallel {
+            animations: Vec::new(),
+        }
+    }
+
+    pub fn with(mut self, animation: impl Animation<C> + 'static) -> Parallel<C> {
+        self.animations.push(Box::new(animation));
+        self
+    }
+}
+
+impl<C> Animation<C> for Parallel<C> {
+    fn advance(&mut self, target: &mut C, dt: f32) -> f32 {
+        // what is left over is what the last one to finish left over
+        let mut left = dt;
+        for animation in self.animations.iter_mut() {
+            if !animation.is_finished() {
+                left = left.min(animation.advance(target, dt));
+            }
+        }
+
+        match self.is_finished() {
+            true => left,
+            false => 0.0,
+        }
+    }
+
+    fn is_finished(&self) -> bool {
+        self.animations
+            .iter()
+            .all(|animation| animation.is_finished())
+    }
+}
+
+/// Handle to an animation playing in a Tweens.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TweenId(usize);
+
+/// Animations playing on targets of type `C`, dropped once finished.
+/// * The application keeps a `Tweens<World>` resource and advances it before
+///   each fixed update. Animations of other targets, e.g. a `Tweens<Renderer>`
+///   for the ambient light, are advanced by whoever owns them.
+/// * The `Tweens<World>` resource is taken out of the World while it runs, so
+///   its callbacks can not add to it. Chain with a Sequence instead.
+pub struct Tweens<C> {
+    animations: Vec<(TweenId, Box<dyn Animation<C>>)>,
+    next_id: usize,
+}
+
+impl<C> Default for Tweens<C> {
+    fn default() -> Tweens<C> {
+        Tweens {
+            animations: Vec::new(),
+            next_id: 0,
+        }
+    }
+}
+
+impl<C> Tweens<C> {
+    pub fn new() -> Tweens<C> {
+        Tweens::default()
+    }
+
+    pub fn add(&mut self, animation: impl Animation<C> + 'static) -> TweenId {
+        let id = TweenId(self.next_id);
+        self.next_id += 1;
+        self.animations.push((id, Box::new(animation)));
+        id
+    }
+
+    /// Stops an animation where it is, without completion callbacks.
+    /// Returns false if it already finished.
+    pub fn cancel(&mut self, id: TweenId) -> bool {
+        let count = self.animations.len();
+        self.animations.retain(|(other, _)| *other != id);
+        self.animations.len() != count
+    }
+
+    pub fn is_playing(&self, id: TweenId) -> bool {
+        self.animations.iter().any(|(other, _)| *other == id)
+    }
+
+    pub fn clear(&mut self) {
+        self.animations.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.animations.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.animations.is_empty()
+    }
+
+    /// Advances every animation by `dt` seconds, in the order they were
+    /// added.
+    pub fn update(&mut self, target: &mut C, dt: f32) {
+        for (_, animation) in self.animations.iter_mut() {
+            animation.advance(target, dt);
+        }
+        self.animations
+            .retain(|(_, animation)| !animation.is_finished());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A target recording the values and callbacks it got.
+    #[derive(Default)]
+    struct Log {
+        value: f32,
+        events: Vec<&'static str>,
+    }
+
+    fn tween(duration: f32) -> Tween<Log> {
+        Tween::new(0.0, 10.0, duration, Ease::Linear, |log: &mut Log, v| {
+            log.value = v
+        })
+    }
+
+    fn assert_close(a: f32, b: f32) {
+        assert!((a - b).abs() < 1e-5, "{} is not {}", a, b);
+    }
+
+    #[test]
+    fn tween_returns_leftover_time_and_completes_once() {
+        let mut log = Log::default();
+        let mut tween = tween(1.0).on_complete(|log: &mut Log| log.events.push("done"));
+
+        assert_eq!(tween.advance(&mut log, 0.25), 0.0);
+        assert_close(log.value, 2.5);
+        assert!(!tween.is_finished());
+
+        assert_close(tween.advance(&mut log, 1.0), 0.25);
+        assert_eq!(log.value, 10.0);
+        assert!(tween.is_finished());
+
+        // a finished tween hands back all of dt and calls nothing again
+        assert_eq!(tween.advance(&mut log, 0.5), 0.5);
+        assert_eq!(log.events, ["done"]);
+    }
+
+    #[test]
+    fn zero_length_tween_finishes_at_once() {
+        let mut log = Log::default();
+        let mut tween = tween(0.0).on_complete(|log: &mut Log| log.events.push("done"));
+
+        assert_eq!(tween.advance(&mut log, 0.0), 0.0);
+        assert_eq!(log.value, 10.0);
+        assert!(tween.is_finished());
+
+        assert_eq!(tween.advance(&mut log, 0.1), 0.1);
+        assert_eq!(log.events, ["done"]);
+    }
+
+    #[test]
+    fn sequence_carries_leftover_time_into_the_next_step() {
+        let mut log = Log::default();
+        let mut sequence = Sequence::new()
+            .then(tween(1.0).on_complete(|log: &mut Log| log.events.push("tweened")))
+            .then_wait(0.5)
+            .then_call(|log: &mut Log| log.events.push("called"))
+            .then(tween(1.0));
+
+        // through the first tween and the wait, a quarter into the second
+        assert_eq!(sequence.advance(&mut log, 1.75), 0.0);
+        assert_eq!(log.events, ["tweened", "called"]);
+        assert_close(log.value, 2.5);
+        assert!(!sequence.is_finished());
+
+        assert_close(sequence.advance(&mut log, 1.0), 0.25);
+        assert!(sequence.is_finished());
+    }
+
+    #[test]
+    fn sequence_stops_at_an_unfinished_wait() {
+        let mut log = Log::default();
+        let mut sequence = Sequence::new()
+            .then_wait(1.0)
+            .then_call(|log: &mut Log| log.events.push("called"));
+
+        sequence.advance(&mut log, 0.5);
+        assert!(log.events.is_empty());
+
+        assert_close(sequence.advance(&mut log, 0.75), 0.25);
+        assert_eq!(log.events, ["called"]);
+    }
+
+    #[test]
+    fn parallel_finishes_with_its_longest_child() {
+        let mut log = Log::default();
+        let mut parallel = Parallel::new()
+            .with(tween(1.0).on_complete(|log: &mut Log| log.events.push("short")))
+            .with(Delay::new(2.0));
+
+        assert_eq!(parallel.advance(&mut log, 1.5), 0.0);
+        assert_eq!(log.events, ["short"]);
+        assert!(!parallel.is_finished());
+
+        assert_close(parallel.advance(&mut log, 1.0), 0.5);
+        assert!(parallel.is_finished());
+        assert_eq!(log.events, ["short"]);
+    }
+
+    #[test]
+    fn cancelled_animations_skip_their_callbacks() {
+        let mut log = Log::default();
+        let mut tweens = Tweens::new();
+        let cancelled =
+            tweens.add(tween(1.0).on_complete(|log: &mut Log| log.events.push("cancelled")));
+        let kept = tweens.add(tween(1.0).on_complete(|log: &mut Log| log.events.push("kept")));
+
+        tweens.update(&mut log, 0.5);
+        assert!(tweens.cancel(cancelled));
+        assert!(!tweens.cancel(cancelled));
+        assert!(!tweens.is_playing(cancelled));
+
+        tweens.update(&mut log, 1.0);
+        assert_eq!(log.events, ["kept"]);
+        assert!(!tweens.is_playing(kept));
+    }
+
+    #[test]
+    fn update_drops_finished_animations() {
+        let mut log = Log::default();
+        let mut tweens = Tweens::new();
+        let short = tweens.add(tween(1.0));
+        let long = tweens.add(tween(2.0));
+
+        tweens.update(&mut log, 1.0);
+        assert_eq!(tweens.len(), 1);
+        assert!(!tweens.is_playing(short));
+        assert!(tweens.is_playing(long));
+
+        tweens.update(&mut log, 1.0);
+        assert!(tweens.is_empty());
+    }
+}
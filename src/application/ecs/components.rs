@@ -0,0 +1,108 @@
+use nalgebra_glm::{self as glm, TMat4};
+
+use std::sync::Arc;
+
+use crate::application::renderer::renderables::quad::Quad;
+use crate::application::renderer::renderables::vertices::{ColorVertex2D, Vertex2D};
+use crate::application::renderer::renderables::{ColorRenderable, Renderable};
+use crate::application::renderer::target::Target;
+use crate::application::renderer::texture::Texture;
+
+pub use crate::application::animation::{AnimatedSprite, Skeleton};
+pub use crate::application::renderer::renderables::lights::{DirectionalLight, PointLight};
+pub use crate::application::scene::Transform2D as Transform;
+
+/// Transform as of the previous fixed update. Kept up to date by the
+/// application and blended with Transform when drawing, so motion looks
+/// smooth at any frame rate.
+/// * Set it equal to Transform to move an entity without interpolating, e.g.
+///   when teleporting.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PreviousTransform(pub Transform);
+
+/// Textured rectangle centered on its entity's Transform.
+pub struct Sprite {
+    pub texture: Arc<Texture>,
+    /// Width and height in world units.
+    pub size: [f32; 2],
+    /// Top left and bottom right texture coordinates, for sprites that use
+    /// part of a texture.
+    pub uv: [[f32; 2]; 2],
+    pub layer: i32,
+}
+
+impl Sprite {
+    /// Sprite showing the whole texture.
+    pub fn new(texture: Arc<Texture>, size: [f32; 2]) -> Sprite {
+        Sprite {
+            texture,
+            size,
+            uv: [[0.0, 0.0], [1.0, 1.0]],
+            layer: 0,
+        }
+    }
+}
+
+impl Renderable for Sprite {
+    fn vertices(&self) -> Vec<Vertex2D> {
+        Quad::rect(self.size, self.uv).into()
+    }
+
+    fn matrix(&self) -> TMat4<f32> {
+        glm::identity()
+    }
+
+    fn layer(&self) -> i32 {
+        self.layer
+    }
+
+    fn texture(&self) -> Arc<Texture> {
+        self.texture.clone()
+    }
+}
+
+/// Vertex colored triangles, positioned relative to their entity's Transform.
+pub struct ColorShape {
+    pub vertices: Vec<ColorVertex2D>,
+    pub layer: i32,
+}
+
+impl ColorRenderable for ColorShape {
+    fn vertices(&self) -> Vec<ColorVertex2D> {
+        self.vertices.clone()
+    }
+
+    fn matrix(&self) -> TMat4<f32> {
+        glm::identity()
+    }
+
+    fn layer(&self) -> i32 {
+        self.layer
+    }
+}
+
+/// Views the world from its entity's Transform position and rotation. The
+/// render system uses the first active camera it finds for each view of each
+/// window and render target.
+#[derive(Clone, Copy, Debug)]
+pub struct Camera {
+    pub zoom: f32,
+    pub active: bool,
+    /// Window or render target the camera renders to. None is the primary
+    /// window.
+    pub target: Option<Target>,
+    /// View of the target the camera renders, e.g. a player's part of a
+    /// split screen.
+    pub view: usize,
+}
+
+impl Default for Camera {
+    fn default() -> Camera {
+        Camera {
+            zoom: 1.0,
+            active: true,
+            target: None,
+            view: 0,
+        }
+    }
+}
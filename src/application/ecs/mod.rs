@@ -0,0 +1,287 @@
+pub mod components;
+pub mod systems;
+
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+
+/// Handle to an entity in a World. Handles of despawned entities are never
+/// reused, so a stale handle can not alias a newer entity.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Entity {
+    index: usize,
+    generation: u32,
+}
+
+/// Components of one type, indexed by entity.
+struct Storage<T> {
+    components: Vec<Option<(u32, T)>>,
+}
+
+impl<T> Storage<T> {
+    fn get(&self, entity: Entity) -> Option<&T> {
+        match self.components.get(entity.index) {
+            Some(Some((generation, component))) if *generation == entity.generation => {
+                Some(component)
+            }
+            _ => None,
+        }
+    }
+
+    fn get_mut(&mut self, entity: Entity) -> Option<&mut T> {
+        match self.components.get_mut(entity.index) {
+            Some(Some((generation, component))) if *generation == entity.generation => {
+                Some(component)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Lets the World clear components of despawned entities without knowing
+/// their types.
+trait AnyStorage {
+    fn clear(&mut self, index: usize);
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<T: 'static> AnyStorage for Storage<T> {
+    fn clear(&mut self, index: usize) {
+        if let Some(component) = self.components.get_mut(index) {
+            *component = None;
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// Entities, their components, and resources shared between systems.
+/// * Any `'static` type can be a component or a resource. An entity has at
+///   most one component of each type.
+#[derive(Default)]
+pub struct World {
+    generations: Vec<u32>,
+    alive: Vec<bool>,
+    free: Vec<usize>,
+    storages: HashMap<TypeId, Box<dyn AnyStorage>>,
+    resources: HashMap<TypeId, Box<dyn Any>>,
+}
+
+impl World {
+    pub fn new() -> World {
+        World::default()
+    }
+
+    /// Creates an entity without components.
+    pub fn spawn(&mut self) -> Entity {
+        match self.free.pop() {
+            Some(index) => {
+                self.alive[index] = true;
+                Entity {
+                    index,
+                    generation: self.generations[index],
+                }
+            }
+            None => {
+                self.generations.push(0);
+                self.alive.push(true);
+                Entity {
+                    index: self.generations.len() - 1,
+                    generation: 0,
+                }
+            }
+        }
+    }
+
+    /// Removes an entity and all of its components.
+    pub fn despawn(&mut self, entity: Entity) {
+        if !self.is_alive(entity) {
+            return;
+        }
+
+        for storage in self.storages.values_mut() {
+            storage.clear(entity.index);
+        }
+
+        self.alive[entity.index] = false;
+        self.generations[entity.index] += 1;
+        self.free.push(entity.index);
+    }
+
+    pub fn is_alive(&self, entity: Entity) -> bool {
+        self.alive.get(entity.index).copied().unwrap_or(false)
+            && self.generations[entity.index] == entity.generation
+    }
+
+    /// Returns every living entity.
+    pub fn entities(&self) -> Vec<Entity> {
+        self.alive
+            .iter()
+            .enumerate()
+            .filter(|(_, &alive)| alive)
+            .map(|(index, _)| Entity {
+                index,
+                generation: self.generations[index],
+            })
+            .collect()
+    }
+
+    /// Adds a component to an entity, replacing any existing component of the
+    /// same type.
+    /// * Does nothing if the entity has been despawned.
+    pub fn insert<T: 'static>(&mut self, entity: Entity, component: T) {
+        if !self.is_alive(entity) {
+            return;
+        }
+
+        let storage = self
+            .storages
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| {
+                Box::new(Storage::<T> {
+                    components: Vec::new(),
+                })
+            })
+            .as_any_mut()
+            .downcast_mut::<Storage<T>>()
+            .unwrap();
+
+        if storage.components.len() <= entity.index {
+            storage.components.resize_with(entity.index + 1, || None);
+        }
+        storage.components[entity.index] = Some((entity.generation, component));
+    }
+
+    /// Removes a component from an entity, returning it.
+    pub fn remove<T: 'static>(&mut self, entity: Entity) -> Option<T> {
+        let storage = self.storage_mut::<T>()?;
+        storage.get(entity)?;
+        storage.components[entity.index]
+            .take()
+            .map(|(_, component)| component)
+    }
+
+    pub fn get<T: 'static>(&self, entity: Entity) -> Option<&T> {
+        self.storage::<T>()?.get(entity)
+    }
+
+    pub fn get_mut<T: 'static>(&mut self, entity: Entity) -> Option<&mut T> {
+        self.storage_mut::<T>()?.get_mut(entity)
+    }
+
+    pub fn has<T: 'static>(&self, entity: Entity) -> bool {
+        self.get::<T>(entity).is_some()
+    }
+
+    /// Iterates over every entity with a component of type T.
+    pub fn query<T: 'static>(&self) -> impl Iterator<Item = (Entity, &T)> {
+        self.storage::<T>()
+            .into_iter()
+            .flat_map(|storage| storage.components.iter().enumerate())
+            .filter_map(|(index, component)| {
+                component.as_ref().map(|(generation, component)| {
+                    (
+                        Entity {
+                            index,
+                            generation: *generation,
+                        },
+                        component,
+                    )
+                })
+            })
+    }
+
+    /// Iterates over every entity with a component of type T, mutably.
+    pub fn query_mut<T: 'static>(&mut self) -> impl Iterator<Item = (Entity, &mut T)> {
+        self.storage_mut::<T>()
+            .into_iter()
+            .flat_map(|storage| storage.components.iter_mut().enumerate())
+            .filter_map(|(index, component)| {
+                component.as_mut().map(|(generation, component)| {
+                    (
+                        Entity {
+                            index,
+                            generation: *generation,
+                        },
+                        component,
+                    )
+                })
+            })
+    }
+
+    /// Returns the entities that have both a T and a U component, for systems
+    /// that read one and write the other.
+    pub fn with<T: 'static, U: 'static>(&self) -> Vec<Entity> {
+        self.query::<T>()
+            .map(|(entity, _)| entity)
+            .filter(|&entity| self.has::<U>(entity))
+            .collect()
+    }
+
+    /// Adds a resource: a value shared by all systems rather than owned by an
+    /// entity. Replaces any existing resource of the same type.
+    pub fn insert_resource<T: 'static>(&mut self, resource: T) {
+        self.resources.insert(TypeId::of::<T>(), Box::new(resource));
+    }
+
+    pub fn remove_resource<T: 'static>(&mut self) -> Option<T> {
+        self.resources
+            .remove(&TypeId::of::<T>())
+            .map(|resource| *resource.downcast::<T>().unwrap())
+    }
+
+    pub fn resource<T: 'static>(&self) -> Option<&T> {
+        self.resources.get(&TypeId::of::<T>())?.downcast_ref::<T>()
+    }
+
+    pub fn resource_mut<T: 'static>(&mut self) -> Option<&mut T> {
+        self.resources
+            .get_mut(&TypeId::of::<T>())?
+            .downcast_mut::<T>()
+    }
+
+    fn storage<T: 'static>(&self) -> Option<&Storage<T>> {
+        self.storages
+            .get(&TypeId::of::<T>())?
+            .as_any()
+            .downcast_ref::<Storage<T>>()
+    }
+
+    fn storage_mut<T: 'static>(&mut self) -> Option<&mut Storage<T>> {
+        self.storages
+            .get_mut(&TypeId::of::<T>())?
+            .as_any_mut()
+            .downcast_mut::<Storage<T>>()
+    }
+}
+
+type System = Box<dyn FnMut(&mut World)>;
+
+/// Game logic run on the World once per fixed update, so zero or more times
+/// per frame, in the order added.
+#[derive(Default)]
+pub struct Schedule {
+    systems: Vec<System>,
+}
+
+impl Schedule {
+    pub fn new() -> Schedule {
+        Schedule::default()
+    }
+
+    pub fn add_system(&mut self, system: impl FnMut(&mut World) + 'static) {
+        self.systems.push(Box::new(system));
+    }
+
+    pub fn run(&mut self, world: &mut World) {
+        for system in self.systems.iter_mut() {
+            system(world);
+        }
+    }
+}
@@ -0,0 +1,141 @@
+use nalgebra_glm::{self as glm, TMat4};
+
+use super::components::*;
+use super::{Entity, World};
+
+use crate::application::animation::{AnimationEvent, Tweens};
+use crate::application::renderer::camera::Camera2D;
+use crate::application::renderer::target::Target;
+use crate::application::renderer::Frame;
+use crate::application::scene::{world_directional_light, world_point_light};
+
+/// Transform of an entity blended from its previous one by `alpha`.
+fn interpolated_transform(world: &World, entity: Entity, alpha: f32) -> Option<Transform> {
+    let transform = world.get::<Transform>(entity)?;
+
+    Some(match world.get::<PreviousTransform>(entity) {
+        Some(PreviousTransform(previous)) => previous.lerp(transform, alpha),
+        None => *transform,
+    })
+}
+
+/// World matrix of an entity. Entities without a Transform sit at the origin.
+fn entity_matrix(world: &World, entity: Entity, alpha: f32) -> TMat4<f32> {
+    interpolated_transform(world, entity, alpha)
+        .map(|transform| transform.matrix())
+        .unwrap_or_else(glm::identity)
+}
+
+/// Records every Transform as the PreviousTransform. Run before each fixed
+/// update.
+pub fn store_previous_transforms(world: &mut World) {
+    let transforms = world
+        .query::<Transform>()
+        .map(|(entity, transform)| (entity, *transform))
+        .collect::<Vec<_>>();
+
+    for (entity, transform) in transforms {
+        world.insert(entity, PreviousTransform(transform));
+    }
+}
+
+/// Animation events of the last fixed update, by entity, in the order they
+/// happened. Replaced by every `animate_sprites` run.
+#[derive(Clone, Debug, Default)]
+pub struct AnimationEvents(pub Vec<(Entity, AnimationEvent)>);
+
+/// Advances every AnimatedSprite by `dt` seconds and stores what happened in
+/// the AnimationEvents resource. The application runs it before each fixed
+/// update.
+pub fn animate_sprites(world: &mut World, dt: f32) {
+    let mut events = Vec::new();
+
+    for (entity, sprite) in world.query_mut::<AnimatedSprite>() {
+        events.extend(sprite.update(dt).into_iter().map(|event| (entity, event)));
+    }
+
+    world.insert_resource(AnimationEvents(events));
+}
+
+/// Advances and poses every Skeleton by `dt` seconds, adding what happened to
+/// the AnimationEvents resource. The application runs it after
+/// `animate_sprites`.
+pub fn animate_skeletons(world: &mut World, dt: f32) {
+    let mut events = Vec::new();
+
+    for (entity, skeleton) in world.query_mut::<Skeleton>() {
+        events.extend(skeleton.update(dt).into_iter().map(|event| (entity, event)));
+    }
+
+    match world.resource_mut::<AnimationEvents>() {
+        Some(AnimationEvents(existing)) => existing.extend(events),
+        None => world.insert_resource(AnimationEvents(events)),
+    }
+}
+
+/// Advances the `Tweens<World>` resource by `dt` seconds. The application
+/// runs it before each fixed update.
+pub fn run_tweens(world: &mut World, dt: f32) {
+    if let Some(mut tweens) = world.remove_resource::<Tweens<World>>() {
+        tweens.update(world, dt);
+        world.insert_resource(tweens);
+    }
+}
+
+/// Feeds the World to a frame: sets the camera of the frame's view of its
+/// window or render target, then queues every Sprite, AnimatedSprite,
+/// Skeleton and ColorShape and collects every light. Transforms are
+/// interpolated by the frame's alpha.
+pub fn render_system(world: &World, frame: &mut Frame) {
+    let alpha = frame.alpha();
+    let target = frame.target();
+    let view = frame.view();
+    let primary = Target::Window(frame.renderer().primary_window());
+
+    let camera = world
+        .query::<Camera>()
+        .find(|(_, camera)| {
+            camera.active && camera.target.unwrap_or(primary) == target && camera.view == view
+        })
+        .map(|(entity, camera)| {
+            let transform = interpolated_transform(world, entity, alpha).unwrap_or_default();
+
+            Camera2D {
+                position: transform.position,
+                rotation: transform.rotation,
+                zoom: camera.zoom,
+            }
+        })
+        .unwrap_or_default();
+    frame.set_camera(camera);
+
+    for (entity, sprite) in world.query::<Sprite>() {
+        frame.draw_with_matrix(sprite, entity_matrix(world, entity, alpha));
+    }
+
+    for (entity, sprite) in world.query::<AnimatedSprite>() {
+        frame.draw_with_matrix(sprite, entity_matrix(world, entity, alpha));
+    }
+
+    for (entity, skeleton) in world.query::<Skeleton>() {
+        skeleton.draw_with_matrix(frame, entity_matrix(world, entity, alpha));
+    }
+
+    for (entity, shape) in world.query::<ColorShape>() {
+        frame.color_draw_with_matrix(shape, entity_matrix(world, entity, alpha));
+    }
+
+    for (entity, light) in world.query::<DirectionalLight>() {
+        frame.directional(world_directional_light(
+            &entity_matrix(world, entity, alpha),
+            light,
+        ));
+    }
+
+    for (entity, light) in world.query::<PointLight>() {
+        frame.point(world_point_light(
+            &entity_matrix(world, entity, alpha),
+            light,
+        ));
+    }
+}
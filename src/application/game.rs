@@ -0,0 +1,64 @@
+use winit::event::WindowEvent;
+use winit::event_loop::EventLoopWindowTarget;
+use winit::window::WindowId;
+
+use super::ecs::World;
+use super::input::Input;
+use super::renderer::{Frame, Renderer};
+use super::window::WindowConfig;
+
+/// Access to the engine from game callbacks.
+pub struct Context<'a> {
+    pub world: &'a mut World,
+    pub renderer: &'a mut Renderer,
+    pub(crate) event_loop: &'a EventLoopWindowTarget<()>,
+    pub(crate) exit_requested: &'a mut bool,
+}
+
+impl Context<'_> {
+    /// Returns the input state.
+    /// * Panics if the `Input` resource has been removed from the World.
+    pub fn input(&self) -> &Input {
+        self.world.resource::<Input>().unwrap()
+    }
+
+    /// Opens another window, e.g. a tool window of an editor. `draw` is
+    /// called for it every frame, and it closes when the user closes it.
+    pub fn create_window(&mut self, config: &WindowConfig) -> WindowId {
+        self.renderer.create_window(self.event_loop, config)
+    }
+
+    /// Closes the application once the current callback returns. `on_exit`
+    /// is still called.
+    pub fn exit(&mut self) {
+        *self.exit_requested = true;
+    }
+}
+
+/// User game logic, driven by `Application::run_game`. Every callback has an
+/// empty default, so a game only implements what it needs.
+/// * Game logic runs at a fixed rate: `update`, then the application's
+///   systems, zero or more times per frame. `draw` runs once per frame, after
+///   the render system has drawn the World's entities.
+pub trait Game {
+    /// Called once before the first frame, e.g. to load textures and spawn
+    /// entities.
+    fn init(&mut self, _context: &mut Context) {}
+
+    /// Called once per fixed update. `dt` is the timestep in seconds and is
+    /// the same every call, so the simulation is deterministic.
+    fn update(&mut self, _context: &mut Context, _dt: f32) {}
+
+    /// Called once per frame for each view of each render target and open
+    /// window to queue renderables and lights not owned by the World.
+    /// `Frame::target` and `Frame::view` tell them apart, and `Frame::alpha`
+    /// gives how far the frame is between the previous and current update.
+    fn draw(&mut self, _frame: &mut Frame) {}
+
+    /// Called for every window event, after the application has handled it.
+    /// `window` is the window the event was sent to.
+    fn on_event(&mut self, _context: &mut Context, _window: WindowId, _event: &WindowEvent) {}
+
+    /// Called once when the application is about to close.
+    fn on_exit(&mut self, _context: &mut Context) {}
+}
@@ -0,0 +1,108 @@
+use serde::{Deserialize, Serialize};
+
+use winit::event::{MouseButton, VirtualKeyCode};
+
+use std::collections::HashMap;
+use std::fmt;
+
+use super::gamepad::GamepadButton;
+
+/// Physical input an action can be bound to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Binding {
+    Key(VirtualKeyCode),
+    Mouse(MouseButton),
+    Gamepad(GamepadButton),
+}
+
+/// Named actions and the inputs that trigger them, so game code asks for
+/// "jump" instead of Space. Any one of an action's bindings triggers it.
+/// * Stored as JSON mapping action names to binding lists, e.g.
+///   `{"jump": [{"Key": "Space"}, {"Gamepad": "South"}]}`.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ActionMap {
+    actions: HashMap<String, Vec<Binding>>,
+}
+
+impl ActionMap {
+    pub fn new() -> ActionMap {
+        ActionMap::default()
+    }
+
+    /// Loads an action map from a JSON file.
+    pub fn load(path: &str) -> Result<ActionMap, ActionMapError> {
+        let json = std::fs::read_to_string(path)?;
+        ActionMap::from_json(&json)
+    }
+
+    /// Writes the action map to a JSON file, e.g. after the player rebinds.
+    pub fn save(&self, path: &str) -> Result<(), ActionMapError> {
+        std::fs::write(path, self.to_json()?)?;
+        Ok(())
+    }
+
+    pub fn from_json(json: &str) -> Result<ActionMap, ActionMapError> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    pub fn to_json(&self) -> Result<String, ActionMapError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Adds a binding to an action, creating the action if needed.
+    pub fn bind(&mut self, action: &str, binding: Binding) {
+        let bindings = self.actions.entry(action.to_string()).or_default();
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+    }
+
+    pub fn unbind(&mut self, action: &str, binding: Binding) {
+        if let Some(bindings) = self.actions.get_mut(action) {
+            bindings.retain(|&b| b != binding);
+        }
+    }
+
+    /// Removes every binding of an action.
+    pub fn clear(&mut self, action: &str) {
+        self.actions.remove(action);
+    }
+
+    pub fn bindings(&self, action: &str) -> &[Binding] {
+        self.actions.get(action).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    pub fn actions(&self) -> impl Iterator<Item = &str> {
+        self.actions.keys().map(String::as_str)
+    }
+}
+
+#[derive(Debug)]
+pub enum ActionMapError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+}
+
+impl fmt::Display for ActionMapError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ActionMapError::Io(err) => write!(f, "Failed to access action map: {}", err),
+            ActionMapError::Json(err) => write!(f, "Invalid action map: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for ActionMapError {}
+
+impl From<std::io::Error> for ActionMapError {
+    fn from(err: std::io::Error) -> ActionMapError {
+        ActionMapError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for ActionMapError {
+    fn from(err: serde_json::Error) -> ActionMapError {
+        ActionMapError::Json(err)
+    }
+}
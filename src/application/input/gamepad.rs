@@ -0,0 +1,137 @@
+use serde::{Deserialize, Serialize};
+
+use std::collections::HashMap;
+
+use super::Buttons;
+
+/// Buttons of a standard layout controller, named by position so bindings
+/// work the same on every brand. South is A on Xbox and Cross on PlayStation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum GamepadButton {
+    South,
+    East,
+    West,
+    North,
+    LeftBumper,
+    RightBumper,
+    LeftTrigger,
+    RightTrigger,
+    Select,
+    Start,
+    Mode,
+    LeftStick,
+    RightStick,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+}
+
+/// Analog inputs of a standard layout controller. Sticks range from -1 to 1
+/// with positive y up, triggers from 0 to 1.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum GamepadAxis {
+    LeftStickX,
+    LeftStickY,
+    RightStickX,
+    RightStickY,
+    LeftTrigger,
+    RightTrigger,
+}
+
+/// Identifies a connected controller. Backends keep the id of a controller
+/// stable for as long as it stays connected.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct GamepadId(pub usize);
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum GamepadEventKind {
+    Connected {
+        name: String,
+    },
+    Disconnected,
+    ButtonPressed(GamepadButton),
+    ButtonReleased(GamepadButton),
+    /// Raw axis value, before the dead zone is applied.
+    AxisChanged(GamepadAxis, f32),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct GamepadEvent {
+    pub id: GamepadId,
+    pub kind: GamepadEventKind,
+}
+
+/// Source of controller events, e.g. the platform's gamepad API or a mock.
+/// The application polls it once per frame and feeds the events to `Input`.
+pub trait GamepadBackend {
+    /// Returns the events that happened since the last poll, in order.
+    fn poll(&mut self) -> Vec<GamepadEvent>;
+}
+
+/// State of one connected controller.
+#[derive(Debug, Default)]
+pub struct GamepadState {
+    pub(super) name: String,
+    pub(super) buttons: Buttons<GamepadButton>,
+    pub(super) axes: HashMap<GamepadAxis, f32>,
+    pub(super) dead_zone: f32,
+}
+
+impl GamepadState {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn down(&self, button: GamepadButton) -> bool {
+        self.buttons.down.contains(&button)
+    }
+
+    pub fn pressed(&self, button: GamepadButton) -> bool {
+        self.buttons.pressed.contains(&button)
+    }
+
+    pub fn released(&self, button: GamepadButton) -> bool {
+        self.buttons.released.contains(&button)
+    }
+
+    /// Axis value with the dead zone applied. Values inside the dead zone
+    /// read as 0 and the rest is rescaled to still reach 1.
+    pub fn axis(&self, axis: GamepadAxis) -> f32 {
+        let value = self.axes.get(&axis).copied().unwrap_or(0.0);
+        apply_dead_zone(value, self.dead_zone)
+    }
+
+    /// Left stick as [x, y], with the dead zone applied to its length so
+    /// diagonals are not cut off.
+    pub fn left_stick(&self) -> [f32; 2] {
+        self.stick(GamepadAxis::LeftStickX, GamepadAxis::LeftStickY)
+    }
+
+    pub fn right_stick(&self) -> [f32; 2] {
+        self.stick(GamepadAxis::RightStickX, GamepadAxis::RightStickY)
+    }
+
+    fn stick(&self, x: GamepadAxis, y: GamepadAxis) -> [f32; 2] {
+        let x = self.axes.get(&x).copied().unwrap_or(0.0);
+        let y = self.axes.get(&y).copied().unwrap_or(0.0);
+
+        let length = (x * x + y * y).sqrt();
+        if length == 0.0 {
+            return [0.0, 0.0];
+        }
+
+        let scale = apply_dead_zone(length.min(1.0), self.dead_zone) / length;
+        [x * scale, y * scale]
+    }
+}
+
+/// Zeroes values within `dead_zone` of 0 and rescales the rest to the full
+/// range.
+pub fn apply_dead_zone(value: f32, dead_zone: f32) -> f32 {
+    if value.abs() <= dead_zone {
+        return 0.0;
+    }
+
+    value.signum() * (value.abs() - dead_zone) / (1.0 - dead_zone)
+}
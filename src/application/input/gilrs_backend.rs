@@ -0,0 +1,93 @@
+use gilrs::{Axis, Button, EventType, Gilrs};
+
+use super::gamepad::*;
+
+/// Gamepad backend using the platform's controller API through gilrs, which
+/// also maps known controllers to the standard layout.
+pub struct GilrsBackend {
+    gilrs: Gilrs,
+}
+
+impl GilrsBackend {
+    pub fn new() -> Result<GilrsBackend, gilrs::Error> {
+        Ok(GilrsBackend {
+            gilrs: Gilrs::new()?,
+        })
+    }
+}
+
+fn button(button: Button) -> Option<GamepadButton> {
+    Some(match button {
+        Button::South => GamepadButton::South,
+        Button::East => GamepadButton::East,
+        Button::West => GamepadButton::West,
+        Button::North => GamepadButton::North,
+        Button::LeftTrigger => GamepadButton::LeftBumper,
+        Button::RightTrigger => GamepadButton::RightBumper,
+        Button::LeftTrigger2 => GamepadButton::LeftTrigger,
+        Button::RightTrigger2 => GamepadButton::RightTrigger,
+        Button::Select => GamepadButton::Select,
+        Button::Start => GamepadButton::Start,
+        Button::Mode => GamepadButton::Mode,
+        Button::LeftThumb => GamepadButton::LeftStick,
+        Button::RightThumb => GamepadButton::RightStick,
+        Button::DPadUp => GamepadButton::DPadUp,
+        Button::DPadDown => GamepadButton::DPadDown,
+        Button::DPadLeft => GamepadButton::DPadLeft,
+        Button::DPadRight => GamepadButton::DPadRight,
+        _ => return None,
+    })
+}
+
+fn axis(axis: Axis) -> Option<GamepadAxis> {
+    Some(match axis {
+        Axis::LeftStickX => GamepadAxis::LeftStickX,
+        Axis::LeftStickY => GamepadAxis::LeftStickY,
+        Axis::RightStickX => GamepadAxis::RightStickX,
+        Axis::RightStickY => GamepadAxis::RightStickY,
+        Axis::LeftZ => GamepadAxis::LeftTrigger,
+        Axis::RightZ => GamepadAxis::RightTrigger,
+        _ => return None,
+    })
+}
+
+impl GamepadBackend for GilrsBackend {
+    fn poll(&mut self) -> Vec<GamepadEvent> {
+        let mut events = Vec::new();
+
+        while let Some(event) = self.gilrs.next_event() {
+            let id = GamepadId(event.id.into());
+
+            let kind = match event.event {
+                EventType::Connected => GamepadEventKind::Connected {
+                    name: self.gilrs.gamepad(event.id).name().to_string(),
+                },
+                EventType::Disconnected => GamepadEventKind::Disconnected,
+                EventType::ButtonPressed(b, _) => match button(b) {
+                    Some(b) => GamepadEventKind::ButtonPressed(b),
+                    None => continue,
+                },
+                EventType::ButtonReleased(b, _) => match button(b) {
+                    Some(b) => GamepadEventKind::ButtonReleased(b),
+                    None => continue,
+                },
+                // analog triggers report through button values
+                EventType::ButtonChanged(Button::LeftTrigger2, value, _) => {
+                    GamepadEventKind::AxisChanged(GamepadAxis::LeftTrigger, value)
+                }
+                EventType::ButtonChanged(Button::RightTrigger2, value, _) => {
+                    GamepadEventKind::AxisChanged(GamepadAxis::RightTrigger, value)
+                }
+                EventType::AxisChanged(a, value, _) => match axis(a) {
+                    Some(a) => GamepadEventKind::AxisChanged(a, value),
+                    None => continue,
+                },
+                _ => continue,
+            };
+
+            events.push(GamepadEvent { id, kind });
+        }
+
+        events
+    }
+}
@@ -0,0 +1,66 @@
+use std::sync::{Arc, Mutex};
+
+use super::gamepad::*;
+
+/// Gamepad backend driven by code instead of hardware, for tests and
+/// replays. Events sent through a `MockGamepads` handle come out of the next
+/// poll.
+#[derive(Default)]
+pub struct MockGamepadBackend {
+    events: Arc<Mutex<Vec<GamepadEvent>>>,
+}
+
+impl MockGamepadBackend {
+    /// Creates a backend and the handle that sends events to it.
+    pub fn new() -> (MockGamepadBackend, MockGamepads) {
+        let backend = MockGamepadBackend::default();
+        let handle = MockGamepads {
+            events: backend.events.clone(),
+        };
+
+        (backend, handle)
+    }
+}
+
+impl GamepadBackend for MockGamepadBackend {
+    fn poll(&mut self) -> Vec<GamepadEvent> {
+        std::mem::take(&mut *self.events.lock().unwrap())
+    }
+}
+
+/// Sends events to a `MockGamepadBackend`.
+#[derive(Clone)]
+pub struct MockGamepads {
+    events: Arc<Mutex<Vec<GamepadEvent>>>,
+}
+
+impl MockGamepads {
+    pub fn send(&self, id: GamepadId, kind: GamepadEventKind) {
+        self.events.lock().unwrap().push(GamepadEvent { id, kind });
+    }
+
+    pub fn connect(&self, id: GamepadId, name: &str) {
+        self.send(
+            id,
+            GamepadEventKind::Connected {
+                name: name.to_string(),
+            },
+        );
+    }
+
+    pub fn disconnect(&self, id: GamepadId) {
+        self.send(id, GamepadEventKind::Disconnected);
+    }
+
+    pub fn press(&self, id: GamepadId, button: GamepadButton) {
+        self.send(id, GamepadEventKind::ButtonPressed(button));
+    }
+
+    pub fn release(&self, id: GamepadId, button: GamepadButton) {
+        self.send(id, GamepadEventKind::ButtonReleased(button));
+    }
+
+    pub fn set_axis(&self, id: GamepadId, axis: GamepadAxis, value: f32) {
+        self.send(id, GamepadEventKind::AxisChanged(axis, value));
+    }
+}
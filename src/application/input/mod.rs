@@ -0,0 +1,723 @@
+pub mod action;
+pub mod gamepad;
+#[cfg(feature = "gilrs")]
+pub mod gilrs_backend;
+pub mod mock;
+pub use action::{ActionMap, ActionMapError, Binding};
+pub use gamepad::{
+    GamepadAxis, GamepadBackend, GamepadButton, GamepadEvent, GamepadEventKind, GamepadId,
+    GamepadState,
+};
+
+use winit::dpi::PhysicalPosition;
+use winit::event::{ElementState, MouseButton, MouseScrollDelta, VirtualKeyCode, WindowEvent};
+
+use std::collections::{BTreeMap, HashSet};
+use std::hash::Hash;
+
+/// Held, just pressed and just released state of a set of buttons.
+#[derive(Debug)]
+struct Buttons<T> {
+    down: HashSet<T>,
+    pressed: HashSet<T>,
+    released: HashSet<T>,
+}
+
+impl<T> Default for Buttons<T> {
+    fn default() -> Buttons<T> {
+        Buttons {
+            down: HashSet::new(),
+            pressed: HashSet::new(),
+            released: HashSet::new(),
+        }
+    }
+}
+
+impl<T: Copy + Eq + Hash> Buttons<T> {
+    fn press(&mut self, button: T) {
+        // key repeat sends presses for held keys, which are not new presses
+        if self.down.insert(button) {
+            self.pressed.insert(button);
+        }
+    }
+
+    fn release(&mut self, button: T) {
+        if self.down.remove(&button) {
+            self.released.insert(button);
+        }
+    }
+
+    fn release_all(&mut self) {
+        self.released.extend(self.down.drain());
+    }
+
+    fn end_update(&mut self) {
+        self.pressed.clear();
+        self.released.clear();
+    }
+}
+
+/// Keyboard, mouse and gamepad state, kept as a World resource and fed by the
+/// application's event loop.
+/// * Only the primary window's events reach it, so the cursor is always in
+///   the primary window's pixels. Other windows' events go to
+///   `Game::on_event`.
+/// * "Just pressed" and "just released" state, scroll, text input and
+///   gamepad events last for exactly one fixed update, so nothing is missed
+///   or seen twice no matter how many updates run in a frame.
+#[derive(Debug)]
+pub struct Input {
+    keys: Buttons<VirtualKeyCode>,
+    mouse_buttons: Buttons<MouseButton>,
+    gamepads: BTreeMap<GamepadId, GamepadState>,
+    gamepad_events: Vec<GamepadEvent>,
+    /// Gamepads removed at the end of the update, once their held buttons
+    /// were seen released.
+    disconnected: Vec<GamepadId>,
+    dead_zone: f32,
+    cursor_position: Option<PhysicalPosition<f64>>,
+    cursor_world: Option<[f32; 2]>,
+    scroll: [f32; 2],
+    text: String,
+    actions: ActionMap,
+}
+
+impl Default for Input {
+    fn default() -> Input {
+        Input::new(ActionMap::new())
+    }
+}
+
+impl Input {
+    pub fn new(actions: ActionMap) -> Input {
+        Input {
+            keys: Buttons::default(),
+            mouse_buttons: Buttons::default(),
+            gamepads: BTreeMap::new(),
+            gamepad_events: Vec::new(),
+            disconnected: Vec::new(),
+            dead_zone: 0.15,
+            cursor_position: None,
+            cursor_world: None,
+            scroll: [0.0, 0.0],
+            text: String::new(),
+            actions,
+        }
+    }
+
+    /// Updates the state from a window event.
+    pub fn handle_event(&mut self, event: &WindowEvent) {
+        match event {
+            WindowEvent::KeyboardInput { input, .. } => {
+                if let Some(key) = input.virtual_keycode {
+                    match input.state {
+                        ElementState::Pressed => self.keys.press(key),
+                        ElementState::Released => self.keys.release(key),
+                    }
+                }
+            }
+            WindowEvent::MouseInput { state, button, .. } => match state {
+                ElementState::Pressed => self.mouse_buttons.press(*button),
+                ElementState::Released => self.mouse_buttons.release(*button),
+            },
+            WindowEvent::CursorMoved { position, .. } => {
+                self.cursor_position = Some(*position);
+            }
+            WindowEvent::CursorLeft { .. } => {
+                self.cursor_position = None;
+                self.cursor_world = None;
+            }
+            WindowEvent::MouseWheel { delta, .. } => {
+                let [x, y] = match delta {
+                    MouseScrollDelta::LineDelta(x, y) => [*x, *y],
+                    // roughly one line per 20 pixels
+                    MouseScrollDelta::PixelDelta(position) => {
+                        [position.x as f32 / 20.0, position.y as f32 / 20.0]
+                    }
+                };
+                self.scroll[0] += x;
+                self.scroll[1] += y;
+            }
+            WindowEvent::ReceivedCharacter(c) if !c.is_control() => {
+                self.text.push(*c);
+            }
+            // release events are not delivered to unfocused windows
+            WindowEvent::Focused(false) => {
+                self.keys.release_all();
+                self.mouse_buttons.release_all();
+            }
+            _ => {}
+        }
+    }
+
+    /// Updates the state from a gamepad backend event.
+    /// * A disconnected gamepad stays until the end of the update, with its
+    ///   held buttons just released and its axes at rest.
+    pub fn handle_gamepad_event(&mut self, event: GamepadEvent) {
+        match &event.kind {
+            GamepadEventKind::Connected { name } => {
+                self.disconnected.retain(|&id| id != event.id);
+                self.gamepads.insert(
+                    event.id,
+                    GamepadState {
+                        name: name.clone(),
+                        dead_zone: self.dead_zone,
+                        ..Default::default()
+                    },
+                );
+            }
+            GamepadEventKind::Disconnected => {
+                if let Some(gamepad) = self.gamepads.get_mut(&event.id) {
+                    gamepad.buttons.release_all();
+                    gamepad.axes.clear();
+                    self.disconnected.push(event.id);
+                }
+            }
+            kind => {
+                // some backends report input before the connection
+                let dead_zone = self.dead_zone;
+                let gamepad = self
+                    .gamepads
+                    .entry(event.id)
+                    .or_insert_with(|| GamepadState {
+                        dead_zone,
+                        ..Default::default()
+                    });
+
+                match *kind {
+                    GamepadEventKind::ButtonPressed(button) => gamepad.buttons.press(button),
+                    GamepadEventKind::ButtonReleased(button) => gamepad.buttons.release(button),
+                    GamepadEventKind::AxisChanged(axis, value) => {
+                        gamepad.axes.insert(axis, value);
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        self.gamepad_events.push(event);
+    }
+
+    /// Sets the cursor position in world coordinates. The application does
+    /// this before each frame's updates, as the camera may have moved.
+    pub fn set_cursor_world(&mut self, position: Option<[f32; 2]>) {
+        self.cursor_world = position;
+    }
+
+    /// Clears the per-update state. Called after every fixed update.
+    pub fn end_update(&mut self) {
+        self.keys.end_update();
+        self.mouse_buttons.end_update();
+        for gamepad in self.gamepads.values_mut() {
+            gamepad.buttons.end_update();
+        }
+        for id in self.disconnected.drain(..) {
+            self.gamepads.remove(&id);
+        }
+        self.gamepad_events.clear();
+        self.scroll = [0.0, 0.0];
+        self.text.clear();
+    }
+
+    pub fn key_down(&self, key: VirtualKeyCode) -> bool {
+        self.keys.down.contains(&key)
+    }
+
+    pub fn key_pressed(&self, key: VirtualKeyCode) -> bool {
+        self.keys.pressed.contains(&key)
+    }
+
+    pub fn key_released(&self, key: VirtualKeyCode) -> bool {
+        self.keys.released.contains(&key)
+    }
+
+    pub fn mouse_down(&self, button: MouseButton) -> bool {
+        self.mouse_buttons.down.contains(&button)
+    }
+
+    pub fn mouse_pressed(&self, button: MouseButton) -> bool {
+        self.mouse_buttons.pressed.contains(&button)
+    }
+
+    pub fn mouse_released(&self, button: MouseButton) -> bool {
+        self.mouse_buttons.released.contains(&button)
+    }
+
+    /// Returns true while the button is held on any gamepad.
+    pub fn gamepad_down(&self, button: GamepadButton) -> bool {
+        self.gamepads.values().any(|gamepad| gamepad.down(button))
+    }
+
+    pub fn gamepad_pressed(&self, button: GamepadButton) -> bool {
+        self.gamepads
+            .values()
+            .any(|gamepad| gamepad.pressed(button))
+    }
+
+    pub fn gamepad_released(&self, button: GamepadButton) -> bool {
+        self.gamepads
+            .values()
+            .any(|gamepad| gamepad.released(button))
+    }
+
+    /// State of one gamepad, for games giving each player a controller.
+    pub fn gamepad(&self, id: GamepadId) -> Option<&GamepadState> {
+        self.gamepads.get(&id)
+    }
+
+    /// Connected gamepads in id order.
+    pub fn gamepads(&self) -> impl Iterator<Item = (GamepadId, &GamepadState)> {
+        self.gamepads.iter().map(|(&id, gamepad)| (id, gamepad))
+    }
+
+    /// Gamepad events since the last update, e.g. to notice connections.
+    pub fn gamepad_events(&self) -> &[GamepadEvent] {
+        &self.gamepad_events
+    }
+
+    /// Sets the fraction of an axis' range around rest that reads as 0.
+    /// Defaults to 0.15.
+    pub fn set_dead_zone(&mut self, dead_zone: f32) {
+        self.dead_zone = dead_zone.clamp(0.0, 0.99);
+        for gamepad in self.gamepads.values_mut() {
+            gamepad.dead_zone = self.dead_zone;
+        }
+    }
+
+    pub fn dead_zone(&self) -> f32 {
+        self.dead_zone
+    }
+
+    /// Cursor position in window pixels, None if the cursor is outside the
+    /// window.
+    pub fn cursor_position(&self) -> Option<PhysicalPosition<f64>> {
+        self.cursor_position
+    }
+
+    /// Cursor position in world coordinates, None if the cursor is outside
+    /// the window or on the letterbox bars.
+    pub fn cursor_world(&self) -> Option<[f32; 2]> {
+        self.cursor_world
+    }
+
+    /// Scroll since the last update, in lines. Positive y scrolls up.
+    pub fn scroll(&self) -> [f32; 2] {
+        self.scroll
+    }
+
+    /// Text typed since the last update.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn actions(&self) -> &ActionMap {
+        &self.actions
+    }
+
+    /// Returns the action map for rebinding.
+    pub fn actions_mut(&mut self) -> &mut ActionMap {
+        &mut self.actions
+    }
+
+    pub fn set_actions(&mut self, actions: ActionMap) {
+        self.actions = actions;
+    }
+
+    fn binding_down(&self, binding: Binding) -> bool {
+        match binding {
+            Binding::Key(key) => self.key_down(key),
+            Binding::Mouse(button) => self.mouse_down(button),
+            Binding::Gamepad(button) => self.gamepad_down(button),
+        }
+    }
+
+    fn binding_pressed(&self, binding: Binding) -> bool {
+        match binding {
+            Binding::Key(key) => self.key_pressed(key),
+            Binding::Mouse(button) => self.mouse_pressed(button),
+            Binding::Gamepad(button) => self.gamepad_pressed(button),
+        }
+    }
+
+    fn binding_released(&self, binding: Binding) -> bool {
+        match binding {
+            Binding::Key(key) => self.key_released(key),
+            Binding::Mouse(button) => self.mouse_released(button),
+            Binding::Gamepad(button) => self.gamepad_released(button),
+        }
+    }
+
+    /// Returns true while any of the action's bindings is held.
+    pub fn action_down(&self, action: &str) -> bool {
+        self.actions
+            .bindings(action)
+            .iter()
+            .any(|&binding| self.binding_down(binding))
+    }
+
+    /// Returns true on the update any of the action's bindings is pressed.
+    pub fn action_pressed(&self, action: &str) -> bool {
+        self.actions
+            .bindings(action)
+            .iter()
+            .any(|&binding| self.binding_pressed(binding))
+    }
+
+    /// Returns true on the update the last held binding of the action is
+    /// released.
+    pub fn action_released(&self, action: &str) -> bool {
+        !self.action_down(action)
+            && self
+                .actions
+                .bindings(action)
+                .iter()
+                .any(|&binding| self.binding_released(binding))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::gamepad::apply_dead_zone;
+    use super::mock::{MockGamepadBackend, MockGamepads};
+    use super::*;
+    use winit::event::{DeviceId, KeyboardInput, ModifiersState};
+
+    const PAD: GamepadId = GamepadId(0);
+
+    // only used to build events, never handed back to winit
+    const DEVICE: DeviceId = unsafe { DeviceId::dummy() };
+
+    #[allow(deprecated)]
+    fn key(key: VirtualKeyCode, state: ElementState) -> WindowEvent<'static> {
+        WindowEvent::KeyboardInput {
+            device_id: DEVICE,
+            input: KeyboardInput {
+                scancode: 0,
+                state,
+                virtual_keycode: Some(key),
+                modifiers: ModifiersState::empty(),
+            },
+            is_synthetic: false,
+        }
+    }
+
+    #[allow(deprecated)]
+    fn mouse(button: MouseButton, state: ElementState) -> WindowEvent<'static> {
+        WindowEvent::MouseInput {
+            device_id: DEVICE,
+            state,
+            button,
+            modifiers: ModifiersState::empty(),
+        }
+    }
+
+    fn setup() -> (Input, MockGamepadBackend, MockGamepads) {
+        let (backend, pads) = MockGamepadBackend::new();
+        (Input::default(), backend, pads)
+    }
+
+    fn poll(input: &mut Input, backend: &mut MockGamepadBackend) {
+        for event in backend.poll() {
+            input.handle_gamepad_event(event);
+        }
+    }
+
+    #[test]
+    fn connect_adds_gamepad() {
+        let (mut input, mut backend, pads) = setup();
+        pads.connect(PAD, "Test Pad");
+        poll(&mut input, &mut backend);
+
+        assert_eq!(input.gamepad(PAD).unwrap().name(), "Test Pad");
+        assert_eq!(input.gamepads().count(), 1);
+        assert_eq!(
+            input.gamepad_events(),
+            &[GamepadEvent {
+                id: PAD,
+                kind: GamepadEventKind::Connected {
+                    name: "Test Pad".to_string()
+                },
+            }]
+        );
+
+        input.end_update();
+        assert!(input.gamepad_events().is_empty());
+        assert!(input.gamepad(PAD).is_some());
+    }
+
+    #[test]
+    fn press_and_release_last_one_update() {
+        let (mut input, mut backend, pads) = setup();
+        pads.connect(PAD, "Test Pad");
+        pads.press(PAD, GamepadButton::South);
+        poll(&mut input, &mut backend);
+
+        assert!(input.gamepad_down(GamepadButton::South));
+        assert!(input.gamepad_pressed(GamepadButton::South));
+        assert!(!input.gamepad_released(GamepadButton::South));
+
+        input.end_update();
+        assert!(input.gamepad_down(GamepadButton::South));
+        assert!(!input.gamepad_pressed(GamepadButton::South));
+
+        pads.release(PAD, GamepadButton::South);
+        poll(&mut input, &mut backend);
+        assert!(!input.gamepad_down(GamepadButton::South));
+        assert!(input.gamepad_released(GamepadButton::South));
+
+        input.end_update();
+        assert!(!input.gamepad_released(GamepadButton::South));
+    }
+
+    #[test]
+    fn repeated_press_is_not_a_new_press() {
+        let (mut input, mut backend, pads) = setup();
+        pads.press(PAD, GamepadButton::East);
+        poll(&mut input, &mut backend);
+        input.end_update();
+
+        pads.press(PAD, GamepadButton::East);
+        poll(&mut input, &mut backend);
+        assert!(input.gamepad_down(GamepadButton::East));
+        assert!(!input.gamepad_pressed(GamepadButton::East));
+    }
+
+    #[test]
+    fn axes_apply_dead_zone() {
+        let (mut input, mut backend, pads) = setup();
+        pads.connect(PAD, "Test Pad");
+        pads.set_axis(PAD, GamepadAxis::LeftStickX, 0.1);
+        pads.set_axis(PAD, GamepadAxis::RightTrigger, 1.0);
+        pads.set_axis(PAD, GamepadAxis::RightStickY, -0.575);
+        poll(&mut input, &mut backend);
+
+        let gamepad = input.gamepad(PAD).unwrap();
+        assert_eq!(gamepad.axis(GamepadAxis::LeftStickX), 0.0);
+        assert_eq!(gamepad.axis(GamepadAxis::RightTrigger), 1.0);
+        assert!((gamepad.axis(GamepadAxis::RightStickY) + 0.5).abs() < 1e-6);
+
+        input.set_dead_zone(0.05);
+        let gamepad = input.gamepad(PAD).unwrap();
+        assert!(gamepad.axis(GamepadAxis::LeftStickX) > 0.0);
+    }
+
+    #[test]
+    fn apply_dead_zone_rescales() {
+        assert_eq!(apply_dead_zone(0.15, 0.15), 0.0);
+        assert_eq!(apply_dead_zone(-0.1, 0.15), 0.0);
+        assert_eq!(apply_dead_zone(1.0, 0.15), 1.0);
+        assert_eq!(apply_dead_zone(-1.0, 0.15), -1.0);
+        assert!((apply_dead_zone(0.575, 0.15) - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn stick_dead_zone_keeps_diagonals() {
+        let (mut input, mut backend, pads) = setup();
+        pads.set_axis(PAD, GamepadAxis::LeftStickX, 0.12);
+        pads.set_axis(PAD, GamepadAxis::LeftStickY, 0.12);
+        poll(&mut input, &mut backend);
+
+        // each axis is inside the dead zone, but the stick's length is not
+        let [x, y] = input.gamepad(PAD).unwrap().left_stick();
+        assert!(x > 0.0 && (x - y).abs() < 1e-6);
+    }
+
+    #[test]
+    fn disconnect_releases_held_buttons_then_removes_gamepad() {
+        let (mut input, mut backend, pads) = setup();
+        pads.connect(PAD, "Test Pad");
+        pads.press(PAD, GamepadButton::Start);
+        pads.set_axis(PAD, GamepadAxis::LeftStickX, 1.0);
+        poll(&mut input, &mut backend);
+        input.end_update();
+
+        pads.disconnect(PAD);
+        poll(&mut input, &mut backend);
+        assert!(!input.gamepad_down(GamepadButton::Start));
+        assert!(input.gamepad_released(GamepadButton::Start));
+        assert_eq!(
+            input.gamepad(PAD).unwrap().axis(GamepadAxis::LeftStickX),
+            0.0
+        );
+
+        input.end_update();
+        assert!(input.gamepad(PAD).is_none());
+        assert!(!input.gamepad_released(GamepadButton::Start));
+    }
+
+    #[test]
+    fn reconnect_in_same_update_keeps_gamepad() {
+        let (mut input, mut backend, pads) = setup();
+        pads.connect(PAD, "Test Pad");
+        pads.disconnect(PAD);
+        pads.connect(PAD, "Test Pad");
+        poll(&mut input, &mut backend);
+        input.end_update();
+
+        assert!(input.gamepad(PAD).is_some());
+    }
+
+    #[test]
+    fn actions_read_gamepad_bindings() {
+        let (mut input, mut backend, pads) = setup();
+        input
+            .actions_mut()
+            .bind("jump", Binding::Gamepad(GamepadButton::South));
+        pads.press(PAD, GamepadButton::South);
+        poll(&mut input, &mut backend);
+
+        assert!(input.action_pressed("jump"));
+        input.end_update();
+
+        pads.release(PAD, GamepadButton::South);
+        poll(&mut input, &mut backend);
+        assert!(input.action_released("jump"));
+    }
+
+    #[test]
+    fn keys_are_just_pressed_and_released_for_one_update() {
+        let mut input = Input::default();
+        input.handle_event(&key(VirtualKeyCode::Space, ElementState::Pressed));
+
+        assert!(input.key_down(VirtualKeyCode::Space));
+        assert!(input.key_pressed(VirtualKeyCode::Space));
+        assert!(!input.key_released(VirtualKeyCode::Space));
+
+        input.end_update();
+        assert!(input.key_down(VirtualKeyCode::Space));
+        assert!(!input.key_pressed(VirtualKeyCode::Space));
+
+        // key repeat is not a new press
+        input.handle_event(&key(VirtualKeyCode::Space, ElementState::Pressed));
+        assert!(!input.key_pressed(VirtualKeyCode::Space));
+
+        input.handle_event(&key(VirtualKeyCode::Space, ElementState::Released));
+        assert!(!input.key_down(VirtualKeyCode::Space));
+        assert!(input.key_released(VirtualKeyCode::Space));
+
+        input.end_update();
+        assert!(!input.key_released(VirtualKeyCode::Space));
+    }
+
+    #[test]
+    fn press_and_release_in_one_update_are_both_seen() {
+        let mut input = Input::default();
+        input.handle_event(&mouse(MouseButton::Left, ElementState::Pressed));
+        input.handle_event(&mouse(MouseButton::Left, ElementState::Released));
+
+        assert!(!input.mouse_down(MouseButton::Left));
+        assert!(input.mouse_pressed(MouseButton::Left));
+        assert!(input.mouse_released(MouseButton::Left));
+
+        input.end_update();
+        assert!(!input.mouse_pressed(MouseButton::Left));
+        assert!(!input.mouse_released(MouseButton::Left));
+    }
+
+    #[test]
+    fn mouse_buttons_are_just_pressed_and_released_for_one_update() {
+        let mut input = Input::default();
+        input.handle_event(&mouse(MouseButton::Right, ElementState::Pressed));
+
+        assert!(input.mouse_down(MouseButton::Right));
+        assert!(input.mouse_pressed(MouseButton::Right));
+        assert!(!input.mouse_down(MouseButton::Left));
+
+        input.end_update();
+        assert!(input.mouse_down(MouseButton::Right));
+        assert!(!input.mouse_pressed(MouseButton::Right));
+
+        input.handle_event(&mouse(MouseButton::Right, ElementState::Released));
+        assert!(input.mouse_released(MouseButton::Right));
+
+        input.end_update();
+        assert!(!input.mouse_down(MouseButton::Right));
+        assert!(!input.mouse_released(MouseButton::Right));
+    }
+
+    #[test]
+    fn losing_focus_releases_everything() {
+        let mut input = Input::default();
+        input.handle_event(&key(VirtualKeyCode::A, ElementState::Pressed));
+        input.handle_event(&mouse(MouseButton::Left, ElementState::Pressed));
+        input.end_update();
+
+        input.handle_event(&WindowEvent::Focused(false));
+        assert!(!input.key_down(VirtualKeyCode::A));
+        assert!(input.key_released(VirtualKeyCode::A));
+        assert!(!input.mouse_down(MouseButton::Left));
+        assert!(input.mouse_released(MouseButton::Left));
+
+        // the release the window never got is not released twice
+        input.end_update();
+        input.handle_event(&key(VirtualKeyCode::A, ElementState::Released));
+        assert!(!input.key_released(VirtualKeyCode::A));
+    }
+
+    #[test]
+    fn scroll_and_text_last_one_update() {
+        let mut input = Input::default();
+        input.handle_event(&WindowEvent::MouseWheel {
+            device_id: DEVICE,
+            delta: MouseScrollDelta::LineDelta(0.0, 1.0),
+            phase: winit::event::TouchPhase::Moved,
+            #[allow(deprecated)]
+            modifiers: ModifiersState::empty(),
+        });
+        input.handle_event(&WindowEvent::MouseWheel {
+            device_id: DEVICE,
+            delta: MouseScrollDelta::PixelDelta(PhysicalPosition::new(40.0, -20.0)),
+            phase: winit::event::TouchPhase::Moved,
+            #[allow(deprecated)]
+            modifiers: ModifiersState::empty(),
+        });
+        input.handle_event(&WindowEvent::ReceivedCharacter('h'));
+        input.handle_event(&WindowEvent::ReceivedCharacter('\u{8}'));
+        input.handle_event(&WindowEvent::ReceivedCharacter('i'));
+
+        assert_eq!(input.scroll(), [2.0, 0.0]);
+        assert_eq!(input.text(), "hi");
+
+        input.end_update();
+        assert_eq!(input.scroll(), [0.0, 0.0]);
+        assert_eq!(input.text(), "");
+    }
+
+    #[test]
+    fn action_map_round_trips_through_json() {
+        let mut actions = ActionMap::new();
+        actions.bind("jump", Binding::Key(VirtualKeyCode::Space));
+        actions.bind("jump", Binding::Gamepad(GamepadButton::South));
+        actions.bind("fire", Binding::Mouse(MouseButton::Left));
+        actions.bind("fire", Binding::Mouse(MouseButton::Other(4)));
+
+        let json = actions.to_json().unwrap();
+        assert_eq!(ActionMap::from_json(&json).unwrap(), actions);
+    }
+
+    #[test]
+    fn action_is_released_once_its_last_binding_is() {
+        let mut input = Input::default();
+        input
+            .actions_mut()
+            .bind("left", Binding::Key(VirtualKeyCode::Left));
+        input
+            .actions_mut()
+            .bind("left", Binding::Key(VirtualKeyCode::A));
+
+        input.handle_event(&key(VirtualKeyCode::Left, ElementState::Pressed));
+        input.handle_event(&key(VirtualKeyCode::A, ElementState::Pressed));
+        assert!(input.action_pressed("left"));
+        input.end_update();
+
+        input.handle_event(&key(VirtualKeyCode::Left, ElementState::Released));
+        assert!(input.action_down("left"));
+        assert!(!input.action_released("left"));
+        input.end_update();
+
+        input.handle_event(&key(VirtualKeyCode::A, ElementState::Released));
+        assert!(!input.action_down("left"));
+        assert!(input.action_released("left"));
+        assert!(!input.action_released("right"));
+    }
+}
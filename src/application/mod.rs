@@ -1,125 +1,245 @@
-mod renderer;
-use renderer::renderables::lights::{DirectionalLight, PointLight};
-use renderer::renderables::quad::ColorQuad;
-use renderer::renderables::triangle::ColorTriangle;
-use renderer::renderables::vertices::ColorVertex2D;
-use renderer::Renderer;
-
-use vulkano::sync::{self, GpuFuture};
+pub mod animation;
+pub mod ecs;
+pub mod game;
+pub mod input;
+pub mod renderer;
+pub mod scene;
+pub mod tiled;
+pub mod timestep;
+pub mod window;
+use animation::Tweens;
+use ecs::systems::{
+    animate_skeletons, animate_sprites, render_system, run_tweens, store_previous_transforms,
+};
+use ecs::{Schedule, World};
+use game::{Context, Game};
+use input::{ActionMap, GamepadBackend, Input};
+use renderer::config::RendererConfig;
+use renderer::target::Target;
+use renderer::{Frame, Renderer};
+use timestep::FixedTimestep;
+use window::WindowConfig;
 
 use winit::event::{Event, WindowEvent};
 use winit::event_loop::{ControlFlow, EventLoop};
 
+use std::time::Instant;
+
 pub struct Application {
     event_loop: EventLoop<()>,
     renderer: Renderer,
+    world: World,
+    schedule: Schedule,
+    timestep: FixedTimestep,
+    gamepad_backend: Option<Box<dyn GamepadBackend>>,
 }
 
 impl Application {
-    pub fn new() -> Application {
+    /// Opens the window and initializes the renderer.
+    pub fn new(window: WindowConfig) -> Application {
+        Application::with_renderer_config(window, RendererConfig::default())
+    }
+
+    pub fn with_renderer_config(window: WindowConfig, config: RendererConfig) -> Application {
         let event_loop = EventLoop::new();
-        let renderer = Renderer::new(&event_loop);
+        let renderer = Renderer::new(&event_loop, &window, config);
+
+        let mut world = World::new();
+        world.insert_resource(Input::new(ActionMap::new()));
+        world.insert_resource(Tweens::<World>::new());
 
         Application {
             event_loop,
             renderer,
+            world,
+            schedule: Schedule::new(),
+            timestep: FixedTimestep::default(),
+            gamepad_backend: Application::default_gamepad_backend(),
         }
     }
 
-    pub fn run(mut self) {
-        let mut previous_frame_end =
-            Some(Box::new(sync::now(self.renderer.device.clone())) as Box<dyn GpuFuture>);
-
-        // just for testing
-        // let quad = ColorQuad {
-        //     vertices: [
-        //         ColorVertex2D {
-        //             position: [-1.0, -1.0, 1.0],
-        //             color: [1.0, 1.0, 1.0]
-        //         },
-        //         ColorVertex2D {
-        //             position: [-1.0, 1.0, 1.0],
-        //             color: [1.0, 1.0, 1.0]
-        //         },
-        //         ColorVertex2D {
-        //             position: [1.0, 1.0, 1.0],
-        //             color: [1.0, 1.0, 1.0]
-        //         },
-        //         ColorVertex2D {
-        //             position: [-1.0, -1.0, 1.0],
-        //             color: [1.0, 1.0, 1.0]
-        //         },
-        //         ColorVertex2D {
-        //             position: [1.0, 1.0, 1.0],
-        //             color: [1.0, 1.0, 1.0]
-        //         },
-        //         ColorVertex2D {
-        //             position: [1.0, -1.0, 1.0],
-        //             color: [1.0, 1.0, 1.0]
-        //         }
-        //     ],
-        //     matrix: nalgebra_glm::identity()
-        // };
-
-        let triangle = ColorTriangle {
-            vertices: [
-                ColorVertex2D {
-                    position: [-0.5, 0.5, 0.0],
-                    color: [1.0, 0.0, 0.0],
-                },
-                ColorVertex2D {
-                    position: [0.5, 0.5, 0.0],
-                    color: [0.0, 1.0, 0.0],
-                },
-                ColorVertex2D {
-                    position: [0.0, -0.5, 0.0],
-                    color: [0.0, 0.0, 1.0],
-                },
-            ],
-            matrix: nalgebra_glm::identity(),
-        };
-
-        let dir_light = DirectionalLight {
-            direction: [-1.0, 1.0],
-            color: [1.0, 1.0, 1.0],
-            intensity: 0.5,
-        };
-
-        let point_light = PointLight {
-            position: [0.0, 0.0, 0.0],
-            color: [1.0, 1.0, 1.0],
-            intensity: 0.5,
-        };
-
-        self.event_loop
-            .run(move |event, _, control_flow| match event {
-                Event::WindowEvent {
-                    event: WindowEvent::CloseRequested,
-                    ..
-                } => {
-                    *control_flow = ControlFlow::Exit;
-                }
-                Event::WindowEvent {
-                    event: WindowEvent::Resized(_),
-                    ..
-                } => {
-                    self.renderer.recreate_swapchain();
+    #[cfg(feature = "gilrs")]
+    fn default_gamepad_backend() -> Option<Box<dyn GamepadBackend>> {
+        match input::gilrs_backend::GilrsBackend::new() {
+            Ok(backend) => Some(Box::new(backend)),
+            Err(err) => {
+                println!("Gamepads unavailable: {}", err);
+                None
+            }
+        }
+    }
+
+    #[cfg(not(feature = "gilrs"))]
+    fn default_gamepad_backend() -> Option<Box<dyn GamepadBackend>> {
+        None
+    }
+
+    /// Replaces where gamepad input comes from, e.g. with a
+    /// `MockGamepadBackend` for tests.
+    pub fn set_gamepad_backend(&mut self, backend: impl GamepadBackend + 'static) {
+        self.gamepad_backend = Some(Box::new(backend));
+    }
+
+    /// The game state: entities, their components, and shared resources.
+    /// Input state is the `Input` resource.
+    pub fn world(&mut self) -> &mut World {
+        &mut self.world
+    }
+
+    /// The renderer, e.g. for loading textures before the game starts.
+    pub fn renderer(&mut self) -> &mut Renderer {
+        &mut self.renderer
+    }
+
+    /// Sets the rate game logic runs at. Defaults to 60 updates per second.
+    pub fn set_timestep(&mut self, timestep: FixedTimestep) {
+        self.timestep = timestep;
+    }
+
+    /// Adds a system to run on the world every fixed update, after
+    /// `Game::update`.
+    pub fn add_system(&mut self, system: impl FnMut(&mut World) + 'static) {
+        self.schedule.add_system(system);
+    }
+
+    /// Runs the application without any game logic beyond its systems.
+    pub fn run(self) {
+        struct NoGame;
+        impl Game for NoGame {}
+
+        self.run_game(NoGame);
+    }
+
+    /// Runs the application, driving the given game's callbacks. Never
+    /// returns.
+    pub fn run_game(mut self, mut game: impl Game + 'static) {
+        let mut exit_requested = false;
+
+        game.init(&mut Context {
+            world: &mut self.world,
+            renderer: &mut self.renderer,
+            event_loop: &self.event_loop,
+            exit_requested: &mut exit_requested,
+        });
+
+        let mut last_frame = Instant::now();
+
+        self.event_loop.run(move |event, event_loop, control_flow| {
+            let mut context = Context {
+                world: &mut self.world,
+                renderer: &mut self.renderer,
+                event_loop,
+                exit_requested: &mut exit_requested,
+            };
+
+            match event {
+                Event::WindowEvent { window_id, event } => {
+                    let primary = window_id == context.renderer.primary_window();
+
+                    match event {
+                        WindowEvent::CloseRequested if primary => context.exit(),
+                        WindowEvent::Resized(_) | WindowEvent::ScaleFactorChanged { .. } => {
+                            if let Some(window) = context.renderer.surface_mut(window_id) {
+                                window.invalidate_swapchain();
+                            }
+                        }
+                        _ => {}
+                    }
+
+                    // Input follows the primary window only; tool windows
+                    // reach the game through on_event
+                    if let Some(input) = context.world.resource_mut::<Input>().filter(|_| primary) {
+                        input.handle_event(&event);
+                    }
+
+                    game.on_event(&mut context, window_id, &event);
+
+                    // other windows close on their own, the game having had
+                    // its chance to save whatever they were showing
+                    if !primary && matches!(event, WindowEvent::CloseRequested) {
+                        context.renderer.close_window(window_id);
+                    }
                 }
                 Event::RedrawEventsCleared => {
-                    previous_frame_end
-                        .as_mut()
-                        .take()
-                        .unwrap()
-                        .cleanup_finished();
-
-                    self.renderer.start();
-                    self.renderer.color_draw(&triangle);
-                    self.renderer.ambient();
-                    self.renderer.directional(&dir_light);
-                    self.renderer.point(&point_light);
-                    self.renderer.finish(&mut previous_frame_end);
+                    let now = Instant::now();
+                    let steps = self.timestep.advance(now.duration_since(last_frame));
+                    last_frame = now;
+
+                    if let Some(input) = context.world.resource_mut::<Input>() {
+                        if let Some(backend) = self.gamepad_backend.as_mut() {
+                            for event in backend.poll() {
+                                input.handle_gamepad_event(event);
+                            }
+                        }
+
+                        // the camera may have moved since the cursor did
+                        let cursor_world = input
+                            .cursor_position()
+                            .and_then(|position| context.renderer.window_to_world(position));
+                        input.set_cursor_world(cursor_world);
+                    }
+
+                    for _ in 0..steps {
+                        store_previous_transforms(context.world);
+                        animate_sprites(context.world, self.timestep.dt());
+                        animate_skeletons(context.world, self.timestep.dt());
+                        run_tweens(context.world, self.timestep.dt());
+                        game.update(&mut context, self.timestep.dt());
+                        self.schedule.run(context.world);
+
+                        if let Some(input) = context.world.resource_mut::<Input>() {
+                            input.end_update();
+                        }
+                    }
+
+                    let windows = self
+                        .renderer
+                        .window_ids()
+                        .into_iter()
+                        .filter(|&id| !self.renderer.surface(id).unwrap().is_minimized())
+                        .map(Target::Window)
+                        .collect::<Vec<_>>();
+
+                    // nothing to show while minimized, so only keep updating,
+                    // sleeping between steps instead of spinning
+                    if windows.is_empty() {
+                        *control_flow = ControlFlow::WaitUntil(now + self.timestep.step());
+                    } else {
+                        *control_flow = ControlFlow::Poll;
+
+                        // render targets first, so windows show this frame's
+                        // images of them
+                        let render_targets = self
+                            .renderer
+                            .render_target_ids()
+                            .into_iter()
+                            .map(Target::Texture);
+
+                        for target in render_targets.chain(windows) {
+                            self.renderer.start_target(target);
+                            let mut frame = Frame::new(&mut self.renderer, self.timestep.alpha());
+                            render_system(&self.world, &mut frame);
+                            game.draw(&mut frame);
+                            frame.submit();
+                            self.renderer.finish();
+                        }
+                    }
+                }
+                Event::LoopDestroyed => {
+                    game.on_exit(&mut context);
                 }
                 _ => {}
-            });
+            }
+
+            if exit_requested {
+                *control_flow = ControlFlow::Exit;
+            }
+        });
+    }
+}
+
+impl Default for Application {
+    fn default() -> Application {
+        Application::new(WindowConfig::default())
     }
 }
@@ -0,0 +1,272 @@
+pub mod packer;
+pub mod sheet;
+pub use packer::{AtlasBuilder, PackedAtlas};
+
+use nalgebra_glm::TMat4;
+
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+
+use super::renderables::quad::Quad;
+use super::texture::Texture;
+use super::Renderer;
+use crate::application::ecs::components::Sprite;
+use sheet::SheetJson;
+
+/// Where a named image sits in an atlas.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AtlasRegion {
+    /// Index of the atlas page holding the image.
+    pub page: usize,
+    /// Top left and bottom right texture coordinates, as used by `Sprite`.
+    pub uv: [[f32; 2]; 2],
+    /// Size of the image in pixels.
+    pub size: [u32; 2],
+}
+
+/// Images packed into one or more shared textures, looked up by name, so
+/// sprites drawn from the same page share a texture.
+/// * Regions keep the order they were added or listed in, which sprite
+///   sheet animations rely on.
+pub struct TextureAtlas {
+    pages: Vec<Arc<Texture>>,
+    names: Vec<String>,
+    regions: Vec<AtlasRegion>,
+    indices: HashMap<String, usize>,
+}
+
+impl TextureAtlas {
+    pub fn new(pages: Vec<Arc<Texture>>, regions: Vec<(String, AtlasRegion)>) -> TextureAtlas {
+        let (names, regions): (Vec<String>, Vec<AtlasRegion>) = regions.into_iter().unzip();
+        let indices = names
+            .iter()
+            .enumerate()
+            .map(|(index, name)| (name.clone(), index))
+            .collect();
+
+        TextureAtlas {
+            pages,
+            names,
+            regions,
+            indices,
+        }
+    }
+
+    /// Loads a sprite sheet exported by Aseprite or TexturePacker as JSON,
+    /// in either the hash or the array layout. The sheet image is looked up
+    /// next to the JSON file.
+    /// * Rotated frames, and trimmed frames that lost some of their source
+    ///   image, are refused with `AtlasError::Unsupported`.
+    pub fn load(renderer: &Renderer, path: &str) -> Result<TextureAtlas, AtlasError> {
+        let sheet = SheetJson::load(path)?;
+        TextureAtlas::from_sheet(renderer, path, &sheet)
+    }
+
+    pub(crate) fn from_sheet(
+        renderer: &Renderer,
+        path: &str,
+        sheet: &SheetJson,
+    ) -> Result<TextureAtlas, AtlasError> {
+        let image = Path::new(path)
+            .parent()
+            .unwrap_or_else(|| Path::new(""))
+            .join(&sheet.meta.image);
+        let texture = renderer.load_texture(&image.to_string_lossy())?;
+        let [width, height] = texture.dimensions().map(|d| d as f32);
+
+        let regions = sheet
+            .frames
+            .0
+            .iter()
+            .map(|(name, frame)| {
+                if frame.rotated {
+                    return Err(AtlasError::Unsupported(format!(
+                        "frame {} is rotated; export the sheet without rotation",
+                        name
+                    )));
+                }
+                if frame.is_cropped() {
+                    return Err(AtlasError::Unsupported(format!(
+                        "frame {} is trimmed; export the sheet without trimming",
+                        name
+                    )));
+                }
+
+                let rect = frame.frame;
+                let region = AtlasRegion {
+                    page: 0,
+                    uv: [
+                        [rect.x as f32 / width, rect.y as f32 / height],
+                        [
+                            (rect.x + rect.w) as f32 / width,
+                            (rect.y + rect.h) as f32 / height,
+                        ],
+                    ],
+                    size: [rect.w, rect.h],
+                };
+
+                Ok((name.clone(), region))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(TextureAtlas::new(vec![texture], regions))
+    }
+
+    /// Atlas over a texture cut into a grid of equal cells, e.g. a tileset
+    /// image. Regions are named by their index, counting row by row from the
+    /// top left.
+    /// * `margin` pixels around the image and `spacing` pixels between cells
+    ///   are skipped. Partial cells at the edges are left out.
+    pub fn from_grid(
+        texture: Arc<Texture>,
+        cell: [u32; 2],
+        margin: u32,
+        spacing: u32,
+    ) -> Result<TextureAtlas, AtlasError> {
+        if cell.contains(&0) {
+            return Err(AtlasError::Invalid(format!(
+                "grid cells of {}x{} pixels are empty",
+                cell[0], cell[1]
+            )));
+        }
+
+        let [width, height] = texture.dimensions();
+        let count =
+            |size: u32, cell: u32| (size.saturating_sub(2 * margin) + spacing) / (cell + spacing);
+        let [columns, rows] = [count(width, cell[0]), count(height, cell[1])];
+
+        let regions = (0..rows * columns)
+            .map(|index| {
+                let x = margin + (index % columns) * (cell[0] + spacing);
+                let y = margin + (index / columns) * (cell[1] + spacing);
+
+                let region = AtlasRegion {
+                    page: 0,
+                    uv: [
+                        [x as f32 / width as f32, y as f32 / height as f32],
+                        [
+                            (x + cell[0]) as f32 / width as f32,
+                            (y + cell[1]) as f32 / height as f32,
+                        ],
+                    ],
+                    size: cell,
+                };
+
+                (index.to_string(), region)
+            })
+            .collect();
+
+        Ok(TextureAtlas::new(vec![texture], regions))
+    }
+
+    pub fn region(&self, name: &str) -> Option<&AtlasRegion> {
+        self.index(name).map(|index| &self.regions[index])
+    }
+
+    /// Position of a region in the atlas' order.
+    pub fn index(&self, name: &str) -> Option<usize> {
+        self.indices.get(name).copied()
+    }
+
+    pub fn region_at(&self, index: usize) -> Option<&AtlasRegion> {
+        self.regions.get(index)
+    }
+
+    /// Region names in order.
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    pub fn len(&self) -> usize {
+        self.regions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.regions.is_empty()
+    }
+
+    pub fn pages(&self) -> &[Arc<Texture>] {
+        &self.pages
+    }
+
+    /// The page texture holding a region.
+    pub fn texture(&self, region: &AtlasRegion) -> Arc<Texture> {
+        self.pages[region.page].clone()
+    }
+
+    /// Sprite showing the named image, `size` world units large.
+    pub fn sprite(&self, name: &str, size: [f32; 2]) -> Option<Sprite> {
+        let region = self.region(name)?;
+
+        Some(Sprite {
+            uv: region.uv,
+            ..Sprite::new(self.texture(region), size)
+        })
+    }
+
+    /// Quad showing the named image, `size` units large and centered on the
+    /// origin before `matrix` is applied.
+    pub fn quad(&self, name: &str, size: [f32; 2], matrix: TMat4<f32>, layer: i32) -> Option<Quad> {
+        let region = self.region(name)?;
+
+        Some(Quad {
+            vertices: Quad::rect(size, region.uv),
+            matrix,
+            layer,
+            texture: self.texture(region),
+        })
+    }
+}
+
+#[derive(Debug)]
+pub enum AtlasError {
+    Io(std::io::Error),
+    Png(png::DecodingError),
+    Json(serde_json::Error),
+    Invalid(String),
+    /// An image does not fit on an atlas page, even on its own.
+    TooLarge {
+        name: String,
+        size: [u32; 2],
+    },
+    Unsupported(String),
+}
+
+impl fmt::Display for AtlasError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AtlasError::Io(err) => write!(f, "Failed to read atlas: {}", err),
+            AtlasError::Png(err) => write!(f, "Failed to load atlas image: {}", err),
+            AtlasError::Json(err) => write!(f, "Invalid atlas JSON: {}", err),
+            AtlasError::Invalid(reason) => write!(f, "Invalid atlas: {}", reason),
+            AtlasError::TooLarge { name, size } => write!(
+                f,
+                "Image {} ({}x{}) does not fit on an atlas page",
+                name, size[0], size[1]
+            ),
+            AtlasError::Unsupported(feature) => write!(f, "Unsupported atlas: {}", feature),
+        }
+    }
+}
+
+impl std::error::Error for AtlasError {}
+
+impl From<std::io::Error> for AtlasError {
+    fn from(err: std::io::Error) -> AtlasError {
+        AtlasError::Io(err)
+    }
+}
+
+impl From<png::DecodingError> for AtlasError {
+    fn from(err: png::DecodingError) -> AtlasError {
+        AtlasError::Png(err)
+    }
+}
+
+impl From<serde_json::Error> for AtlasError {
+    fn from(err: serde_json::Error) -> AtlasError {
+        AtlasError::Json(err)
+    }
+}
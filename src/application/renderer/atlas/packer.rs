@@ -0,0 +1,410 @@
+use std::collections::HashMap;
+
+use super::super::texture::load_png;
+use super::super::Renderer;
+use super::{AtlasError, AtlasRegion, TextureAtlas};
+
+/// Packs images into atlas pages on the CPU.
+/// * Images are placed on shelves, tallest first, and spill onto a new page
+///   once a page is full.
+/// * `padding` transparent pixels separate images, and each image's edge
+///   pixels are repeated `extrude` times around it, so filtering and
+///   rounding at the border of a region never pick up a neighbour.
+pub struct AtlasBuilder {
+    page_size: u32,
+    padding: u32,
+    extrude: u32,
+    images: Vec<(String, Vec<u8>, [u32; 2])>,
+}
+
+/// Result of packing: RGBA8 pages and the regions within them.
+pub struct PackedAtlas {
+    pub pages: Vec<(Vec<u8>, [u32; 2])>,
+    pub regions: Vec<(String, AtlasRegion)>,
+}
+
+struct Placement {
+    page: usize,
+    position: [u32; 2],
+}
+
+impl AtlasBuilder {
+    /// Builder for pages of at most `page_size` by `page_size` pixels. Pages
+    /// are cropped to what they use.
+    pub fn new(page_size: u32) -> AtlasBuilder {
+        AtlasBuilder {
+            page_size,
+            padding: 1,
+            extrude: 1,
+            images: Vec::new(),
+        }
+    }
+
+    pub fn with_padding(mut self, padding: u32) -> AtlasBuilder {
+        self.padding = padding;
+        self
+    }
+
+    pub fn with_extrude(mut self, extrude: u32) -> AtlasBuilder {
+        self.extrude = extrude;
+        self
+    }
+
+    /// Adds a PNG file under the given name.
+    pub fn add_png(&mut self, name: &str, path: &str) -> Result<(), AtlasError> {
+        let (pixels, dimensions) = load_png(path)?;
+        self.add_pixels(name, pixels, dimensions)
+    }
+
+    /// Adds RGBA8 pixels under the given name. Adding a name again replaces
+    /// the earlier image.
+    pub fn add_pixels(
+        &mut self,
+        name: &str,
+        pixels: Vec<u8>,
+        dimensions: [u32; 2],
+    ) -> Result<(), AtlasError> {
+        let expected = dimensions[0] as usize * dimensions[1] as usize * 4;
+        if pixels.len() != expected {
+            return Err(AtlasError::Invalid(format!(
+                "image {} has {} bytes, not the {} of {}x{} RGBA8 pixels",
+                name,
+                pixels.len(),
+                expected,
+                dimensions[0],
+                dimensions[1]
+            )));
+        }
+
+        match self.images.iter_mut().find(|(n, _, _)| n == name) {
+            Some(image) => *image = (name.to_string(), pixels, dimensions),
+            None => self.images.push((name.to_string(), pixels, dimensions)),
+        }
+        Ok(())
+    }
+
+    /// Packs the images without touching the GPU, e.g. to save the pages.
+    /// Regions are in the order images were added.
+    pub fn pack(&self) -> Result<PackedAtlas, AtlasError> {
+        let border = self.extrude * 2;
+        let slot = |dimensions: [u32; 2]| [dimensions[0] + border, dimensions[1] + border];
+
+        for (name, _, dimensions) in &self.images {
+            let [width, height] = slot(*dimensions);
+            if width > self.page_size || height > self.page_size {
+                return Err(AtlasError::TooLarge {
+                    name: name.clone(),
+                    size: *dimensions,
+                });
+            }
+        }
+
+        // tallest first keeps shelves tight
+        let mut order = (0..self.images.len()).collect::<Vec<_>>();
+        order.sort_by_key(|&i| {
+            let [width, height] = self.images[i].2;
+            (std::cmp::Reverse(height), std::cmp::Reverse(width))
+        });
+
+        // per page: shelves as (y, height, next free x), and the next free y
+        let mut pages: Vec<(Vec<[u32; 3]>, u32)> = Vec::new();
+        let mut placements = HashMap::new();
+
+        for i in order {
+            let [width, height] = slot(self.images[i].2);
+
+            let placement = pages
+                .iter_mut()
+                .enumerate()
+                .find_map(|(page, (shelves, next_y))| {
+                    let shelf = shelves.iter_mut().find(|[_, shelf_height, x]| {
+                        height <= *shelf_height && x + width <= self.page_size
+                    });
+
+                    if let Some([y, _, x]) = shelf {
+                        let position = [*x, *y];
+                        *x += width + self.padding;
+                        return Some(Placement { page, position });
+                    }
+
+                    match *next_y + height <= self.page_size {
+                        true => {
+                            let position = [0, *next_y];
+                            shelves.push([*next_y, height, width + self.padding]);
+                            *next_y += height + self.padding;
+                            Some(Placement { page, position })
+                        }
+                        false => None,
+                    }
+                })
+                .unwrap_or_else(|| {
+                    pages.push((
+                        vec![[0, height, width + self.padding]],
+                        height + self.padding,
+                    ));
+                    Placement {
+                        page: pages.len() - 1,
+                        position: [0, 0],
+                    }
+                });
+
+            placements.insert(i, placement);
+        }
+
+        // crop pages to what they use
+        let mut sizes = vec![[1, 1]; pages.len()];
+        for (i, placement) in &placements {
+            let [width, height] = slot(self.images[*i].2);
+            let size = &mut sizes[placement.page];
+            size[0] = size[0].max(placement.position[0] + width);
+            size[1] = size[1].max(placement.position[1] + height);
+        }
+
+        let mut pages = sizes
+            .iter()
+            .map(|size| (vec![0; (size[0] * size[1] * 4) as usize], *size))
+            .collect::<Vec<_>>();
+
+        let regions = self
+            .images
+            .iter()
+            .enumerate()
+            .map(|(i, (name, pixels, dimensions))| {
+                let placement = &placements[&i];
+                let (page, page_size) = &mut pages[placement.page];
+                let origin = [
+                    placement.position[0] + self.extrude,
+                    placement.position[1] + self.extrude,
+                ];
+
+                blit(page, *page_size, pixels, *dimensions, origin, self.extrude);
+
+                let [page_width, page_height] = page_size.map(|d| d as f32);
+                let region = AtlasRegion {
+                    page: placement.page,
+                    uv: [
+                        [
+                            origin[0] as f32 / page_width,
+                            origin[1] as f32 / page_height,
+                        ],
+                        [
+                            (origin[0] + dimensions[0]) as f32 / page_width,
+                            (origin[1] + dimensions[1]) as f32 / page_height,
+                        ],
+                    ],
+                    size: *dimensions,
+                };
+
+                (name.clone(), region)
+            })
+            .collect();
+
+        Ok(PackedAtlas { pages, regions })
+    }
+
+    /// Packs the images and uploads the pages as textures.
+    pub fn build(&self, renderer: &Renderer) -> Result<TextureAtlas, AtlasError> {
+        Ok(self.pack()?.upload(renderer))
+    }
+}
+
+impl PackedAtlas {
+    /// Uploads the pages as textures. Blocks until the uploads are done.
+    pub fn upload(self, renderer: &Renderer) -> TextureAtlas {
+        let pages = self
+            .pages
+            .iter()
+            .map(|(pixels, dimensions)| renderer.create_texture(pixels, *dimensions))
+            .collect();
+
+        TextureAtlas::new(pages, self.regions)
+    }
+}
+
+/// Copies an image into a page with its top left at `origin`, repeating its
+/// edge pixels `extrude` times outward.
+fn blit(
+    page: &mut [u8],
+    page_size: [u32; 2],
+    pixels: &[u8],
+    dimensions: [u32; 2],
+    origin: [u32; 2],
+    extrude: u32,
+) {
+    let [width, height] = dimensions;
+    if width == 0 || height == 0 {
+        return;
+    }
+
+    let extrude = extrude as i64;
+    for y in -extrude..height as i64 + extrude {
+        for x in -extrude..width as i64 + extrude {
+            let source_x = x.clamp(0, width as i64 - 1) as usize;
+            let source_y = y.clamp(0, height as i64 - 1) as usize;
+            let source = (source_y * width as usize + source_x) * 4;
+
+            let page_x = (origin[0] as i64 + x) as usize;
+            let page_y = (origin[1] as i64 + y) as usize;
+            let dest = (page_y * page_size[0] as usize + page_x) * 4;
+
+            page[dest..dest + 4].copy_from_slice(&pixels[source..source + 4]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Solid image of one color.
+    fn solid(color: [u8; 4], dimensions: [u32; 2]) -> Vec<u8> {
+        color.repeat((dimensions[0] * dimensions[1]) as usize)
+    }
+
+    fn pixel(page: &(Vec<u8>, [u32; 2]), [x, y]: [u32; 2]) -> [u8; 4] {
+        let start = ((y * page.1[0] + x) * 4) as usize;
+        page.0[start..start + 4].try_into().unwrap()
+    }
+
+    fn region<'a>(atlas: &'a PackedAtlas, name: &str) -> &'a AtlasRegion {
+        &atlas.regions.iter().find(|(n, _)| n == name).unwrap().1
+    }
+
+    #[test]
+    fn names_regions_with_their_uvs() {
+        let mut builder = AtlasBuilder::new(64).with_padding(0).with_extrude(0);
+        builder
+            .add_pixels("wide", solid([255; 4], [8, 2]), [8, 2])
+            .unwrap();
+        builder
+            .add_pixels("tall", solid([255; 4], [2, 4]), [2, 4])
+            .unwrap();
+        let atlas = builder.pack().unwrap();
+
+        // regions keep the order images were added in
+        let names = atlas.regions.iter().map(|(name, _)| name.as_str());
+        assert_eq!(names.collect::<Vec<_>>(), ["wide", "tall"]);
+
+        // the taller image is placed first, and the page cropped around both
+        assert_eq!(atlas.pages.len(), 1);
+        assert_eq!(atlas.pages[0].1, [10, 4]);
+
+        let tall = region(&atlas, "tall");
+        assert_eq!(tall.size, [2, 4]);
+        assert_eq!(tall.uv, [[0.0, 0.0], [0.2, 1.0]]);
+
+        let wide = region(&atlas, "wide");
+        assert_eq!(wide.size, [8, 2]);
+        assert_eq!(wide.uv, [[0.2, 0.0], [1.0, 0.5]]);
+    }
+
+    #[test]
+    fn overflows_onto_new_pages() {
+        let mut builder = AtlasBuilder::new(8);
+        for name in ["a", "b", "c"] {
+            builder
+                .add_pixels(name, solid([255; 4], [4, 4]), [4, 4])
+                .unwrap();
+        }
+        let atlas = builder.pack().unwrap();
+
+        // each image takes 6 pixels with its extruded border, so two never
+        // share an 8 pixel page
+        assert_eq!(atlas.pages.len(), 3);
+        for (page, (name, region)) in atlas.regions.iter().enumerate() {
+            assert_eq!(region.page, page, "{}", name);
+            assert_eq!(region.uv, [[1.0 / 6.0; 2], [5.0 / 6.0; 2]]);
+            assert_eq!(atlas.pages[page].1, [6, 6]);
+        }
+    }
+
+    #[test]
+    fn extrudes_edge_pixels() {
+        let [a, b, c, d] = [
+            [1, 0, 0, 255],
+            [2, 0, 0, 255],
+            [3, 0, 0, 255],
+            [4, 0, 0, 255],
+        ];
+        let pixels = [a, b, c, d].concat();
+
+        let mut builder = AtlasBuilder::new(16).with_extrude(2);
+        builder.add_pixels("quad", pixels, [2, 2]).unwrap();
+        let atlas = builder.pack().unwrap();
+
+        let page = &atlas.pages[0];
+        assert_eq!(page.1, [6, 6]);
+
+        // the image sits 2 pixels in, its edges repeated out to the corners
+        let rows = (0..6)
+            .map(|y| (0..6).map(|x| pixel(page, [x, y])[0]).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            rows,
+            [
+                [1, 1, 1, 2, 2, 2],
+                [1, 1, 1, 2, 2, 2],
+                [1, 1, 1, 2, 2, 2],
+                [3, 3, 3, 4, 4, 4],
+                [3, 3, 3, 4, 4, 4],
+                [3, 3, 3, 4, 4, 4],
+            ]
+        );
+        assert_eq!(region(&atlas, "quad").uv, [[2.0 / 6.0; 2], [4.0 / 6.0; 2]]);
+    }
+
+    #[test]
+    fn pads_images_with_transparent_pixels() {
+        let mut builder = AtlasBuilder::new(16).with_padding(2).with_extrude(0);
+        builder
+            .add_pixels("a", solid([255; 4], [2, 2]), [2, 2])
+            .unwrap();
+        builder
+            .add_pixels("b", solid([128; 4], [2, 2]), [2, 2])
+            .unwrap();
+        let atlas = builder.pack().unwrap();
+
+        let page = &atlas.pages[0];
+        assert_eq!(page.1, [6, 2]);
+
+        let row = (0..6).map(|x| pixel(page, [x, 0])).collect::<Vec<_>>();
+        assert_eq!(
+            row,
+            [[255; 4], [255; 4], [0; 4], [0; 4], [128; 4], [128; 4]]
+        );
+    }
+
+    #[test]
+    fn refuses_bad_images() {
+        let mut builder = AtlasBuilder::new(8);
+        assert!(matches!(
+            builder.add_pixels("short", vec![0; 12], [2, 2]),
+            Err(AtlasError::Invalid(_))
+        ));
+
+        // 7 pixels and a 1 pixel border on each side do not fit
+        builder
+            .add_pixels("big", solid([255; 4], [7, 7]), [7, 7])
+            .unwrap();
+        assert!(matches!(
+            builder.pack(),
+            Err(AtlasError::TooLarge { size: [7, 7], .. })
+        ));
+    }
+
+    #[test]
+    fn replaces_images_added_again() {
+        let mut builder = AtlasBuilder::new(16).with_extrude(0);
+        builder
+            .add_pixels("a", solid([255; 4], [2, 2]), [2, 2])
+            .unwrap();
+        builder
+            .add_pixels("a", solid([9; 4], [1, 1]), [1, 1])
+            .unwrap();
+        let atlas = builder.pack().unwrap();
+
+        assert_eq!(atlas.regions.len(), 1);
+        assert_eq!(region(&atlas, "a").size, [1, 1]);
+        assert_eq!(pixel(&atlas.pages[0], [0, 0]), [9; 4]);
+    }
+}
@@ -0,0 +1,189 @@
+//! Sprite sheet JSON as exported by Aseprite and TexturePacker.
+
+use serde::de::{MapAccess, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer};
+
+use std::fmt;
+
+use super::AtlasError;
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub struct SheetRect {
+    pub x: u32,
+    pub y: u32,
+    pub w: u32,
+    pub h: u32,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub struct SheetSize {
+    pub w: u32,
+    pub h: u32,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SheetFrame {
+    pub frame: SheetRect,
+    #[serde(default)]
+    pub rotated: bool,
+    #[serde(default)]
+    pub trimmed: bool,
+    pub sprite_source_size: Option<SheetRect>,
+    pub source_size: Option<SheetSize>,
+    /// Milliseconds the frame is shown for. Aseprite only.
+    pub duration: Option<u32>,
+}
+
+impl SheetFrame {
+    /// True if trimming cut transparent pixels off the source image, so the
+    /// frame would have to be placed within a larger rectangle.
+    pub fn is_cropped(&self) -> bool {
+        let full = [self.frame.w, self.frame.h];
+        let source = self
+            .sprite_source_size
+            .map_or(([0, 0], full), |rect| ([rect.x, rect.y], [rect.w, rect.h]));
+        let source_size = self.source_size.map_or(full, |size| [size.w, size.h]);
+
+        self.trimmed && (source != ([0, 0], full) || source_size != full)
+    }
+}
+
+/// Frames in the order of the file, from either the hash layout (an object
+/// keyed by name) or the array layout (a list of frames with a filename).
+#[derive(Clone, Debug, Default)]
+pub struct SheetFrames(pub Vec<(String, SheetFrame)>);
+
+impl<'de> Deserialize<'de> for SheetFrames {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<SheetFrames, D::Error> {
+        #[derive(Deserialize)]
+        struct NamedFrame {
+            filename: String,
+            #[serde(flatten)]
+            frame: SheetFrame,
+        }
+
+        struct FramesVisitor;
+
+        impl<'de> Visitor<'de> for FramesVisitor {
+            type Value = SheetFrames;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "a map or a list of frames")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<SheetFrames, A::Error> {
+                let mut frames = Vec::new();
+                while let Some(entry) = map.next_entry::<String, SheetFrame>()? {
+                    frames.push(entry);
+                }
+                Ok(SheetFrames(frames))
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<SheetFrames, A::Error> {
+                let mut frames = Vec::new();
+                while let Some(named) = seq.next_element::<NamedFrame>()? {
+                    frames.push((named.filename, named.frame));
+                }
+                Ok(SheetFrames(frames))
+            }
+        }
+
+        deserializer.deserialize_any(FramesVisitor)
+    }
+}
+
+/// A named range of frames. Aseprite only.
+#[derive(Clone, Debug, Deserialize)]
+pub struct SheetTag {
+    pub name: String,
+    pub from: usize,
+    pub to: usize,
+    /// "forward", "reverse", "pingpong" or "pingpong_reverse".
+    #[serde(default)]
+    pub direction: String,
+    /// How many times to play the range, none meaning forever. Newer
+    /// Aseprite versions only.
+    pub repeat: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SheetMeta {
+    /// Sheet image, relative to the JSON file.
+    pub image: String,
+    pub size: Option<SheetSize>,
+    #[serde(default)]
+    pub frame_tags: Vec<SheetTag>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct SheetJson {
+    pub frames: SheetFrames,
+    pub meta: SheetMeta,
+}
+
+impl SheetJson {
+    pub fn load(path: &str) -> Result<SheetJson, AtlasError> {
+        let json = std::fs::read_to_string(path)?;
+        SheetJson::from_json(&json)
+    }
+
+    pub fn from_json(json: &str) -> Result<SheetJson, AtlasError> {
+        Ok(serde_json::from_str(json)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(json: &str) -> SheetFrame {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn reads_hash_and_array_layouts() {
+        let hash = r#"{
+            "frames": {
+                "b": { "frame": { "x": 0, "y": 0, "w": 8, "h": 8 } },
+                "a": { "frame": { "x": 8, "y": 0, "w": 8, "h": 8 }, "duration": 100 }
+            },
+            "meta": { "image": "sheet.png" }
+        }"#;
+        let array = r#"{
+            "frames": [
+                { "filename": "b", "frame": { "x": 0, "y": 0, "w": 8, "h": 8 } },
+                { "filename": "a", "frame": { "x": 8, "y": 0, "w": 8, "h": 8 }, "duration": 100 }
+            ],
+            "meta": { "image": "sheet.png" }
+        }"#;
+
+        for json in [hash, array] {
+            let sheet = SheetJson::from_json(json).unwrap();
+            let names = sheet.frames.0.iter().map(|(name, _)| name.as_str());
+            assert_eq!(names.collect::<Vec<_>>(), ["b", "a"]);
+            assert_eq!(sheet.frames.0[1].1.duration, Some(100));
+        }
+    }
+
+    #[test]
+    fn trimmed_frames_are_cropped_only_if_pixels_were_cut() {
+        let untouched = frame(
+            r#"{ "frame": { "x": 0, "y": 0, "w": 8, "h": 8 }, "trimmed": true,
+                 "spriteSourceSize": { "x": 0, "y": 0, "w": 8, "h": 8 },
+                 "sourceSize": { "w": 8, "h": 8 } }"#,
+        );
+        assert!(!untouched.is_cropped());
+
+        let cropped = frame(
+            r#"{ "frame": { "x": 0, "y": 0, "w": 6, "h": 8 }, "trimmed": true,
+                 "spriteSourceSize": { "x": 2, "y": 0, "w": 6, "h": 8 },
+                 "sourceSize": { "w": 8, "h": 8 } }"#,
+        );
+        assert!(cropped.is_cropped());
+
+        let untrimmed = frame(r#"{ "frame": { "x": 0, "y": 0, "w": 8, "h": 8 } }"#);
+        assert!(!untrimmed.is_cropped());
+    }
+}
@@ -0,0 +1,118 @@
+use vulkano::format::Format;
+use vulkano::image::view::{ImageView, ImageViewAbstract};
+use vulkano::image::{AttachmentImage, SampleCount};
+use vulkano::memory::allocator::StandardMemoryAllocator;
+use vulkano::pipeline::graphics::viewport::Viewport;
+use vulkano::render_pass::{Framebuffer, FramebufferCreateInfo, RenderPass};
+
+use std::sync::Arc;
+
+/// Framebuffers around a set of final images, along with the G-buffer and
+/// depth images they share. Windows and render targets are drawn through
+/// these.
+pub(super) struct Attachments {
+    pub framebuffers: Vec<Arc<Framebuffer>>,
+    pub color_buffer: Arc<ImageView<AttachmentImage>>,
+    pub viewport: Viewport,
+}
+
+impl Attachments {
+    /// Creates one framebuffer per final image. Every final image must have
+    /// the given dimensions and the render pass' final format.
+    pub fn new(
+        memory_allocator: &StandardMemoryAllocator,
+        final_views: Vec<Arc<dyn ImageViewAbstract>>,
+        dimensions: [u32; 2],
+        final_format: Format,
+        render_pass: Arc<RenderPass>,
+        samples: SampleCount,
+    ) -> Attachments {
+        let depth_buffer = ImageView::new_default(
+            AttachmentImage::transient_multisampled(
+                memory_allocator,
+                dimensions,
+                samples,
+                Format::D16_UNORM,
+            )
+            .unwrap(),
+        )
+        .unwrap();
+
+        // lighting reads this one. with multisampling it is the resolve
+        // target of the multisampled G-buffer
+        let color_buffer = ImageView::new_default(
+            AttachmentImage::transient_input_attachment(
+                memory_allocator,
+                dimensions,
+                Format::A2B10G10R10_UNORM_PACK32,
+            )
+            .unwrap(),
+        )
+        .unwrap();
+
+        // multisampled G-buffer color, and the multisampled image lighting
+        // and translucency render into before the final resolve
+        let multisampled_buffers = if samples == SampleCount::Sample1 {
+            None
+        } else {
+            let color_ms_buffer = ImageView::new_default(
+                AttachmentImage::transient_multisampled(
+                    memory_allocator,
+                    dimensions,
+                    samples,
+                    Format::A2B10G10R10_UNORM_PACK32,
+                )
+                .unwrap(),
+            )
+            .unwrap();
+
+            let lit_ms_buffer = ImageView::new_default(
+                AttachmentImage::transient_multisampled(
+                    memory_allocator,
+                    dimensions,
+                    samples,
+                    final_format,
+                )
+                .unwrap(),
+            )
+            .unwrap();
+
+            Some((color_ms_buffer, lit_ms_buffer))
+        };
+
+        let framebuffers = final_views
+            .into_iter()
+            .map(|view| {
+                let attachments: Vec<Arc<dyn ImageViewAbstract>> = match &multisampled_buffers {
+                    Some((color_ms_buffer, lit_ms_buffer)) => vec![
+                        view,
+                        color_ms_buffer.clone(),
+                        color_buffer.clone(),
+                        depth_buffer.clone(),
+                        lit_ms_buffer.clone(),
+                    ],
+                    None => vec![view, color_buffer.clone(), depth_buffer.clone()],
+                };
+
+                Framebuffer::new(
+                    render_pass.clone(),
+                    FramebufferCreateInfo {
+                        attachments,
+                        ..Default::default()
+                    },
+                )
+                .unwrap()
+            })
+            .collect::<Vec<_>>();
+
+        Attachments {
+            framebuffers,
+            color_buffer,
+            viewport: Viewport {
+                origin: [0.0, 0.0],
+                dimensions: [dimensions[0] as f32, dimensions[1] as f32],
+                depth_range: 0.0..1.0,
+            },
+        }
+    }
+}
@@ -0,0 +1,61 @@
+use nalgebra_glm::{self as glm, TMat4};
+
+/// View onto the world. Everything drawn is transformed by the inverse of the
+/// camera, so moving the camera right moves the scene left.
+/// * The default camera leaves world coordinates equal to normalized device
+///   coordinates.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Camera2D {
+    /// World position shown at the center of the view.
+    pub position: [f32; 2],
+    /// Counter-clockwise rotation in radians.
+    pub rotation: f32,
+    /// Magnification. 2.0 shows half as much of the world in each direction.
+    pub zoom: f32,
+}
+
+impl Default for Camera2D {
+    fn default() -> Camera2D {
+        Camera2D {
+            position: [0.0, 0.0],
+            rotation: 0.0,
+            zoom: 1.0,
+        }
+    }
+}
+
+impl Camera2D {
+    /// Matrix taking world coordinates to normalized device coordinates.
+    pub fn view_matrix(&self) -> TMat4<f32> {
+        glm::scaling(&glm::vec3(self.zoom, self.zoom, 1.0))
+            * glm::rotation(-self.rotation, &glm::vec3(0.0, 0.0, 1.0))
+            * glm::translation(&glm::vec3(-self.position[0], -self.position[1], 0.0))
+    }
+
+    /// Converts normalized device coordinates back into world coordinates.
+    pub fn view_to_world(&self, position: [f32; 2]) -> [f32; 2] {
+        let world =
+            glm::inverse(&self.view_matrix()) * glm::vec4(position[0], position[1], 0.0, 1.0);
+        [world.x, world.y]
+    }
+
+    /// Converts world coordinates into normalized device coordinates.
+    pub fn world_to_view(&self, position: [f32; 2]) -> [f32; 2] {
+        let view = self.view_matrix() * glm::vec4(position[0], position[1], 0.0, 1.0);
+        [view.x, view.y]
+    }
+
+    /// Smallest world-aligned rectangle containing everything the camera
+    /// shows, as its minimum and maximum corners.
+    pub fn visible_bounds(&self) -> [[f32; 2]; 2] {
+        let corners = [[-1.0, -1.0], [1.0, -1.0], [1.0, 1.0], [-1.0, 1.0]]
+            .map(|corner| self.view_to_world(corner));
+
+        let mut bounds = [corners[0], corners[0]];
+        for [x, y] in corners {
+            bounds[0] = [bounds[0][0].min(x), bounds[0][1].min(y)];
+            bounds[1] = [bounds[1][0].max(x), bounds[1][1].max(y)];
+        }
+        bounds
+    }
+}
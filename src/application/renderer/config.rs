@@ -0,0 +1,27 @@
+use vulkano::image::SampleCount;
+
+/// Settings the renderer is created with. Anything that can change at runtime
+/// has a setter on `Renderer` instead.
+#[derive(Clone, Debug)]
+pub struct RendererConfig {
+    /// Number of frames the CPU may record ahead of the GPU. Each frame in
+    /// flight owns its own uniform buffers and descriptor sets.
+    pub frames_in_flight: usize,
+    /// MSAA sample count for the deferred G-buffer and depth. Falls back to
+    /// the highest count the device supports if this one isn't.
+    pub samples: SampleCount,
+    /// Fixed resolution to render the scene at, e.g. [320, 180] for pixel
+    /// art. The result is upscaled to the window by the largest integer
+    /// factor that fits and letterboxed. None renders at window resolution.
+    pub virtual_resolution: Option<[u32; 2]>,
+}
+
+impl Default for RendererConfig {
+    fn default() -> RendererConfig {
+        RendererConfig {
+            frames_in_flight: 2,
+            samples: SampleCount::Sample4,
+            virtual_resolution: None,
+        }
+    }
+}
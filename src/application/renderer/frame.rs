@@ -0,0 +1,224 @@
+use vulkano::buffer::{BufferUsage, CpuAccessibleBuffer, CpuBufferPool};
+use vulkano::descriptor_set::allocator::StandardDescriptorSetAllocator;
+use vulkano::device::Device;
+use vulkano::memory::allocator::StandardMemoryAllocator;
+use vulkano::sync::{FenceSignalFuture, GpuFuture};
+
+use nalgebra_glm::TMat4;
+
+use std::sync::Arc;
+
+use super::camera::Camera2D;
+use super::renderables::lights::{DirectionalLight, PointLight};
+use super::renderables::vertices::Vertex2D;
+use super::renderables::{ColorRenderable, Renderable};
+use super::shaders::*;
+use super::target::Target;
+use super::texture::Texture;
+use super::Renderer;
+use crate::application::scene::{world_directional_light, world_point_light, Attachment, Scene};
+
+/// Per-frame resources. A frame in flight is only reused once the fence of
+/// its previous submission has signaled, so nothing here can be written
+/// while the GPU is still reading it.
+pub struct FrameResources {
+    pub fence: Option<Arc<FenceSignalFuture<Box<dyn GpuFuture + Send + Sync>>>>,
+    pub descriptor_set_allocator: StandardDescriptorSetAllocator,
+    pub model_uniform_buffer: CpuBufferPool<deferred_vert::ty::ModelData>,
+    pub ambient_buffer: Arc<CpuAccessibleBuffer<ambient_frag::ty::AmbientData>>,
+    pub point_buffer: CpuBufferPool<point_frag::ty::PointData>,
+    pub directional_buffer: CpuBufferPool<directional_frag::ty::DirectionalData>,
+    pub light_buffer: CpuBufferPool<translucent_frag::ty::LightData>,
+}
+
+impl FrameResources {
+    pub fn new(
+        device: Arc<Device>,
+        memory_allocator: Arc<StandardMemoryAllocator>,
+        ambient: ambient_frag::ty::AmbientData,
+    ) -> FrameResources {
+        let ambient_buffer = CpuAccessibleBuffer::from_data(
+            &memory_allocator,
+            BufferUsage {
+                uniform_buffer: true,
+                ..BufferUsage::empty()
+            },
+            false,
+            ambient,
+        )
+        .unwrap();
+
+        FrameResources {
+            fence: None,
+            descriptor_set_allocator: StandardDescriptorSetAllocator::new(device),
+            model_uniform_buffer: CpuBufferPool::uniform_buffer(memory_allocator.clone()),
+            ambient_buffer,
+            point_buffer: CpuBufferPool::uniform_buffer(memory_allocator.clone()),
+            directional_buffer: CpuBufferPool::uniform_buffer(memory_allocator.clone()),
+            light_buffer: CpuBufferPool::uniform_buffer(memory_allocator),
+        }
+    }
+
+    /// Blocks until the GPU has finished the last submission that used this
+    /// frame's resources.
+    pub fn wait(&mut self) {
+        if let Some(fence) = self.fence.take() {
+            if let Err(err) = fence.wait(None) {
+                println!("Failed to wait for frame fence: {:?}", err);
+            }
+        }
+    }
+}
+
+/// Drawing interface for one frame. Renderables are queued in any order and
+/// lights are collected, then drawn after the ambient stage once the frame is
+/// submitted, so callers never deal with the renderer's stages.
+pub struct Frame<'a> {
+    renderer: &'a mut Renderer,
+    alpha: f32,
+    directional_lights: Vec<DirectionalLight>,
+    point_lights: Vec<PointLight>,
+}
+
+impl<'a> Frame<'a> {
+    /// Wraps a renderer that has been started. `alpha` is the interpolation
+    /// factor between the previous and current simulation state.
+    pub(crate) fn new(renderer: &'a mut Renderer, alpha: f32) -> Frame<'a> {
+        Frame {
+            renderer,
+            alpha,
+            directional_lights: Vec::new(),
+            point_lights: Vec::new(),
+        }
+    }
+
+    /// How far this frame is between the previous and the current fixed
+    /// update, from 0 to 1. Blend simulation state by it for smooth motion.
+    pub fn alpha(&self) -> f32 {
+        self.alpha
+    }
+
+    pub fn draw(&mut self, model: &dyn Renderable) {
+        self.renderer.draw(model);
+    }
+
+    pub fn color_draw(&mut self, model: &dyn ColorRenderable) {
+        self.renderer.color_draw(model);
+    }
+
+    pub fn draw_with_matrix(&mut self, model: &dyn Renderable, matrix: TMat4<f32>) {
+        self.renderer.draw_with_matrix(model, matrix);
+    }
+
+    pub fn color_draw_with_matrix(&mut self, model: &dyn ColorRenderable, matrix: TMat4<f32>) {
+        self.renderer.color_draw_with_matrix(model, matrix);
+    }
+
+    /// Queues a renderable over the view, unlit and ignoring the camera. See
+    /// `Renderer::draw_overlay`.
+    pub fn draw_overlay(&mut self, model: &dyn Renderable) {
+        self.renderer.draw_overlay(model);
+    }
+
+    pub fn draw_overlay_with_matrix(&mut self, model: &dyn Renderable, matrix: TMat4<f32>) {
+        self.renderer.draw_overlay_with_matrix(model, matrix);
+    }
+
+    /// Matrix from pixels of the frame's view to its normalized device
+    /// coordinates, for overlays.
+    pub fn pixel_matrix(&self) -> TMat4<f32> {
+        self.renderer.view_pixel_matrix()
+    }
+
+    pub fn directional(&mut self, light: DirectionalLight) {
+        self.directional_lights.push(light);
+    }
+
+    pub fn point(&mut self, light: PointLight) {
+        self.point_lights.push(light);
+    }
+
+    /// Queues a scene's renderables with their world matrices and collects
+    /// its lights.
+    pub fn draw_scene(&mut self, scene: &mut Scene) {
+        scene.update();
+
+        for id in scene.visible_nodes() {
+            let world = scene.world_matrix(id);
+
+            for attachment in scene.attachments(id) {
+                match attachment {
+                    Attachment::Renderable(model) => {
+                        self.draw_with_matrix(model.as_ref(), world * model.matrix())
+                    }
+                    Attachment::ColorRenderable(model) => {
+                        self.color_draw_with_matrix(model.as_ref(), world * model.matrix())
+                    }
+                    Attachment::PointLight(light) => self.point(world_point_light(&world, light)),
+                    Attachment::DirectionalLight(light) => {
+                        self.directional(world_directional_light(&world, light))
+                    }
+                }
+            }
+        }
+    }
+
+    /// The window or render target this frame is drawn to.
+    pub fn target(&self) -> Target {
+        self.renderer.target()
+    }
+
+    /// The view of the target this frame is drawn in, an index into its
+    /// views. Always 0 unless the target is split into several views.
+    pub fn view(&self) -> usize {
+        self.renderer.view()
+    }
+
+    /// Sets the camera renderables queued after this call are viewed through.
+    /// The camera is kept by the frame's view of its window or render target.
+    pub fn set_camera(&mut self, camera: Camera2D) {
+        self.renderer.set_target_camera(camera);
+    }
+
+    /// The camera of the frame's view, as set so far.
+    pub fn camera(&self) -> Camera2D {
+        self.renderer.target_camera()
+    }
+
+    /// Queues textured vertices that are already on the GPU.
+    pub(crate) fn draw_vertex_buffer(
+        &mut self,
+        vertex_buffer: Arc<CpuAccessibleBuffer<[Vertex2D]>>,
+        texture: Arc<Texture>,
+        tint: [f32; 4],
+        matrix: TMat4<f32>,
+        layer: i32,
+        translucent: bool,
+    ) {
+        self.renderer
+            .draw_vertex_buffer(vertex_buffer, texture, tint, matrix, layer, translucent);
+    }
+
+    /// Sets the ambient light, starting with this frame.
+    pub fn set_ambient(&mut self, color: [f32; 3], intensity: f32) {
+        self.renderer.set_ambient(color, intensity);
+    }
+
+    pub fn renderer(&self) -> &Renderer {
+        self.renderer
+    }
+
+    /// Runs the ambient stage and draws the collected lights. The renderer
+    /// is left ready for `finish`.
+    pub(crate) fn submit(self) {
+        self.renderer.ambient();
+
+        for light in &self.directional_lights {
+            self.renderer.directional(light);
+        }
+
+        for light in &self.point_lights {
+            self.renderer.point(light);
+        }
+    }
+}
@@ -0,0 +1,155 @@
+/// Where a fixed render resolution lands inside the window once upscaled.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Letterbox {
+    pub offset: [u32; 2],
+    pub size: [u32; 2],
+}
+
+impl Letterbox {
+    /// Fits `resolution` into `window` with the largest integer scale that
+    /// fits, centered with bars around it.
+    /// * Windows smaller than `resolution` fall back to a fractional downscale
+    ///   so the whole image stays visible.
+    pub fn new(window: [u32; 2], resolution: [u32; 2]) -> Letterbox {
+        let scale_x = window[0] / resolution[0].max(1);
+        let scale_y = window[1] / resolution[1].max(1);
+        let scale = scale_x.min(scale_y);
+
+        let size = if scale >= 1 {
+            [resolution[0] * scale, resolution[1] * scale]
+        } else {
+            let scale = (window[0] as f32 / resolution[0].max(1) as f32)
+                .min(window[1] as f32 / resolution[1].max(1) as f32);
+            [
+                ((resolution[0] as f32 * scale) as u32).max(1),
+                ((resolution[1] as f32 * scale) as u32).max(1),
+            ]
+        };
+
+        Letterbox {
+            offset: [
+                window[0].saturating_sub(size[0]) / 2,
+                window[1].saturating_sub(size[1]) / 2,
+            ],
+            size,
+        }
+    }
+
+    /// Letterbox covering the whole window, for rendering at window resolution.
+    pub fn fill(window: [u32; 2]) -> Letterbox {
+        Letterbox {
+            offset: [0, 0],
+            size: window,
+        }
+    }
+
+    /// Converts a position in window pixels into pixels of `resolution`.
+    /// Returns None if the position falls on the bars.
+    pub fn window_to_render(&self, position: [f64; 2], resolution: [u32; 2]) -> Option<[f32; 2]> {
+        let x = position[0] - self.offset[0] as f64;
+        let y = position[1] - self.offset[1] as f64;
+
+        if x < 0.0 || y < 0.0 || x >= self.size[0] as f64 || y >= self.size[1] as f64 {
+            return None;
+        }
+
+        Some([
+            (x * resolution[0] as f64 / self.size[0] as f64) as f32,
+            (y * resolution[1] as f64 / self.size[1] as f64) as f32,
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RESOLUTION: [u32; 2] = [320, 180];
+
+    #[test]
+    fn exact_multiples_fill_the_window() {
+        for scale in 1..=4 {
+            let window = [320 * scale, 180 * scale];
+            assert_eq!(
+                Letterbox::new(window, RESOLUTION),
+                Letterbox {
+                    offset: [0, 0],
+                    size: window,
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn scale_is_the_largest_integer_that_fits() {
+        // 3x fits both ways, 4x only horizontally
+        assert_eq!(
+            Letterbox::new([1280, 600], RESOLUTION),
+            Letterbox {
+                offset: [160, 30],
+                size: [960, 540],
+            }
+        );
+    }
+
+    #[test]
+    fn odd_leftover_pixels_are_split_between_the_bars() {
+        let letterbox = Letterbox::new([965, 543], RESOLUTION);
+
+        assert_eq!(letterbox.size, [960, 540]);
+        assert_eq!(letterbox.offset, [2, 1]);
+        // the far bars get the extra pixel
+        assert_eq!(965 - letterbox.size[0] - letterbox.offset[0], 3);
+        assert_eq!(543 - letterbox.size[1] - letterbox.offset[1], 2);
+    }
+
+    #[test]
+    fn smaller_windows_downscale_without_vanishing() {
+        assert_eq!(
+            Letterbox::new([160, 120], RESOLUTION),
+            Letterbox {
+                offset: [0, 15],
+                size: [160, 90],
+            }
+        );
+
+        for window in [[1, 1], [0, 0], [319, 179], [1, 500]] {
+            let letterbox = Letterbox::new(window, RESOLUTION);
+            assert!(
+                letterbox.size[0] >= 1 && letterbox.size[1] >= 1,
+                "{:?}",
+                window
+            );
+            assert!(letterbox.size[0] <= window[0].max(1), "{:?}", window);
+            assert!(letterbox.size[1] <= window[1].max(1), "{:?}", window);
+        }
+    }
+
+    #[test]
+    fn window_to_render_maps_the_image_and_skips_the_bars() {
+        let letterbox = Letterbox::new([1000, 600], RESOLUTION);
+        let to_render = |x, y| letterbox.window_to_render([x, y], RESOLUTION);
+
+        assert_eq!(to_render(20.0, 30.0), Some([0.0, 0.0]));
+        assert_eq!(to_render(500.0, 300.0), Some([160.0, 90.0]));
+        let [x, y] = to_render(979.0, 569.0).unwrap();
+        assert!((x - 959.0 / 3.0).abs() < 1e-3 && (y - 539.0 / 3.0).abs() < 1e-3);
+
+        // left, right, top and bottom bars
+        assert_eq!(to_render(19.9, 300.0), None);
+        assert_eq!(to_render(980.0, 300.0), None);
+        assert_eq!(to_render(500.0, 29.9), None);
+        assert_eq!(to_render(500.0, 570.0), None);
+    }
+
+    #[test]
+    fn fill_maps_window_pixels_to_themselves() {
+        let letterbox = Letterbox::fill([640, 480]);
+
+        assert_eq!(
+            letterbox.window_to_render([123.0, 456.0], [640, 480]),
+            Some([123.0, 456.0])
+        );
+        assert_eq!(letterbox.window_to_render([640.0, 0.0], [640, 480]), None);
+    }
+}
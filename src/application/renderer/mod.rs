@@ -1,49 +1,100 @@
-use vulkano::buffer::{BufferUsage, CpuAccessibleBuffer, CpuBufferPool, TypedBufferAccess};
+use vulkano::buffer::{BufferUsage, CpuAccessibleBuffer, TypedBufferAccess};
 use vulkano::command_buffer::allocator::StandardCommandBufferAllocator;
 use vulkano::command_buffer::{
-    AutoCommandBufferBuilder, CommandBufferUsage, PrimaryAutoCommandBuffer, SubpassContents,
+    AutoCommandBufferBuilder, BlitImageInfo, ClearColorImageInfo, CommandBufferUsage,
+    PrimaryAutoCommandBuffer, PrimaryCommandBufferAbstract, SubpassContents,
 };
-use vulkano::descriptor_set::allocator::StandardDescriptorSetAllocator;
 use vulkano::descriptor_set::{PersistentDescriptorSet, WriteDescriptorSet};
-use vulkano::device::physical::PhysicalDeviceType;
+use vulkano::device::physical::{PhysicalDevice, PhysicalDeviceType};
 use vulkano::device::{Device, DeviceCreateInfo, DeviceExtensions, Queue, QueueCreateInfo};
 use vulkano::format::Format;
 use vulkano::image::view::ImageView;
-use vulkano::image::{AttachmentImage, ImageAccess, SwapchainImage};
+use vulkano::image::{ImageDimensions, ImageLayout, ImmutableImage, MipmapsCount, SampleCount};
 use vulkano::instance::{Instance, InstanceCreateInfo};
 use vulkano::memory::allocator::StandardMemoryAllocator;
 use vulkano::pipeline::graphics::color_blend::{
     AttachmentBlend, BlendFactor, BlendOp, ColorBlendState,
 };
-use vulkano::pipeline::graphics::depth_stencil::DepthStencilState;
+use vulkano::pipeline::graphics::depth_stencil::{CompareOp, DepthState, DepthStencilState};
 use vulkano::pipeline::graphics::input_assembly::InputAssemblyState;
+use vulkano::pipeline::graphics::multisample::MultisampleState;
 use vulkano::pipeline::graphics::rasterization::{CullMode, RasterizationState};
 use vulkano::pipeline::graphics::vertex_input::BuffersDefinition;
-use vulkano::pipeline::graphics::viewport::{Viewport, ViewportState};
-use vulkano::pipeline::{GraphicsPipeline, Pipeline};
-use vulkano::render_pass::{Framebuffer, FramebufferCreateInfo, RenderPass, Subpass};
+use vulkano::pipeline::graphics::viewport::{Scissor, Viewport, ViewportState};
+use vulkano::pipeline::{GraphicsPipeline, Pipeline, StateMode};
+use vulkano::render_pass::{
+    AttachmentDescription, AttachmentReference, Framebuffer, LoadOp, RenderPass,
+    RenderPassCreateInfo, StoreOp, Subpass, SubpassDependency, SubpassDescription,
+};
+use vulkano::sampler::{Sampler, SamplerAddressMode, SamplerCreateInfo};
 use vulkano::swapchain::{
-    self, AcquireError, Surface, Swapchain, SwapchainAcquireFuture, SwapchainCreateInfo,
-    SwapchainCreationError, SwapchainPresentInfo,
+    self, AcquireError, PresentMode, SwapchainAcquireFuture, SwapchainPresentInfo,
 };
-use vulkano::sync::GpuFuture;
+use vulkano::sync::{self, AccessFlags, GpuFuture, PipelineStages};
 use vulkano::VulkanLibrary;
 
-use winit::event_loop::EventLoop;
-use winit::window::{Window, WindowBuilder};
+use nalgebra_glm::TMat4;
+
+use winit::dpi::PhysicalPosition;
+use winit::event_loop::EventLoopWindowTarget;
+use winit::window::{Window, WindowId};
 
 // VkSurfaceBuild allows winit to build a vulkan surface directly
 use vulkano_win::{required_extensions, VkSurfaceBuild};
 
+use std::collections::HashMap;
 use std::sync::Arc;
 
+use super::scene::Scene;
+use super::window::{FullscreenMode, WindowConfig};
+
 mod shaders;
 use shaders::*;
 
+pub mod camera;
+use camera::Camera2D;
+
+pub mod config;
+use config::RendererConfig;
+
+mod frame;
+pub use frame::Frame;
+use frame::FrameResources;
+
+pub mod letterbox;
+
+mod queue;
+use queue::{DrawPass, DrawQueue, QueuedDraw, QueuedVertices};
+
+mod attachments;
+use attachments::Attachments;
+
+pub mod surface;
+use surface::WindowSurface;
+
+pub mod target;
+use target::{RenderTarget, RenderTargetId, Target};
+
+pub mod view;
+use view::View;
+
+pub mod texture;
+use texture::{has_partial_alpha, load_png, Texture};
+
+pub mod atlas;
+
+pub mod tilemap;
+
+pub mod text;
+
 pub mod renderables;
 use renderables::lights::*;
 use renderables::vertices::*;
 
+/// Point lights the translucent shaders evaluate per view, as sized in
+/// translucent.frag and textured_translucent.frag.
+const MAX_TRANSLUCENT_POINT_LIGHTS: usize = 64;
+
 // render stage allows renderer to function as state machine
 enum RenderStage {
     Stopped,
@@ -51,31 +102,43 @@ enum RenderStage {
     Ambient,
     Point,
     Directional,
-    RedrawNeeded,
 }
 
 pub struct Renderer {
-    surface: Arc<Surface>,
     pub device: Arc<Device>,
     queue: Arc<Queue>,
-    swapchain: Arc<Swapchain>,
     memory_allocator: Arc<StandardMemoryAllocator>,
-    descriptor_set_allocator: StandardDescriptorSetAllocator,
     command_buffer_allocator: StandardCommandBufferAllocator,
     render_pass: Arc<RenderPass>,
+    samples: SampleCount,
     deferred_pipeline: PipelineInfo,
     ambient_pipeline: PipelineInfo,
     point_pipeline: PipelineInfo,
     directional_pipeline: PipelineInfo,
-    model_uniform_buffer: CpuBufferPool<deferred_vert::ty::ModelData>,
-    ambient_buffer: Arc<CpuAccessibleBuffer<ambient_frag::ty::AmbientData>>,
-    point_buffer: CpuBufferPool<point_frag::ty::PointData>,
-    directional_buffer: CpuBufferPool<directional_frag::ty::DirectionalData>,
+    translucent_pipeline: PipelineInfo,
+    textured_pipeline: PipelineInfo,
+    textured_translucent_pipeline: PipelineInfo,
+    sampler: Arc<Sampler>,
+    frames: Vec<FrameResources>,
+    frame_index: usize,
+    previous_frame_index: Option<usize>,
+    ambient: ambient_frag::ty::AmbientData,
+    // lights of the view being drawn, for translucent geometry: ambient and
+    // directional light summed, as they light everything evenly, and the
+    // point lights
+    frame_light: [f32; 3],
+    frame_points: Vec<PointLight>,
     screen_vertices: Arc<CpuAccessibleBuffer<[BasicVertex2D]>>,
-    viewport: Viewport,
-    framebuffers: Vec<Arc<Framebuffer>>,
-    color_buffer: Arc<ImageView<AttachmentImage>>,
+    windows: HashMap<WindowId, WindowSurface>,
+    primary: WindowId,
+    render_targets: HashMap<RenderTargetId, RenderTarget>,
+    next_render_target: usize,
+    // where the frame being recorded is drawn to, and in which view
+    target: Target,
+    view: usize,
+    framebuffer: Option<Arc<Framebuffer>>,
     commands: Option<AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>>,
+    draw_queue: DrawQueue,
     image_index: u32,
     acquire_future: Option<SwapchainAcquireFuture>,
     render_stage: RenderStage,
@@ -83,7 +146,11 @@ pub struct Renderer {
 
 impl Renderer {
     /// Initializes a new Vulkan program and returns a Renderer instance.
-    pub fn new(event_loop: &EventLoop<()>) -> Renderer {
+    pub fn new(
+        event_loop: &EventLoopWindowTarget<()>,
+        window_config: &WindowConfig,
+        config: RendererConfig,
+    ) -> Renderer {
         // vulkan instance. vulkano takes care of most of the configuration
         let instance = {
             let library = VulkanLibrary::new().unwrap();
@@ -102,7 +169,8 @@ impl Renderer {
         };
 
         // surface to render to. provided by winit, helper function by vulkano
-        let surface = WindowBuilder::new()
+        let surface = window_config
+            .builder(event_loop)
             .build_vk_surface(event_loop, instance.clone())
             .unwrap();
 
@@ -162,45 +230,15 @@ impl Renderer {
 
         let queue = queues.next().unwrap();
 
-        let (swapchain, images) = {
-            let caps = device
-                .physical_device()
-                .surface_capabilities(&surface, Default::default())
-                .unwrap();
-
-            let image_usage = caps.supported_usage_flags;
-            let composite_alpha = caps.supported_composite_alpha.iter().next().unwrap();
-
-            let image_format = Some(
-                device
-                    .physical_device()
-                    .surface_formats(&surface, Default::default())
-                    .unwrap()[0]
-                    .0,
-            );
-
-            let window = surface.object().unwrap().downcast_ref::<Window>().unwrap();
-            let image_extent: [u32; 2] = window.inner_size().into();
-
-            Swapchain::new(
-                device.clone(),
-                surface.clone(),
-                SwapchainCreateInfo {
-                    min_image_count: caps.min_image_count,
-                    image_format,
-                    image_extent,
-                    image_usage,
-                    composite_alpha,
-                    ..Default::default()
-                },
-            )
-            .unwrap()
-        };
-
-        println!("{}", images.len());
+        // every window shares the render pass and pipelines, so they all
+        // render to the format picked for the first one
+        let image_format = device
+            .physical_device()
+            .surface_formats(&surface, Default::default())
+            .unwrap()[0]
+            .0;
 
         let memory_allocator = Arc::new(StandardMemoryAllocator::new_default(device.clone()));
-        let descriptor_set_allocator = StandardDescriptorSetAllocator::new(device.clone());
         let command_buffer_allocator =
             StandardCommandBufferAllocator::new(device.clone(), Default::default());
 
@@ -212,53 +250,43 @@ impl Renderer {
         let point_frag = point_frag::load(device.clone()).unwrap();
         let directional_vert = directional_vert::load(device.clone()).unwrap();
         let directional_frag = directional_frag::load(device.clone()).unwrap();
+        let textured_vert = textured_vert::load(device.clone()).unwrap();
+        let textured_frag = textured_frag::load(device.clone()).unwrap();
+        let translucent_frag = translucent_frag::load(device.clone()).unwrap();
+        let textured_translucent_frag = textured_translucent_frag::load(device.clone()).unwrap();
 
-        let render_pass = vulkano::ordered_passes_renderpass!(device.clone(),
-            attachments: {
-                final_color: {
-                    load: Clear,
-                    store: Store,
-                    format: swapchain.image_format(),
-                    samples: 1,
-                },
-                color: {
-                    load: Clear,
-                    store: DontCare,
-                    format: Format::A2B10G10R10_UNORM_PACK32,
-                    samples: 1,
-                },
-                depth: {
-                    load: Clear,
-                    store: DontCare,
-                    format: Format::D16_UNORM,
-                    samples: 1,
-                }
-            },
-            passes: [
-                {
-                    color: [color],
-                    depth_stencil: {depth},
-                    input: []
-                },
-                {
-                    color: [final_color],
-                    depth_stencil: {},
-                    input: [color]
-                }
-            ]
-        )
-        .unwrap();
+        let samples = Renderer::supported_samples(device.physical_device(), config.samples);
+        println!("Using {:?} MSAA samples", samples);
+
+        let render_pass = Renderer::create_render_pass(device.clone(), image_format, samples);
 
         let deferred_pass = Subpass::from(render_pass.clone(), 0).unwrap();
         let lighting_pass = Subpass::from(render_pass.clone(), 1).unwrap();
+        let translucent_pass = Subpass::from(render_pass.clone(), 2).unwrap();
+
+        // equal depths pass so later draws on the same layer end up on top
+        let layered_depth_test = |write_enable: bool| DepthStencilState {
+            depth: Some(DepthState {
+                enable_dynamic: false,
+                compare_op: StateMode::Fixed(CompareOp::LessOrEqual),
+                write_enable: StateMode::Fixed(write_enable),
+            }),
+            ..Default::default()
+        };
+
+        let multisample_state = MultisampleState {
+            rasterization_samples: samples,
+            ..Default::default()
+        };
 
         let deferred_pipeline = GraphicsPipeline::start()
             .vertex_input_state(BuffersDefinition::new().vertex::<ColorVertex2D>())
             .vertex_shader(deferred_vert.entry_point("main").unwrap(), ())
             .input_assembly_state(InputAssemblyState::new())
-            .viewport_state(ViewportState::viewport_dynamic_scissor_irrelevant())
+            .viewport_state(ViewportState::viewport_dynamic_scissor_dynamic(1))
             .fragment_shader(deferred_frag.entry_point("main").unwrap(), ())
-            .depth_stencil_state(DepthStencilState::simple_depth_test())
+            .depth_stencil_state(layered_depth_test(true))
+            .multisample_state(multisample_state.clone())
             .rasterization_state(RasterizationState::new().cull_mode(CullMode::Back))
             .render_pass(deferred_pass.clone())
             .build(device.clone())
@@ -268,7 +296,7 @@ impl Renderer {
             .vertex_input_state(BuffersDefinition::new().vertex::<BasicVertex2D>())
             .vertex_shader(ambient_vert.entry_point("main").unwrap(), ())
             .input_assembly_state(InputAssemblyState::new())
-            .viewport_state(ViewportState::viewport_dynamic_scissor_irrelevant())
+            .viewport_state(ViewportState::viewport_dynamic_scissor_dynamic(1))
             .fragment_shader(ambient_frag.entry_point("main").unwrap(), ())
             .color_blend_state(
                 ColorBlendState::new(lighting_pass.num_color_attachments()).blend(
@@ -282,6 +310,7 @@ impl Renderer {
                     },
                 ),
             )
+            .multisample_state(multisample_state.clone())
             .rasterization_state(RasterizationState::new().cull_mode(CullMode::Back))
             .render_pass(lighting_pass.clone())
             .build(device.clone())
@@ -291,7 +320,7 @@ impl Renderer {
             .vertex_input_state(BuffersDefinition::new().vertex::<BasicVertex2D>())
             .vertex_shader(point_vert.entry_point("main").unwrap(), ())
             .input_assembly_state(InputAssemblyState::new())
-            .viewport_state(ViewportState::viewport_dynamic_scissor_irrelevant())
+            .viewport_state(ViewportState::viewport_dynamic_scissor_dynamic(1))
             .fragment_shader(point_frag.entry_point("main").unwrap(), ())
             .color_blend_state(
                 ColorBlendState::new(lighting_pass.num_color_attachments()).blend(
@@ -305,6 +334,7 @@ impl Renderer {
                     },
                 ),
             )
+            .multisample_state(multisample_state.clone())
             .rasterization_state(RasterizationState::new().cull_mode(CullMode::Back))
             .render_pass(lighting_pass.clone())
             .build(device.clone())
@@ -314,7 +344,7 @@ impl Renderer {
             .vertex_input_state(BuffersDefinition::new().vertex::<BasicVertex2D>())
             .vertex_shader(directional_vert.entry_point("main").unwrap(), ())
             .input_assembly_state(InputAssemblyState::new())
-            .viewport_state(ViewportState::viewport_dynamic_scissor_irrelevant())
+            .viewport_state(ViewportState::viewport_dynamic_scissor_dynamic(1))
             .fragment_shader(directional_frag.entry_point("main").unwrap(), ())
             .color_blend_state(
                 ColorBlendState::new(lighting_pass.num_color_attachments()).blend(
@@ -328,11 +358,59 @@ impl Renderer {
                     },
                 ),
             )
+            .multisample_state(multisample_state.clone())
             .rasterization_state(RasterizationState::new().cull_mode(CullMode::Back))
             .render_pass(lighting_pass.clone())
             .build(device.clone())
             .unwrap();
 
+        let textured_pipeline = GraphicsPipeline::start()
+            .vertex_input_state(BuffersDefinition::new().vertex::<Vertex2D>())
+            .vertex_shader(textured_vert.entry_point("main").unwrap(), ())
+            .input_assembly_state(InputAssemblyState::new())
+            .viewport_state(ViewportState::viewport_dynamic_scissor_dynamic(1))
+            .fragment_shader(textured_frag.entry_point("main").unwrap(), ())
+            .depth_stencil_state(layered_depth_test(true))
+            .multisample_state(multisample_state.clone())
+            .rasterization_state(RasterizationState::new().cull_mode(CullMode::Back))
+            .render_pass(deferred_pass.clone())
+            .build(device.clone())
+            .unwrap();
+
+        // translucent geometry is blended over the lit scene, depth tested
+        // against the opaque geometry but without occluding anything itself
+        let translucent_pipeline = GraphicsPipeline::start()
+            .vertex_input_state(BuffersDefinition::new().vertex::<ColorVertex2D>())
+            .vertex_shader(deferred_vert.entry_point("main").unwrap(), ())
+            .input_assembly_state(InputAssemblyState::new())
+            .viewport_state(ViewportState::viewport_dynamic_scissor_dynamic(1))
+            .fragment_shader(translucent_frag.entry_point("main").unwrap(), ())
+            .depth_stencil_state(layered_depth_test(false))
+            .color_blend_state(
+                ColorBlendState::new(translucent_pass.num_color_attachments()).blend_alpha(),
+            )
+            .multisample_state(multisample_state.clone())
+            .rasterization_state(RasterizationState::new().cull_mode(CullMode::Back))
+            .render_pass(translucent_pass.clone())
+            .build(device.clone())
+            .unwrap();
+
+        let textured_translucent_pipeline = GraphicsPipeline::start()
+            .vertex_input_state(BuffersDefinition::new().vertex::<Vertex2D>())
+            .vertex_shader(textured_vert.entry_point("main").unwrap(), ())
+            .input_assembly_state(InputAssemblyState::new())
+            .viewport_state(ViewportState::viewport_dynamic_scissor_dynamic(1))
+            .fragment_shader(textured_translucent_frag.entry_point("main").unwrap(), ())
+            .depth_stencil_state(layered_depth_test(false))
+            .color_blend_state(
+                ColorBlendState::new(translucent_pass.num_color_attachments()).blend_alpha(),
+            )
+            .multisample_state(multisample_state)
+            .rasterization_state(RasterizationState::new().cull_mode(CullMode::Back))
+            .render_pass(translucent_pass.clone())
+            .build(device.clone())
+            .unwrap();
+
         let deferred_pipeline = PipelineInfo {
             vert_path: "src/application/renderer/shaders/shaders/deferred.vert".to_string(),
             frag_path: "src/application/renderer/shaders/shaders/deferred.frag".to_string(),
@@ -357,30 +435,49 @@ impl Renderer {
             pipeline: directional_pipeline,
         };
 
-        // buffers
+        let translucent_pipeline = PipelineInfo {
+            vert_path: "src/application/renderer/shaders/shaders/deferred.vert".to_string(),
+            frag_path: "src/application/renderer/shaders/shaders/translucent.frag".to_string(),
+            pipeline: translucent_pipeline,
+        };
 
-        let model_uniform_buffer: CpuBufferPool<deferred_vert::ty::ModelData> =
-            CpuBufferPool::uniform_buffer(memory_allocator.clone());
+        let textured_pipeline = PipelineInfo {
+            vert_path: "src/application/renderer/shaders/shaders/textured.vert".to_string(),
+            frag_path: "src/application/renderer/shaders/shaders/textured.frag".to_string(),
+            pipeline: textured_pipeline,
+        };
 
-        let ambient_buffer = CpuAccessibleBuffer::from_data(
-            &memory_allocator,
-            BufferUsage {
-                uniform_buffer: true,
-                ..BufferUsage::empty()
-            },
-            false,
-            // default ambient light values
-            ambient_frag::ty::AmbientData {
-                color: [1.0, 1.0, 1.0],
-                intensity: 0.1,
+        let textured_translucent_pipeline = PipelineInfo {
+            vert_path: "src/application/renderer/shaders/shaders/textured.vert".to_string(),
+            frag_path: "src/application/renderer/shaders/shaders/textured_translucent.frag"
+                .to_string(),
+            pipeline: textured_translucent_pipeline,
+        };
+
+        // nearest filtering keeps pixel art crisp, clamping keeps atlas
+        // neighbours from bleeding in at the edges
+        let sampler = Sampler::new(
+            device.clone(),
+            SamplerCreateInfo {
+                address_mode: [SamplerAddressMode::ClampToEdge; 3],
+                ..Default::default()
             },
         )
         .unwrap();
 
-        let point_buffer: CpuBufferPool<point_frag::ty::PointData> =
-            CpuBufferPool::uniform_buffer(memory_allocator.clone());
-        let directional_buffer: CpuBufferPool<directional_frag::ty::DirectionalData> =
-            CpuBufferPool::uniform_buffer(memory_allocator.clone());
+        // buffers
+
+        // default ambient light values
+        let ambient = ambient_frag::ty::AmbientData {
+            color: [1.0, 1.0, 1.0],
+            intensity: 0.1,
+        };
+
+        // each frame in flight gets its own uniform buffers and descriptor
+        // allocator so recording a frame never touches resources in use
+        let frames = (0..config.frames_in_flight.max(1))
+            .map(|_| FrameResources::new(device.clone(), memory_allocator.clone(), ambient))
+            .collect::<Vec<_>>();
 
         // screen vertices allow fragment shaders to execute without vertex data
 
@@ -395,18 +492,16 @@ impl Renderer {
         )
         .unwrap();
 
-        let mut viewport = Viewport {
-            origin: [0.0, 0.0],
-            dimensions: [0.0, 0.0],
-            depth_range: 0.0..1.0,
-        };
-
-        let (framebuffers, color_buffer) = Renderer::window_size_dependent_setup(
+        let window = WindowSurface::new(
+            device.clone(),
+            surface,
+            image_format,
             &memory_allocator,
-            &images,
             render_pass.clone(),
-            &mut viewport,
+            samples,
+            config.virtual_resolution,
         );
+        let primary = window.id();
 
         let commands: Option<AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>> = None;
         let image_index = 0;
@@ -415,45 +510,64 @@ impl Renderer {
         let render_stage = RenderStage::Stopped;
 
         Renderer {
-            surface,
             device,
             queue,
-            swapchain,
             memory_allocator,
-            descriptor_set_allocator,
             command_buffer_allocator,
             render_pass,
+            samples,
             deferred_pipeline,
             ambient_pipeline,
             point_pipeline,
             directional_pipeline,
-            model_uniform_buffer,
-            ambient_buffer,
-            point_buffer,
-            directional_buffer,
+            translucent_pipeline,
+            textured_pipeline,
+            textured_translucent_pipeline,
+            sampler,
+            frames,
+            frame_index: 0,
+            previous_frame_index: None,
+            ambient,
+            frame_light: [0.0; 3],
+            frame_points: Vec::new(),
             screen_vertices,
-            viewport,
-            framebuffers,
-            color_buffer,
+            windows: HashMap::from([(primary, window)]),
+            primary,
+            render_targets: HashMap::new(),
+            next_render_target: 0,
+            target: Target::Window(primary),
+            view: 0,
+            framebuffer: None,
             commands,
+            draw_queue: DrawQueue::default(),
             image_index,
             acquire_future,
             render_stage,
         }
     }
 
-    /// Creates a command buffer and prepares the system for rendering.
+    /// Creates a command buffer and prepares the system for rendering to the
+    /// primary window.
+    /// * Nothing is rendered while the window is minimized; the frame's draw
+    ///   calls do nothing.
     pub fn start(&mut self) {
+        self.start_window(self.primary);
+    }
+
+    /// Creates a command buffer and prepares the system for rendering to the
+    /// given window. Frames are recorded one window at a time: `finish` one
+    /// before starting the next.
+    /// * Nothing is rendered while the window is minimized or if it has been
+    ///   closed; the frame's draw calls do nothing.
+    pub fn start_window(&mut self, id: WindowId) {
+        self.start_target(Target::Window(id));
+    }
+
+    /// Creates a command buffer and prepares the system for rendering to a
+    /// window or a render target.
+    pub fn start_target(&mut self, target: Target) {
         match self.render_stage {
-            RenderStage::Stopped => {
-                self.render_stage = RenderStage::Vertex;
-            }
-            RenderStage::RedrawNeeded => {
-                self.recreate_swapchain();
-                self.render_stage = RenderStage::Stopped;
-                self.commands = None;
-                return;
-            }
+            RenderStage::Stopped => {}
             _ => {
                 self.render_stage = RenderStage::Stopped;
                 self.commands = None;
@@ -461,62 +575,236 @@ impl Renderer {
             }
         }
 
-        let (image_index, suboptimal, acquire_future) =
-            match swapchain::acquire_next_image(self.swapchain.clone(), None) {
-                Ok(r) => r,
-                Err(AcquireError::OutOfDate) => {
-                    self.recreate_swapchain();
-                    return;
-                }
-                Err(err) => {
-                    panic!("{}", err);
-                }
-            };
-
-        if suboptimal {
-            self.recreate_swapchain();
-            return;
-        }
+        self.target = target;
 
-        let clear_values = vec![
-            Some([0.15, 0.15, 0.15, 1.0].into()),
-            Some([0.15, 0.15, 0.15, 1.0].into()),
-            Some(1.0.into()),
-        ];
+        let framebuffer = match target {
+            Target::Window(id) => match self.acquire_image(id) {
+                Some(framebuffer) => framebuffer,
+                None => return,
+            },
+            Target::Texture(id) => match self.render_targets.get(&id) {
+                Some(render_target) => {
+                    self.frames[self.frame_index].wait();
+                    render_target.framebuffer()
+                }
+                None => return,
+            },
+        };
 
-        let mut commands = AutoCommandBufferBuilder::primary(
+        let commands = AutoCommandBufferBuilder::primary(
             &self.command_buffer_allocator,
             self.queue.queue_family_index(),
             CommandBufferUsage::OneTimeSubmit,
         )
         .unwrap();
 
-        commands
+        self.commands = Some(commands);
+        self.framebuffer = Some(framebuffer);
+        self.begin_view(0);
+    }
+
+    /// Finishes the current view and starts drawing the next view of the
+    /// window or render target, with the same stages as a whole frame.
+    /// * Must be called after the ambient stage, in place of `finish`, for
+    ///   every view after the first.
+    pub fn next_view(&mut self) {
+        match self.render_stage {
+            RenderStage::Ambient => {}
+            RenderStage::Directional => {}
+            RenderStage::Point => {}
+            _ => {
+                self.commands = None;
+                self.render_stage = RenderStage::Stopped;
+                return;
+            }
+        }
+
+        if self.view + 1 >= self.target_views().len() {
+            self.commands = None;
+            self.render_stage = RenderStage::Stopped;
+            return;
+        }
+
+        self.end_view();
+        self.begin_view(self.view + 1);
+    }
+
+    /// Returns the view being drawn, an index into the target's views.
+    pub fn view(&self) -> usize {
+        self.view
+    }
+
+    /// Returns how many views a window or render target is drawn in, 0 if it
+    /// does not exist.
+    pub fn view_count(&self, target: Target) -> usize {
+        match target {
+            Target::Window(id) => self.windows.get(&id).map(|w| w.views.len()),
+            Target::Texture(id) => self.render_targets.get(&id).map(|t| t.views.len()),
+        }
+        .unwrap_or(0)
+    }
+
+    /// Begins the render pass over one view. Only the view's area is cleared
+    /// and drawn, so earlier views are kept.
+    fn begin_view(&mut self, view: usize) {
+        self.view = view;
+        self.draw_queue.clear();
+
+        let (render_area_offset, render_area_extent) =
+            self.target_views()[view].pixels(self.target_resolution());
+
+        let clear_values = if self.samples == SampleCount::Sample1 {
+            vec![
+                Some([0.15, 0.15, 0.15, 1.0].into()),
+                Some([0.15, 0.15, 0.15, 1.0].into()),
+                Some(1.0.into()),
+            ]
+        } else {
+            // resolve targets are fully overwritten, so they take no clear
+            // value
+            vec![
+                None,
+                Some([0.15, 0.15, 0.15, 1.0].into()),
+                None,
+                Some(1.0.into()),
+                Some([0.15, 0.15, 0.15, 1.0].into()),
+            ]
+        };
+
+        self.commands
+            .as_mut()
+            .unwrap()
             .begin_render_pass(
                 vulkano::command_buffer::RenderPassBeginInfo {
+                    render_area_offset,
+                    render_area_extent,
                     clear_values,
                     ..vulkano::command_buffer::RenderPassBeginInfo::framebuffer(
-                        self.framebuffers[image_index as usize].clone(),
+                        self.framebuffer.clone().unwrap(),
                     )
                 },
                 SubpassContents::Inline,
             )
             .unwrap();
 
-        self.commands = Some(commands);
+        self.render_stage = RenderStage::Vertex;
+    }
+
+    /// Draws the view's translucent geometry and overlays, and ends its
+    /// render pass.
+    fn end_view(&mut self) {
+        self.commands
+            .as_mut()
+            .unwrap()
+            .next_subpass(SubpassContents::Inline)
+            .unwrap();
+
+        let translucent_draws = self.draw_queue.take_translucent();
+        self.record_draws(translucent_draws, DrawPass::Translucent);
+
+        let overlay_draws = self.draw_queue.take_overlay();
+        self.record_draws(overlay_draws, DrawPass::Overlay);
+
+        self.commands.as_mut().unwrap().end_render_pass().unwrap();
+    }
+
+    /// Acquires the window's next swapchain image, recreating the swapchain
+    /// first if needed, and returns the framebuffer to render it with.
+    fn acquire_image(&mut self, id: WindowId) -> Option<Arc<Framebuffer>> {
+        let window = match self.windows.get_mut(&id) {
+            Some(window) if !window.is_minimized() => window,
+            _ => return None,
+        };
+
+        if window.is_outdated() {
+            window.recreate_swapchain(
+                &self.memory_allocator,
+                self.render_pass.clone(),
+                self.samples,
+            );
+            if window.is_outdated() {
+                return None;
+            }
+        }
+
+        // resources of this frame may still be in use by the GPU
+        self.frames[self.frame_index].wait();
+
+        let (image_index, suboptimal, acquire_future) =
+            match swapchain::acquire_next_image(window.swapchain(), None) {
+                Ok(r) => r,
+                Err(AcquireError::OutOfDate) => {
+                    window.invalidate_swapchain();
+                    return None;
+                }
+                Err(err) => {
+                    panic!("{}", err);
+                }
+            };
+
+        if suboptimal {
+            window.invalidate_swapchain();
+            return None;
+        }
+
         self.image_index = image_index;
         self.acquire_future = Some(acquire_future);
+        Some(window.framebuffer(image_index))
     }
 
+    /// Queues a textured renderable for this frame. Opaque renderables are
+    /// drawn when the ambient stage starts, translucent ones after lighting.
     pub fn draw(&mut self, model: &dyn renderables::Renderable) {
+        self.draw_with_matrix(model, model.matrix());
+    }
+
+    /// Queues a textured renderable with the given matrix in place of its own,
+    /// e.g. one composed with a parent transform.
+    pub fn draw_with_matrix(&mut self, model: &dyn renderables::Renderable, matrix: TMat4<f32>) {
         match self.render_stage {
             RenderStage::Vertex => {}
-            RenderStage::RedrawNeeded => {
-                self.recreate_swapchain();
+            _ => {
                 self.commands = None;
                 self.render_stage = RenderStage::Stopped;
                 return;
             }
+        }
+
+        // a render target can not sample the image it is being drawn into
+        let texture = model.texture();
+        if let Target::Texture(id) = self.target {
+            if Arc::ptr_eq(&texture, &self.render_targets[&id].texture()) {
+                return;
+            }
+        }
+
+        let vertex_buffer = self.create_vertex_buffer(&model.vertices());
+        self.draw_vertex_buffer(
+            vertex_buffer,
+            texture,
+            model.tint(),
+            matrix,
+            model.layer(),
+            model.translucent(),
+        );
+    }
+
+    /// Queues a textured renderable drawn over the view once everything else
+    /// is, unlit and not moved by the camera: its matrix maps straight into
+    /// the view's normalized device coordinates (see `view_pixel_matrix`).
+    /// Overlays are drawn back to front by layer, e.g. for a HUD.
+    pub fn draw_overlay(&mut self, model: &dyn renderables::Renderable) {
+        self.draw_overlay_with_matrix(model, model.matrix());
+    }
+
+    /// Queues an overlay with the given matrix in place of its own.
+    pub fn draw_overlay_with_matrix(
+        &mut self,
+        model: &dyn renderables::Renderable,
+        matrix: TMat4<f32>,
+    ) {
+        match self.render_stage {
+            RenderStage::Vertex => {}
             _ => {
                 self.commands = None;
                 self.render_stage = RenderStage::Stopped;
@@ -524,66 +812,110 @@ impl Renderer {
             }
         }
 
-        let model_subbuffer = {
-            let model_mat = model.matrix();
+        let texture = model.texture();
+        if let Target::Texture(id) = self.target {
+            if Arc::ptr_eq(&texture, &self.render_targets[&id].texture()) {
+                return;
+            }
+        }
 
-            let uniform_data = deferred_vert::ty::ModelData {
-                mat: model_mat.into(),
-            };
+        let vertex_buffer = self.create_vertex_buffer(&model.vertices());
+        self.draw_queue.push_overlay(QueuedDraw {
+            vertices: QueuedVertices::Textured(vertex_buffer, texture, model.tint()),
+            matrix,
+            layer: model.layer(),
+        });
+    }
 
-            self.model_uniform_buffer.from_data(uniform_data).unwrap()
-        };
+    /// Matrix taking positions in pixels of the view being drawn, from its
+    /// top left corner, to its normalized device coordinates. Overlays drawn
+    /// with it stay the same size on screen whatever the camera does.
+    pub fn view_pixel_matrix(&self) -> TMat4<f32> {
+        let (_, extent) = self.target_views()[self.view].pixels(self.target_resolution());
+
+        nalgebra_glm::translation(&nalgebra_glm::vec3(-1.0, -1.0, 0.0))
+            * nalgebra_glm::scaling(&nalgebra_glm::vec3(
+                2.0 / extent[0] as f32,
+                2.0 / extent[1] as f32,
+                1.0,
+            ))
+    }
 
-        let model_layout = self
-            .deferred_pipeline
-            .pipeline
-            .layout()
-            .set_layouts()
-            .get(1)
-            .unwrap();
-        let model_set = PersistentDescriptorSet::new(
-            &self.descriptor_set_allocator,
-            model_layout.clone(),
-            [WriteDescriptorSet::buffer(0, model_subbuffer.clone())],
-        )
-        .unwrap();
+    /// Matrix taking framebuffer pixels of the view being drawn to world
+    /// coordinates through its camera, to light fragments by position.
+    fn view_screen_to_world(&self) -> TMat4<f32> {
+        let (offset, _) = self.target_views()[self.view].pixels(self.target_resolution());
+
+        nalgebra_glm::inverse(&self.target_camera().view_matrix())
+            * self.view_pixel_matrix()
+            * nalgebra_glm::translation(&nalgebra_glm::vec3(
+                -(offset[0] as f32),
+                -(offset[1] as f32),
+                0.0,
+            ))
+    }
 
-        let vertex_buffer = CpuAccessibleBuffer::from_iter(
+    /// Uploads textured vertices into a buffer that can be drawn on many
+    /// frames, e.g. a chunk of a tilemap.
+    pub(crate) fn create_vertex_buffer(
+        &self,
+        vertices: &[Vertex2D],
+    ) -> Arc<CpuAccessibleBuffer<[Vertex2D]>> {
+        CpuAccessibleBuffer::from_iter(
             &self.memory_allocator,
             BufferUsage {
                 vertex_buffer: true,
                 ..BufferUsage::empty()
             },
             false,
-            model.vertices().iter().cloned(),
+            vertices.iter().cloned(),
         )
-        .unwrap();
-
-        self.commands
-            .as_mut()
-            .unwrap()
-            .set_viewport(0, [self.viewport.clone()])
-            .bind_pipeline_graphics(self.deferred_pipeline.pipeline.clone())
-            .bind_descriptor_sets(
-                vulkano::pipeline::PipelineBindPoint::Graphics,
-                self.deferred_pipeline.pipeline.layout().clone(),
-                0,
-                model_set.clone(),
-            )
-            .bind_vertex_buffers(0, vertex_buffer.clone())
-            .draw(vertex_buffer.len() as u32, 1, 0, 0)
-            .unwrap();
+        .unwrap()
     }
 
-    pub fn color_draw(&mut self, model: &dyn renderables::ColorRenderable) {
+    /// Queues textured vertices that are already on the GPU.
+    pub(crate) fn draw_vertex_buffer(
+        &mut self,
+        vertex_buffer: Arc<CpuAccessibleBuffer<[Vertex2D]>>,
+        texture: Arc<Texture>,
+        tint: [f32; 4],
+        matrix: TMat4<f32>,
+        layer: i32,
+        translucent: bool,
+    ) {
         match self.render_stage {
             RenderStage::Vertex => {}
-            RenderStage::RedrawNeeded => {
-                self.recreate_swapchain();
+            _ => {
                 self.commands = None;
                 self.render_stage = RenderStage::Stopped;
                 return;
             }
+        }
+
+        self.draw_queue.push(
+            QueuedDraw {
+                vertices: QueuedVertices::Textured(vertex_buffer, texture, tint),
+                matrix: self.target_camera().view_matrix() * matrix,
+                layer,
+            },
+            translucent,
+        );
+    }
+
+    /// Queues a colored renderable for this frame. Opaque renderables are
+    /// drawn when the ambient stage starts, translucent ones after lighting.
+    pub fn color_draw(&mut self, model: &dyn renderables::ColorRenderable) {
+        self.color_draw_with_matrix(model, model.matrix());
+    }
+
+    /// Queues a colored renderable with the given matrix in place of its own.
+    pub fn color_draw_with_matrix(
+        &mut self,
+        model: &dyn renderables::ColorRenderable,
+        matrix: TMat4<f32>,
+    ) {
+        match self.render_stage {
+            RenderStage::Vertex => {}
             _ => {
                 self.commands = None;
                 self.render_stage = RenderStage::Stopped;
@@ -591,28 +923,6 @@ impl Renderer {
             }
         }
 
-        let model_subbuffer = {
-            let uniform_data = deferred_vert::ty::ModelData {
-                mat: model.matrix().into(),
-            };
-
-            self.model_uniform_buffer.from_data(uniform_data).unwrap()
-        };
-
-        let model_layout = self
-            .deferred_pipeline
-            .pipeline
-            .layout()
-            .set_layouts()
-            .get(0)
-            .unwrap();
-        let model_set = PersistentDescriptorSet::new(
-            &self.descriptor_set_allocator,
-            model_layout.clone(),
-            [WriteDescriptorSet::buffer(0, model_subbuffer.clone())],
-        )
-        .unwrap();
-
         let vertex_buffer = CpuAccessibleBuffer::from_iter(
             &self.memory_allocator,
             BufferUsage {
@@ -624,20 +934,193 @@ impl Renderer {
         )
         .unwrap();
 
-        self.commands
-            .as_mut()
-            .unwrap()
-            .set_viewport(0, [self.viewport.clone()])
-            .bind_pipeline_graphics(self.deferred_pipeline.pipeline.clone())
-            .bind_descriptor_sets(
-                vulkano::pipeline::PipelineBindPoint::Graphics,
-                self.deferred_pipeline.pipeline.layout().clone(),
-                0,
-                model_set.clone(),
+        self.draw_queue.push(
+            QueuedDraw {
+                vertices: QueuedVertices::Color(vertex_buffer),
+                matrix: self.target_camera().view_matrix() * matrix,
+                layer: model.layer(),
+            },
+            model.translucent(),
+        );
+    }
+
+    /// Submits everything in a scene: renderables are queued with their
+    /// node's world matrix, then the ambient stage runs and the scene's
+    /// directional and point lights are drawn.
+    /// * Must be called between `start` and `finish`, in place of the usual
+    ///   draw, ambient and lighting calls. More renderables may be drawn
+    ///   before it.
+    pub fn draw_scene(&mut self, scene: &mut Scene) {
+        let mut frame = Frame::new(self, 1.0);
+        frame.draw_scene(scene);
+        frame.submit();
+    }
+
+    /// Records queued draws in order. Opaque draws go to the G-buffer,
+    /// translucent ones are blended over the lit image using the light
+    /// gathered this frame, and overlays are blended over it at full
+    /// brightness.
+    fn record_draws(&mut self, draws: Vec<QueuedDraw>, pass: DrawPass) {
+        let (viewport, scissor) = self.view_viewport();
+        let frame = &self.frames[self.frame_index];
+
+        let light = match pass {
+            DrawPass::Opaque => None,
+            DrawPass::Translucent => Some((self.frame_light, &self.frame_points[..])),
+            DrawPass::Overlay => Some(([1.0; 3], &[][..])),
+        };
+        let screen_to_world = self.view_screen_to_world();
+        let light_subbuffer = light.map(|(ambient, points)| {
+            let points = &points[..points.len().min(MAX_TRANSLUCENT_POINT_LIGHTS)];
+            let mut data = translucent_frag::ty::LightData {
+                screen_to_world: screen_to_world.into(),
+                ambient,
+                point_count: points.len() as u32,
+                points: bytemuck::Zeroable::zeroed(),
+            };
+            for (slot, light) in data.points.iter_mut().zip(points) {
+                *slot = translucent_frag::ty::PointLightData {
+                    position: light.position,
+                    radius: light.radius,
+                    color: light.color,
+                    intensity: light.intensity,
+                };
+            }
+
+            frame.light_buffer.from_data(data).unwrap()
+        });
+        let translucent = pass != DrawPass::Opaque;
+
+        for draw in draws {
+            let pipeline = match (&draw.vertices, translucent) {
+                (QueuedVertices::Color(_), false) => self.deferred_pipeline.pipeline.clone(),
+                (QueuedVertices::Textured(..), false) => self.textured_pipeline.pipeline.clone(),
+                (QueuedVertices::Color(_), true) => self.translucent_pipeline.pipeline.clone(),
+                (QueuedVertices::Textured(..), true) => {
+                    self.textured_translucent_pipeline.pipeline.clone()
+                }
+            };
+            let set_layouts = pipeline.layout().set_layouts();
+
+            let model_subbuffer = {
+                let matrix = match pass {
+                    DrawPass::Overlay => draw.overlay_matrix(),
+                    _ => draw.layered_matrix(),
+                };
+                let uniform_data = deferred_vert::ty::ModelData { mat: matrix.into() };
+
+                frame.model_uniform_buffer.from_data(uniform_data).unwrap()
+            };
+
+            // set 0 is the model, then the frame's light for translucent
+            // draws, then the texture for textured draws
+            let mut sets = vec![PersistentDescriptorSet::new(
+                &frame.descriptor_set_allocator,
+                set_layouts[0].clone(),
+                [WriteDescriptorSet::buffer(0, model_subbuffer)],
             )
-            .bind_vertex_buffers(0, vertex_buffer.clone())
-            .draw(vertex_buffer.len() as u32, 1, 0, 0)
+            .unwrap()];
+
+            if let Some(light_subbuffer) = &light_subbuffer {
+                sets.push(
+                    PersistentDescriptorSet::new(
+                        &frame.descriptor_set_allocator,
+                        set_layouts[sets.len()].clone(),
+                        [WriteDescriptorSet::buffer(0, light_subbuffer.clone())],
+                    )
+                    .unwrap(),
+                );
+            }
+
+            if let QueuedVertices::Textured(_, texture, _) = &draw.vertices {
+                sets.push(
+                    PersistentDescriptorSet::new(
+                        &frame.descriptor_set_allocator,
+                        set_layouts[sets.len()].clone(),
+                        [WriteDescriptorSet::image_view_sampler(
+                            0,
+                            texture.view.clone(),
+                            self.sampler.clone(),
+                        )],
+                    )
+                    .unwrap(),
+                );
+            }
+
+            let commands = self.commands.as_mut().unwrap();
+            commands
+                .set_viewport(0, [viewport.clone()])
+                .set_scissor(0, [scissor])
+                .bind_pipeline_graphics(pipeline.clone())
+                .bind_descriptor_sets(
+                    vulkano::pipeline::PipelineBindPoint::Graphics,
+                    pipeline.layout().clone(),
+                    0,
+                    sets,
+                );
+
+            match &draw.vertices {
+                QueuedVertices::Color(vertex_buffer) => commands
+                    .bind_vertex_buffers(0, vertex_buffer.clone())
+                    .draw(vertex_buffer.len() as u32, 1, 0, 0)
+                    .unwrap(),
+                QueuedVertices::Textured(vertex_buffer, _, tint) => commands
+                    .push_constants(
+                        pipeline.layout().clone(),
+                        0,
+                        textured_frag::ty::TintData { tint: *tint },
+                    )
+                    .bind_vertex_buffers(0, vertex_buffer.clone())
+                    .draw(vertex_buffer.len() as u32, 1, 0, 0)
+                    .unwrap(),
+            };
+        }
+    }
+
+    /// Loads a PNG file as a texture.
+    pub fn load_texture(&self, path: &str) -> Result<Arc<Texture>, png::DecodingError> {
+        let (pixels, dimensions) = load_png(path)?;
+        Ok(self.create_texture(&pixels, dimensions))
+    }
+
+    /// Uploads RGBA8 pixel data as a texture. Blocks until the upload is done.
+    pub fn create_texture(&self, pixels: &[u8], dimensions: [u32; 2]) -> Arc<Texture> {
+        let mut uploads = AutoCommandBufferBuilder::primary(
+            &self.command_buffer_allocator,
+            self.queue.queue_family_index(),
+            CommandBufferUsage::OneTimeSubmit,
+        )
+        .unwrap();
+
+        let image = ImmutableImage::from_iter(
+            &self.memory_allocator,
+            pixels.iter().cloned(),
+            ImageDimensions::Dim2d {
+                width: dimensions[0],
+                height: dimensions[1],
+                array_layers: 1,
+            },
+            MipmapsCount::One,
+            Format::R8G8B8A8_SRGB,
+            &mut uploads,
+        )
+        .unwrap();
+
+        uploads
+            .build()
+            .unwrap()
+            .execute(self.queue.clone())
+            .unwrap()
+            .then_signal_fence_and_flush()
+            .unwrap()
+            .wait(None)
             .unwrap();
+
+        Arc::new(Texture::new(
+            ImageView::new_default(image).unwrap(),
+            dimensions,
+            has_partial_alpha(pixels),
+        ))
     }
 
     /// Executes the ambient render stage.
@@ -651,12 +1134,6 @@ impl Renderer {
             RenderStage::Ambient => {
                 return;
             }
-            RenderStage::RedrawNeeded => {
-                self.recreate_swapchain();
-                self.commands = None;
-                self.render_stage = RenderStage::Stopped;
-                return;
-            }
             _ => {
                 self.commands = None;
                 self.render_stage = RenderStage::Stopped;
@@ -664,6 +1141,18 @@ impl Renderer {
             }
         }
 
+        let opaque_draws = self.draw_queue.take_opaque();
+        self.record_draws(opaque_draws, DrawPass::Opaque);
+
+        // translucent geometry is lit by everything the lit image gets
+        self.frame_light = self.ambient.color.map(|c| c * self.ambient.intensity);
+        self.frame_points.clear();
+
+        let ambient_buffer = self.frames[self.frame_index].ambient_buffer.clone();
+        *ambient_buffer.write().unwrap() = self.ambient;
+
+        let color_buffer = self.target_attachments().color_buffer.clone();
+        let (viewport, scissor) = self.view_viewport();
         let ambient_layout = self
             .ambient_pipeline
             .pipeline
@@ -672,11 +1161,11 @@ impl Renderer {
             .get(0)
             .unwrap();
         let ambient_set = PersistentDescriptorSet::new(
-            &self.descriptor_set_allocator,
+            &self.frames[self.frame_index].descriptor_set_allocator,
             ambient_layout.clone(),
             [
-                WriteDescriptorSet::image_view(0, self.color_buffer.clone()),
-                WriteDescriptorSet::buffer(1, self.ambient_buffer.clone()),
+                WriteDescriptorSet::image_view(0, color_buffer),
+                WriteDescriptorSet::buffer(1, ambient_buffer),
             ],
         )
         .unwrap();
@@ -693,23 +1182,27 @@ impl Renderer {
                 0,
                 ambient_set.clone(),
             )
-            .set_viewport(0, [self.viewport.clone()])
+            .set_viewport(0, [viewport])
+            .set_scissor(0, [scissor])
             .bind_vertex_buffers(0, self.screen_vertices.clone())
             .draw(self.screen_vertices.len() as u32, 1, 0, 0)
             .unwrap();
     }
 
+    fn add_frame_light(&mut self, color: [f32; 3], intensity: f32) {
+        for (total, c) in self.frame_light.iter_mut().zip(color) {
+            *total += c * intensity;
+        }
+    }
+
+    /// Sets the ambient light. Takes effect from the next ambient stage.
     pub fn set_ambient(&mut self, color: [f32; 3], intensity: f32) {
-        self.ambient_buffer = CpuAccessibleBuffer::from_data(
-            &self.memory_allocator,
-            BufferUsage {
-                uniform_buffer: true,
-                ..BufferUsage::empty()
-            },
-            false,
-            ambient_frag::ty::AmbientData { color, intensity },
-        )
-        .unwrap();
+        self.ambient = ambient_frag::ty::AmbientData { color, intensity };
+    }
+
+    /// Color and intensity of the ambient light.
+    pub fn ambient_light(&self) -> ([f32; 3], f32) {
+        (self.ambient.color, self.ambient.intensity)
     }
 
     /// Draws a given DirectionalLight.
@@ -719,12 +1212,6 @@ impl Renderer {
                 self.render_stage = RenderStage::Directional;
             }
             RenderStage::Directional => {}
-            RenderStage::RedrawNeeded => {
-                self.recreate_swapchain();
-                self.commands = None;
-                self.render_stage = RenderStage::Stopped;
-                return;
-            }
             _ => {
                 self.commands = None;
                 self.render_stage = RenderStage::Stopped;
@@ -732,6 +1219,8 @@ impl Renderer {
             }
         }
 
+        self.add_frame_light(light.color, light.intensity);
+
         let directional_subbuffer = {
             let uniform_data = directional_frag::ty::DirectionalData {
                 direction: light.direction.into(),
@@ -740,9 +1229,14 @@ impl Renderer {
                 _dummy0: [0; 8],
             };
 
-            self.directional_buffer.from_data(uniform_data).unwrap()
+            self.frames[self.frame_index]
+                .directional_buffer
+                .from_data(uniform_data)
+                .unwrap()
         };
 
+        let color_buffer = self.target_attachments().color_buffer.clone();
+        let (viewport, scissor) = self.view_viewport();
         let directional_layout = self
             .directional_pipeline
             .pipeline
@@ -751,10 +1245,10 @@ impl Renderer {
             .get(0)
             .unwrap();
         let directional_set = PersistentDescriptorSet::new(
-            &self.descriptor_set_allocator,
+            &self.frames[self.frame_index].descriptor_set_allocator,
             directional_layout.clone(),
             [
-                WriteDescriptorSet::image_view(0, self.color_buffer.clone()),
+                WriteDescriptorSet::image_view(0, color_buffer),
                 WriteDescriptorSet::buffer(1, directional_subbuffer.clone()),
             ],
         )
@@ -763,7 +1257,8 @@ impl Renderer {
         self.commands
             .as_mut()
             .unwrap()
-            .set_viewport(0, [self.viewport.clone()])
+            .set_viewport(0, [viewport])
+            .set_scissor(0, [scissor])
             .bind_pipeline_graphics(self.directional_pipeline.pipeline.clone())
             .bind_vertex_buffers(0, self.screen_vertices.clone())
             .bind_descriptor_sets(
@@ -776,6 +1271,8 @@ impl Renderer {
             .unwrap();
     }
 
+    /// Draws a given PointLight, fading out at its radius.
+    /// * Translucent geometry is lit by the first 64 point lights of a view.
     pub fn point(&mut self, light: &PointLight) {
         match self.render_stage {
             RenderStage::Ambient => {
@@ -785,12 +1282,6 @@ impl Renderer {
                 self.render_stage = RenderStage::Point;
             }
             RenderStage::Point => {}
-            RenderStage::RedrawNeeded => {
-                self.recreate_swapchain();
-                self.commands = None;
-                self.render_stage = RenderStage::Stopped;
-                return;
-            }
             _ => {
                 self.commands = None;
                 self.render_stage = RenderStage::Stopped;
@@ -798,17 +1289,25 @@ impl Renderer {
             }
         }
 
+        self.frame_points.push(*light);
+
         let point_subbuffer = {
             let uniform_data = point_frag::ty::PointData {
-                position: light.position.into(),
-                color: light.color.into(),
-                intensity: light.intensity.into(),
-                _dummy0: [0; 4],
+                screen_to_world: self.view_screen_to_world().into(),
+                position: light.position,
+                radius: light.radius,
+                color: light.color,
+                intensity: light.intensity,
             };
 
-            self.point_buffer.from_data(uniform_data).unwrap()
+            self.frames[self.frame_index]
+                .point_buffer
+                .from_data(uniform_data)
+                .unwrap()
         };
 
+        let color_buffer = self.target_attachments().color_buffer.clone();
+        let (viewport, scissor) = self.view_viewport();
         let point_layout = self
             .point_pipeline
             .pipeline
@@ -817,10 +1316,10 @@ impl Renderer {
             .get(0)
             .unwrap();
         let point_set = PersistentDescriptorSet::new(
-            &self.descriptor_set_allocator,
+            &self.frames[self.frame_index].descriptor_set_allocator,
             point_layout.clone(),
             [
-                WriteDescriptorSet::image_view(0, self.color_buffer.clone()),
+                WriteDescriptorSet::image_view(0, color_buffer),
                 WriteDescriptorSet::buffer(1, point_subbuffer.clone()),
             ],
         )
@@ -829,7 +1328,8 @@ impl Renderer {
         self.commands
             .as_mut()
             .unwrap()
-            .set_viewport(0, [self.viewport.clone()])
+            .set_viewport(0, [viewport])
+            .set_scissor(0, [scissor])
             .bind_pipeline_graphics(self.point_pipeline.pipeline.clone())
             .bind_vertex_buffers(0, self.screen_vertices.clone())
             .bind_descriptor_sets(
@@ -842,53 +1342,335 @@ impl Renderer {
             .unwrap();
     }
 
-    pub fn recreate_swapchain(&mut self) {
-        self.render_stage = RenderStage::RedrawNeeded;
-        self.commands = None;
-
-        let window = self
-            .surface
-            .object()
-            .unwrap()
-            .downcast_ref::<Window>()
+    /// Opens another window sharing the renderer's device, pipelines and
+    /// allocators, e.g. a tool window of an editor. It gets its own
+    /// swapchain and camera; render to it with `start_window`.
+    /// * Panics if the GPU can not present to the new window or the window
+    ///   does not support the primary window's image format.
+    pub fn create_window(
+        &mut self,
+        event_loop: &EventLoopWindowTarget<()>,
+        window_config: &WindowConfig,
+    ) -> WindowId {
+        let surface = window_config
+            .builder(event_loop)
+            .build_vk_surface(event_loop, self.device.instance().clone())
             .unwrap();
-        let image_extent: [u32; 2] = window.inner_size().into();
 
-        let aspect_ratio = image_extent[0] as f32 / image_extent[1] as f32;
+        let presentable = self
+            .device
+            .physical_device()
+            .surface_support(self.queue.queue_family_index(), &surface)
+            .unwrap_or(false);
+        assert!(presentable, "The GPU can not present to the new window");
+
+        let image_format = self.render_pass.attachments()[0].format.unwrap();
+        let window = WindowSurface::new(
+            self.device.clone(),
+            surface,
+            image_format,
+            &self.memory_allocator,
+            self.render_pass.clone(),
+            self.samples,
+            None,
+        );
+
+        let id = window.id();
+        self.windows.insert(id, window);
+        id
+    }
+
+    /// Closes a window opened with `create_window`, waiting for the GPU to
+    /// finish with it first. A frame being recorded for it is dropped.
+    /// * Returns false if the window is the primary window, which lives as
+    ///   long as the renderer, or has already been closed.
+    pub fn close_window(&mut self, id: WindowId) -> bool {
+        if id == self.primary || !self.windows.contains_key(&id) {
+            return false;
+        }
+
+        self.forget_target(Target::Window(id));
+        self.windows.remove(&id);
+        true
+    }
+
+    /// Drops a frame being recorded for the target and waits for the GPU to
+    /// finish every frame, so the target's images can be freed.
+    fn forget_target(&mut self, target: Target) {
+        if target == self.target && !matches!(self.render_stage, RenderStage::Stopped) {
+            self.commands = None;
+            self.acquire_future = None;
+            self.render_stage = RenderStage::Stopped;
+        }
+
+        for frame in self.frames.iter_mut() {
+            frame.wait();
+        }
+
+        if target == self.target {
+            self.target = Target::Window(self.primary);
+        }
+    }
+
+    /// Creates an offscreen image to render frames into with `start_target`,
+    /// sampled through `RenderTarget::texture`. Dimensions are in pixels.
+    pub fn create_render_target(&mut self, dimensions: [u32; 2]) -> RenderTargetId {
+        let format = self.render_pass.attachments()[0].format.unwrap();
+        let render_target = RenderTarget::new(
+            &self.memory_allocator,
+            dimensions,
+            format,
+            self.render_pass.clone(),
+            self.samples,
+        );
 
-        let (new_swapchain, new_images) = match self.swapchain.recreate(SwapchainCreateInfo {
-            image_extent,
-            ..self.swapchain.create_info()
-        }) {
-            Ok(r) => r,
-            Err(SwapchainCreationError::ImageExtentNotSupported { .. }) => return,
-            Err(err) => panic!("Failed to recreate swapchain: {:?}", err),
+        let id = RenderTargetId(self.next_render_target);
+        self.next_render_target += 1;
+        self.render_targets.insert(id, render_target);
+        id
+    }
+
+    /// Removes a render target, waiting for the GPU to finish with it first.
+    /// Textures taken from it stay valid but stop updating.
+    /// * Returns false if the render target has already been removed.
+    pub fn remove_render_target(&mut self, id: RenderTargetId) -> bool {
+        if !self.render_targets.contains_key(&id) {
+            return false;
+        }
+
+        self.forget_target(Target::Texture(id));
+        self.render_targets.remove(&id);
+        true
+    }
+
+    /// Returns every render target, in the order they were created.
+    pub fn render_target_ids(&self) -> Vec<RenderTargetId> {
+        let mut ids = self.render_targets.keys().copied().collect::<Vec<_>>();
+        ids.sort_by_key(|id| id.0);
+        ids
+    }
+
+    pub fn render_target(&self, id: RenderTargetId) -> Option<&RenderTarget> {
+        self.render_targets.get(&id)
+    }
+
+    /// Returns a render target for changing its camera.
+    pub fn render_target_mut(&mut self, id: RenderTargetId) -> Option<&mut RenderTarget> {
+        self.render_targets.get_mut(&id)
+    }
+
+    fn target_attachments(&self) -> &Attachments {
+        match self.target {
+            Target::Window(id) => &self.windows[&id].attachments,
+            Target::Texture(id) => &self.render_targets[&id].attachments,
+        }
+    }
+
+    fn target_views(&self) -> &[View] {
+        match self.target {
+            Target::Window(id) => &self.windows[&id].views,
+            Target::Texture(id) => &self.render_targets[&id].views,
+        }
+    }
+
+    fn target_resolution(&self) -> [u32; 2] {
+        let dimensions = self.target_attachments().viewport.dimensions;
+        [dimensions[0] as u32, dimensions[1] as u32]
+    }
+
+    /// Viewport and scissor of the view being drawn.
+    fn view_viewport(&self) -> (Viewport, Scissor) {
+        self.target_views()[self.view].viewport(self.target_resolution())
+    }
+
+    /// Camera of the view being drawn.
+    pub(crate) fn target_camera(&self) -> Camera2D {
+        self.target_views()[self.view].camera
+    }
+
+    /// Sets the camera of the view being drawn.
+    pub(crate) fn set_target_camera(&mut self, camera: Camera2D) {
+        let views = match self.target {
+            Target::Window(id) => self.windows.get_mut(&id).map(|w| &mut w.views),
+            Target::Texture(id) => self.render_targets.get_mut(&id).map(|t| &mut t.views),
         };
 
-        let (new_framebuffers, new_color_buffer) = Renderer::window_size_dependent_setup(
+        if let Some(view) = views.and_then(|views| views.get_mut(self.view)) {
+            view.camera = camera;
+        }
+    }
+
+    /// The window created with the renderer. The renderer's window methods,
+    /// e.g. `set_fullscreen`, act on it.
+    pub fn primary_window(&self) -> WindowId {
+        self.primary
+    }
+
+    /// Returns every open window, the primary window first.
+    pub fn window_ids(&self) -> Vec<WindowId> {
+        let mut ids = vec![self.primary];
+        ids.extend(self.windows.keys().filter(|&&id| id != self.primary));
+        ids
+    }
+
+    /// Returns what is being rendered to, or what was last rendered to.
+    pub fn target(&self) -> Target {
+        self.target
+    }
+
+    pub fn surface(&self, id: WindowId) -> Option<&WindowSurface> {
+        self.windows.get(&id)
+    }
+
+    /// Returns a window for changing its camera, present mode and the like.
+    pub fn surface_mut(&mut self, id: WindowId) -> Option<&mut WindowSurface> {
+        self.windows.get_mut(&id)
+    }
+
+    fn primary_surface(&self) -> &WindowSurface {
+        &self.windows[&self.primary]
+    }
+
+    fn primary_surface_mut(&mut self) -> &mut WindowSurface {
+        self.windows.get_mut(&self.primary).unwrap()
+    }
+
+    pub fn window(&self) -> &Window {
+        self.primary_surface().window()
+    }
+
+    /// Switches between windowed, borderless and exclusive fullscreen on the
+    /// window's current monitor. The swapchain is recreated by the resize
+    /// that follows.
+    pub fn set_fullscreen(&mut self, mode: FullscreenMode) {
+        self.primary_surface_mut().set_fullscreen(mode);
+    }
+
+    pub fn fullscreen(&self) -> FullscreenMode {
+        self.primary_surface().fullscreen()
+    }
+
+    /// Keeps the cursor inside the window, e.g. for mouse look.
+    /// * Returns false if the platform supports neither confining nor
+    ///   locking the cursor.
+    pub fn set_cursor_grab(&mut self, grab: bool) -> bool {
+        self.primary_surface_mut().set_cursor_grab(grab)
+    }
+
+    pub fn set_cursor_visible(&mut self, visible: bool) {
+        self.primary_surface_mut().set_cursor_visible(visible);
+    }
+
+    pub fn set_title(&mut self, title: &str) {
+        self.primary_surface_mut().set_title(title);
+    }
+
+    /// Returns true if the window has no area to render to.
+    pub fn is_minimized(&self) -> bool {
+        self.primary_surface().is_minimized()
+    }
+
+    /// Ratio of physical to logical pixels of the window's monitor.
+    pub fn scale_factor(&self) -> f64 {
+        self.primary_surface().scale_factor()
+    }
+
+    /// Marks the swapchain as out of date so it is recreated at the start of
+    /// the next frame. Several resizes in one frame then only recreate it
+    /// once, with the window's final size.
+    pub fn invalidate_swapchain(&mut self) {
+        self.primary_surface_mut().invalidate_swapchain();
+    }
+
+    /// Recreates the primary window's swapchain right away rather than at the
+    /// start of the next frame.
+    pub fn recreate_swapchain(&mut self) {
+        let window = self.windows.get_mut(&self.primary).unwrap();
+        window.recreate_swapchain(
             &self.memory_allocator,
-            &new_images,
             self.render_pass.clone(),
-            &mut self.viewport,
+            self.samples,
         );
+    }
 
-        self.swapchain = new_swapchain;
-        self.framebuffers = new_framebuffers;
-        self.color_buffer = new_color_buffer;
+    /// Returns the resolution the scene is rendered at. This is the virtual
+    /// resolution if one is set, otherwise the window size.
+    pub fn render_resolution(&self) -> [u32; 2] {
+        self.primary_surface().render_resolution()
+    }
 
-        self.render_stage = RenderStage::Stopped;
+    /// Sets or clears the fixed virtual resolution. The size dependent
+    /// attachments are rebuilt at the start of the next frame.
+    pub fn set_virtual_resolution(&mut self, virtual_resolution: Option<[u32; 2]>) {
+        self.primary_surface_mut()
+            .set_virtual_resolution(virtual_resolution);
+    }
+
+    /// Converts a cursor position in window pixels into pixels of the render
+    /// resolution, accounting for upscaling and letterboxing.
+    /// * Returns None if the position falls on the letterbox bars.
+    pub fn window_to_render(&self, position: PhysicalPosition<f64>) -> Option<[f32; 2]> {
+        self.primary_surface().window_to_render(position)
+    }
+
+    /// Converts a cursor position in window pixels into world coordinates
+    /// through the current camera.
+    pub fn window_to_world(&self, position: PhysicalPosition<f64>) -> Option<[f32; 2]> {
+        self.primary_surface().window_to_world(position)
+    }
+
+    /// Sets the camera subsequent draws to the primary window are viewed
+    /// through.
+    pub fn set_camera(&mut self, camera: Camera2D) {
+        self.primary_surface_mut().set_camera(camera);
+    }
+
+    pub fn camera(&self) -> Camera2D {
+        self.primary_surface().camera()
+    }
+
+    /// Returns the present modes supported by the window surface.
+    pub fn supported_present_modes(&self) -> Vec<PresentMode> {
+        self.primary_surface().supported_present_modes()
+    }
+
+    /// Returns the present mode currently requested for the swapchain.
+    pub fn present_mode(&self) -> PresentMode {
+        self.primary_surface().present_mode()
+    }
+
+    /// Switches the swapchain to the given present mode from the next frame.
+    /// * Returns false and leaves the swapchain untouched if the surface does
+    ///   not support the requested mode.
+    pub fn set_present_mode(&mut self, present_mode: PresentMode) -> bool {
+        self.primary_surface_mut().set_present_mode(present_mode)
+    }
+
+    /// Enables or disables vertical sync.
+    /// * Disabling vsync prefers mailbox, then immediate, and stays on fifo if
+    ///   neither is supported.
+    pub fn set_vsync(&mut self, enabled: bool) {
+        self.primary_surface_mut().set_vsync(enabled);
+    }
+
+    /// Returns true if the current present mode waits for vertical blanking.
+    pub fn vsync(&self) -> bool {
+        self.primary_surface().vsync()
+    }
+
+    /// Returns the MSAA sample count the G-buffer was created with.
+    pub fn samples(&self) -> SampleCount {
+        self.samples
     }
 
-    pub fn finish(&mut self, previous_frame_end: &mut Option<Box<dyn GpuFuture>>) {
+    /// Submits the recorded frame and presents it.
+    /// * The frame is fenced so its resources are only reused once the GPU is
+    ///   done with them; the application never has to track futures itself.
+    pub fn finish(&mut self) {
         match self.render_stage {
+            RenderStage::Ambient => {}
             RenderStage::Directional => {}
             RenderStage::Point => {}
-            RenderStage::RedrawNeeded => {
-                self.recreate_swapchain();
-                self.commands = None;
-                self.render_stage = RenderStage::Stopped;
-                return;
-            }
             _ => {
                 self.commands = None;
                 self.render_stage = RenderStage::Stopped;
@@ -896,91 +1678,293 @@ impl Renderer {
             }
         }
 
-        let mut commands = self.commands.take().unwrap();
-        commands.end_render_pass().unwrap();
-        let command_buffer = commands.build().unwrap();
+        self.end_view();
 
-        let acquire_future = self.acquire_future.take().unwrap();
+        let mut commands = self.commands.take().unwrap();
+        self.framebuffer = None;
 
-        let mut local_future: Option<Box<dyn GpuFuture>> =
-            Some(Box::new(vulkano::sync::now(self.device.clone())) as Box<dyn GpuFuture>);
+        // render targets are sampled, only windows are presented
+        let mut window = match self.target {
+            Target::Window(id) => self.windows.get_mut(&id),
+            Target::Texture(_) => None,
+        };
 
-        std::mem::swap(&mut local_future, previous_frame_end);
+        // upscale the low resolution scene onto the swapchain image
+        if let Some(window) = &window {
+            if let Some(scene_image) = &window.scene_image {
+                let swapchain_image = window.images[self.image_index as usize].clone();
+
+                let mut blit = BlitImageInfo::images(scene_image.clone(), swapchain_image.clone());
+                blit.regions[0].dst_offsets = [
+                    [window.letterbox.offset[0], window.letterbox.offset[1], 0],
+                    [
+                        window.letterbox.offset[0] + window.letterbox.size[0],
+                        window.letterbox.offset[1] + window.letterbox.size[1],
+                        1,
+                    ],
+                ];
+
+                commands
+                    .clear_color_image(ClearColorImageInfo {
+                        clear_value: [0.0, 0.0, 0.0, 1.0].into(),
+                        ..ClearColorImageInfo::image(swapchain_image)
+                    })
+                    .unwrap()
+                    .blit_image(blit)
+                    .unwrap();
+            }
+        }
 
-        let future = local_future
-            .take()
-            .unwrap()
-            .join(acquire_future)
-            .then_execute(self.queue.clone(), command_buffer)
-            .unwrap()
-            .then_swapchain_present(
-                self.queue.clone(),
-                SwapchainPresentInfo::swapchain_image_index(
-                    self.swapchain.clone(),
-                    self.image_index,
-                ),
-            )
-            .then_signal_fence_and_flush();
+        let command_buffer = commands.build().unwrap();
 
-        match future {
-            Ok(future) => {
-                *previous_frame_end = Some(Box::new(future) as Box<_>);
+        // chain after the most recent submission so frames execute in order
+        let previous_future = match self
+            .previous_frame_index
+            .and_then(|i| self.frames[i].fence.clone())
+        {
+            Some(fence) => fence.boxed_send_sync(),
+            None => {
+                let mut now = sync::now(self.device.clone());
+                now.cleanup_finished();
+                now.boxed_send_sync()
             }
-            Err(vulkano::sync::FlushError::OutOfDate) => {
-                self.recreate_swapchain();
-                *previous_frame_end =
-                    Some(Box::new(vulkano::sync::now(self.device.clone())) as Box<_>);
+        };
+
+        let future = match &window {
+            Some(window) => previous_future
+                .join(self.acquire_future.take().unwrap())
+                .then_execute(self.queue.clone(), command_buffer)
+                .unwrap()
+                .then_swapchain_present(
+                    self.queue.clone(),
+                    SwapchainPresentInfo::swapchain_image_index(
+                        window.swapchain(),
+                        self.image_index,
+                    ),
+                )
+                .boxed_send_sync(),
+            None => previous_future
+                .then_execute(self.queue.clone(), command_buffer)
+                .unwrap()
+                .boxed_send_sync(),
+        }
+        .then_signal_fence_and_flush();
+
+        self.frames[self.frame_index].fence = match future {
+            Ok(future) => Some(Arc::new(future)),
+            Err(sync::FlushError::OutOfDate) => {
+                if let Some(window) = window.as_mut() {
+                    window.invalidate_swapchain();
+                }
+                None
             }
             Err(err) => {
                 println!("Failed to flush future: {:?}", err);
-                *previous_frame_end =
-                    Some(Box::new(vulkano::sync::now(self.device.clone())) as Box<_>);
+                None
             }
-        }
+        };
+
+        self.previous_frame_index = Some(self.frame_index);
+        self.frame_index = (self.frame_index + 1) % self.frames.len();
 
         self.commands = None;
         self.render_stage = RenderStage::Stopped;
     }
 
-    fn window_size_dependent_setup(
-        memory_allocator: &StandardMemoryAllocator,
-        images: &[Arc<SwapchainImage>],
-        render_pass: Arc<RenderPass>,
-        viewport: &mut Viewport,
-    ) -> (Vec<Arc<Framebuffer>>, Arc<ImageView<AttachmentImage>>) {
-        let dimensions = images[0].dimensions().width_height();
-        viewport.dimensions = [dimensions[0] as f32, dimensions[1] as f32];
-
-        let depth_buffer = ImageView::new_default(
-            AttachmentImage::transient(memory_allocator, dimensions, Format::D16_UNORM).unwrap(),
-        )
-        .unwrap();
+    /// Picks the highest sample count no greater than `requested` that the
+    /// device supports for both color and depth attachments.
+    fn supported_samples(physical_device: &PhysicalDevice, requested: SampleCount) -> SampleCount {
+        let properties = physical_device.properties();
+
+        [
+            SampleCount::Sample8,
+            SampleCount::Sample4,
+            SampleCount::Sample2,
+        ]
+        .into_iter()
+        .filter(|&samples| samples as u32 <= requested as u32)
+        .find(|&samples| {
+            properties
+                .framebuffer_color_sample_counts
+                .contains_count(samples)
+                && properties
+                    .framebuffer_depth_sample_counts
+                    .contains_count(samples)
+        })
+        .unwrap_or(SampleCount::Sample1)
+    }
 
-        let color_buffer = ImageView::new_default(
-            AttachmentImage::transient_input_attachment(
-                memory_allocator,
-                dimensions,
-                Format::A2B10G10R10_UNORM_PACK32,
+    /// Creates the deferred render pass: G-buffer, lighting, then forward
+    /// rendered translucent geometry over the lit image.
+    /// * With multisampling, the G-buffer is resolved into a single sampled
+    ///   attachment at the end of the deferred subpass for the lighting
+    ///   subpass to read. Lighting and translucency render into a multisampled
+    ///   image that shares the G-buffer's depth, resolved into the final image.
+    fn create_render_pass(
+        device: Arc<Device>,
+        final_format: Format,
+        samples: SampleCount,
+    ) -> Arc<RenderPass> {
+        if samples == SampleCount::Sample1 {
+            return vulkano::ordered_passes_renderpass!(device,
+                attachments: {
+                    final_color: {
+                        load: Clear,
+                        store: Store,
+                        format: final_format,
+                        samples: 1,
+                    },
+                    color: {
+                        load: Clear,
+                        store: DontCare,
+                        format: Format::A2B10G10R10_UNORM_PACK32,
+                        samples: 1,
+                    },
+                    depth: {
+                        load: Clear,
+                        store: DontCare,
+                        format: Format::D16_UNORM,
+                        samples: 1,
+                    }
+                },
+                passes: [
+                    {
+                        color: [color],
+                        depth_stencil: {depth},
+                        input: []
+                    },
+                    {
+                        color: [final_color],
+                        depth_stencil: {},
+                        input: [color]
+                    },
+                    {
+                        color: [final_color],
+                        depth_stencil: {depth},
+                        input: []
+                    }
+                ]
             )
-            .unwrap(),
-        )
-        .unwrap();
+            .unwrap();
+        }
 
-        let framebuffers = images
-            .iter()
-            .map(|image| {
-                let view = ImageView::new_default(image.clone()).unwrap();
-                Framebuffer::new(
-                    render_pass.clone(),
-                    FramebufferCreateInfo {
-                        attachments: vec![view, color_buffer.clone(), depth_buffer.clone()],
-                        ..Default::default()
-                    },
-                )
-                .unwrap()
+        // built by hand because the renderpass macro puts resolve attachments
+        // in the transfer layout, which is not valid for a resolve target
+        let attachment = |attachment: u32, layout: ImageLayout| {
+            Some(AttachmentReference {
+                attachment,
+                layout,
+                ..Default::default()
             })
-            .collect::<Vec<_>>();
+        };
+
+        // final_color, color_ms, color, depth, lit_ms
+        let attachments = vec![
+            AttachmentDescription {
+                format: Some(final_format),
+                samples: SampleCount::Sample1,
+                load_op: LoadOp::DontCare,
+                store_op: StoreOp::Store,
+                initial_layout: ImageLayout::ColorAttachmentOptimal,
+                final_layout: ImageLayout::ColorAttachmentOptimal,
+                ..Default::default()
+            },
+            AttachmentDescription {
+                format: Some(Format::A2B10G10R10_UNORM_PACK32),
+                samples,
+                load_op: LoadOp::Clear,
+                store_op: StoreOp::DontCare,
+                initial_layout: ImageLayout::ColorAttachmentOptimal,
+                final_layout: ImageLayout::ColorAttachmentOptimal,
+                ..Default::default()
+            },
+            AttachmentDescription {
+                format: Some(Format::A2B10G10R10_UNORM_PACK32),
+                samples: SampleCount::Sample1,
+                load_op: LoadOp::DontCare,
+                store_op: StoreOp::DontCare,
+                initial_layout: ImageLayout::ColorAttachmentOptimal,
+                final_layout: ImageLayout::ShaderReadOnlyOptimal,
+                ..Default::default()
+            },
+            AttachmentDescription {
+                format: Some(Format::D16_UNORM),
+                samples,
+                load_op: LoadOp::Clear,
+                store_op: StoreOp::DontCare,
+                stencil_load_op: LoadOp::Clear,
+                initial_layout: ImageLayout::DepthStencilAttachmentOptimal,
+                final_layout: ImageLayout::DepthStencilAttachmentOptimal,
+                ..Default::default()
+            },
+            AttachmentDescription {
+                format: Some(final_format),
+                samples,
+                load_op: LoadOp::Clear,
+                store_op: StoreOp::DontCare,
+                initial_layout: ImageLayout::ColorAttachmentOptimal,
+                final_layout: ImageLayout::ColorAttachmentOptimal,
+                ..Default::default()
+            },
+        ];
 
-        (framebuffers, color_buffer.clone())
+        let subpasses = vec![
+            SubpassDescription {
+                color_attachments: vec![attachment(1, ImageLayout::ColorAttachmentOptimal)],
+                resolve_attachments: vec![attachment(2, ImageLayout::ColorAttachmentOptimal)],
+                depth_stencil_attachment: attachment(3, ImageLayout::DepthStencilAttachmentOptimal),
+                preserve_attachments: vec![0, 4],
+                ..Default::default()
+            },
+            SubpassDescription {
+                color_attachments: vec![attachment(4, ImageLayout::ColorAttachmentOptimal)],
+                input_attachments: vec![attachment(2, ImageLayout::ShaderReadOnlyOptimal)],
+                preserve_attachments: vec![0, 3],
+                ..Default::default()
+            },
+            SubpassDescription {
+                color_attachments: vec![attachment(4, ImageLayout::ColorAttachmentOptimal)],
+                resolve_attachments: vec![attachment(0, ImageLayout::ColorAttachmentOptimal)],
+                depth_stencil_attachment: attachment(3, ImageLayout::DepthStencilAttachmentOptimal),
+                ..Default::default()
+            },
+        ];
+
+        // same conservative dependencies the renderpass macro generates
+        let dependency = |src_subpass: u32, dst_subpass: u32| SubpassDependency {
+            src_subpass: Some(src_subpass),
+            dst_subpass: Some(dst_subpass),
+            src_stages: PipelineStages {
+                all_graphics: true,
+                ..PipelineStages::empty()
+            },
+            dst_stages: PipelineStages {
+                all_graphics: true,
+                ..PipelineStages::empty()
+            },
+            src_access: AccessFlags {
+                memory_read: true,
+                memory_write: true,
+                ..AccessFlags::empty()
+            },
+            dst_access: AccessFlags {
+                memory_read: true,
+                memory_write: true,
+                ..AccessFlags::empty()
+            },
+            by_region: true,
+            ..Default::default()
+        };
+
+        RenderPass::new(
+            device,
+            RenderPassCreateInfo {
+                attachments,
+                subpasses,
+                dependencies: vec![dependency(0, 1), dependency(1, 2)],
+                ..Default::default()
+            },
+        )
+        .unwrap()
     }
 }
@@ -0,0 +1,107 @@
+use vulkano::buffer::CpuAccessibleBuffer;
+
+use nalgebra_glm::TMat4;
+
+use std::sync::Arc;
+
+use super::renderables::layer_depth;
+use super::renderables::vertices::{ColorVertex2D, Vertex2D};
+use super::texture::Texture;
+
+/// Vertex data of a queued draw. The kind decides which pipeline draws it.
+/// Textured draws carry the tint their texture is multiplied by.
+pub enum QueuedVertices {
+    Color(Arc<CpuAccessibleBuffer<[ColorVertex2D]>>),
+    Textured(Arc<CpuAccessibleBuffer<[Vertex2D]>>, Arc<Texture>, [f32; 4]),
+}
+
+/// A draw recorded during the vertex stage, submitted once the frame's draw
+/// order is known.
+pub struct QueuedDraw {
+    pub vertices: QueuedVertices,
+    pub matrix: TMat4<f32>,
+    pub layer: i32,
+}
+
+impl QueuedDraw {
+    /// Model matrix with the layer baked into the output depth. Vertex z is
+    /// replaced, so layers alone decide what is drawn in front.
+    pub fn layered_matrix(&self) -> TMat4<f32> {
+        with_depth(self.matrix, layer_depth(self.layer))
+    }
+
+    /// Model matrix of an overlay draw, at the nearest depth so nothing else
+    /// in the view hides it. Overlays are ordered among themselves by layer.
+    pub fn overlay_matrix(&self) -> TMat4<f32> {
+        with_depth(self.matrix, 0.0)
+    }
+}
+
+fn with_depth(matrix: TMat4<f32>, depth: f32) -> TMat4<f32> {
+    let mut depth_matrix: TMat4<f32> = nalgebra_glm::identity();
+    depth_matrix[(2, 2)] = 0.0;
+    depth_matrix[(2, 3)] = depth;
+
+    depth_matrix * matrix
+}
+
+/// Which part of a view's render pass queued draws are recorded in.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum DrawPass {
+    /// Into the G-buffer, before lighting.
+    Opaque,
+    /// Blended over the lit image, tinted by the frame's light.
+    Translucent,
+    /// Blended over everything else, unlit.
+    Overlay,
+}
+
+/// Draws of the current frame, split by how they have to be ordered.
+#[derive(Default)]
+pub struct DrawQueue {
+    opaque: Vec<QueuedDraw>,
+    translucent: Vec<QueuedDraw>,
+    overlay: Vec<QueuedDraw>,
+}
+
+impl DrawQueue {
+    pub fn push(&mut self, draw: QueuedDraw, translucent: bool) {
+        match translucent {
+            true => self.translucent.push(draw),
+            false => self.opaque.push(draw),
+        }
+    }
+
+    pub fn push_overlay(&mut self, draw: QueuedDraw) {
+        self.overlay.push(draw);
+    }
+
+    pub fn clear(&mut self) {
+        self.opaque.clear();
+        self.translucent.clear();
+        self.overlay.clear();
+    }
+
+    /// Takes the opaque draws sorted front to back, so the depth test rejects
+    /// as many hidden fragments as possible. Draws on the same layer keep
+    /// submission order.
+    pub fn take_opaque(&mut self) -> Vec<QueuedDraw> {
+        let mut draws = std::mem::take(&mut self.opaque);
+        draws.sort_by_key(|draw| std::cmp::Reverse(draw.layer));
+        draws
+    }
+
+    /// Takes the translucent draws sorted back to front, as blending needs.
+    pub fn take_translucent(&mut self) -> Vec<QueuedDraw> {
+        let mut draws = std::mem::take(&mut self.translucent);
+        draws.sort_by_key(|draw| draw.layer);
+        draws
+    }
+
+    /// Takes the overlay draws sorted back to front, like translucent ones.
+    pub fn take_overlay(&mut self) -> Vec<QueuedDraw> {
+        let mut draws = std::mem::take(&mut self.overlay);
+        draws.sort_by_key(|draw| draw.layer);
+        draws
+    }
+}
@@ -1,11 +1,18 @@
+/// Light falling evenly on everything. Sprites have no normals, so the
+/// direction does not change how they are lit.
+#[derive(Clone, Copy, Debug)]
 pub struct DirectionalLight {
     pub direction: [f32; 2],
     pub color: [f32; 3],
     pub intensity: f32,
 }
 
+/// Light around a point in world space, fading to nothing at `radius`. A
+/// radius of 0 lights the whole view evenly.
+#[derive(Clone, Copy, Debug)]
 pub struct PointLight {
     pub position: [f32; 3],
     pub color: [f32; 3],
     pub intensity: f32,
+    pub radius: f32,
 }
@@ -5,12 +5,45 @@ pub mod vertices;
 
 use nalgebra_glm::TMat4;
 
+use std::sync::Arc;
+
+use super::texture::Texture;
+
+/// Layers are clamped to this range. Higher layers are drawn in front.
+/// * Neighbouring layers are two steps of the 16 bit depth buffer apart.
+pub const MIN_LAYER: i32 = -16383;
+pub const MAX_LAYER: i32 = 16383;
+
+/// Maps a layer onto a depth value in (0, 1), nearer for higher layers.
+pub fn layer_depth(layer: i32) -> f32 {
+    let layer = layer.clamp(MIN_LAYER, MAX_LAYER);
+    0.5 - layer as f32 / (2 * (MAX_LAYER + 1)) as f32
+}
+
 pub trait Renderable {
     fn vertices(&self) -> Vec<vertices::Vertex2D>;
     fn matrix(&self) -> TMat4<f32>;
+    fn layer(&self) -> i32;
+    fn texture(&self) -> Arc<Texture>;
+
+    /// Multiplies the texture's color, e.g. to color white glyphs.
+    fn tint(&self) -> [f32; 4] {
+        [1.0; 4]
+    }
+
+    /// Translucent renderables are drawn back to front after lighting.
+    fn translucent(&self) -> bool {
+        self.texture().translucent() || self.tint()[3] < 1.0
+    }
 }
 
 pub trait ColorRenderable {
     fn vertices(&self) -> Vec<vertices::ColorVertex2D>;
     fn matrix(&self) -> TMat4<f32>;
+    fn layer(&self) -> i32;
+
+    /// Translucent renderables are drawn back to front after lighting.
+    fn translucent(&self) -> bool {
+        self.vertices().iter().any(|vertex| vertex.color[3] < 1.0)
+    }
 }
@@ -2,10 +2,39 @@ use super::vertices::*;
 
 use nalgebra_glm::TMat4;
 
+use std::sync::Arc;
+
+use crate::application::renderer::texture::Texture;
+
 /// Quad with texture data.
 pub struct Quad {
     pub vertices: [Vertex2D; 6],
     pub matrix: TMat4<f32>,
+    pub layer: i32,
+    pub texture: Arc<Texture>,
+}
+
+impl Quad {
+    /// Vertices of a `size` large rectangle centered on the origin, showing
+    /// the part of a texture between the top left and bottom right `uv`.
+    pub fn rect(size: [f32; 2], uv: [[f32; 2]; 2]) -> [Vertex2D; 6] {
+        let [w, h] = [size[0] / 2.0, size[1] / 2.0];
+        let [[u0, v0], [u1, v1]] = uv;
+
+        let vertex = |x: f32, y: f32, u: f32, v: f32| Vertex2D {
+            position: [x, y, 0.0],
+            uv: [u, v],
+        };
+
+        [
+            vertex(-w, -h, u0, v0),
+            vertex(-w, h, u0, v1),
+            vertex(w, h, u1, v1),
+            vertex(-w, -h, u0, v0),
+            vertex(w, h, u1, v1),
+            vertex(w, -h, u1, v0),
+        ]
+    }
 }
 
 impl super::Renderable for Quad {
@@ -16,12 +45,21 @@ impl super::Renderable for Quad {
     fn matrix(&self) -> TMat4<f32> {
         self.matrix
     }
+
+    fn layer(&self) -> i32 {
+        self.layer
+    }
+
+    fn texture(&self) -> Arc<Texture> {
+        self.texture.clone()
+    }
 }
 
 /// Quad with color data. No texture data.
 pub struct ColorQuad {
     pub vertices: [ColorVertex2D; 6],
     pub matrix: TMat4<f32>,
+    pub layer: i32,
 }
 
 impl super::ColorRenderable for ColorQuad {
@@ -32,4 +70,8 @@ impl super::ColorRenderable for ColorQuad {
     fn matrix(&self) -> TMat4<f32> {
         self.matrix
     }
+
+    fn layer(&self) -> i32 {
+        self.layer
+    }
 }
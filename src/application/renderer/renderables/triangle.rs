@@ -2,10 +2,16 @@ use super::vertices::*;
 
 use nalgebra_glm::TMat4;
 
+use std::sync::Arc;
+
+use crate::application::renderer::texture::Texture;
+
 /// Triangle with texture data.
 pub struct Triangle {
     pub vertices: [Vertex2D; 3],
     pub matrix: TMat4<f32>,
+    pub layer: i32,
+    pub texture: Arc<Texture>,
 }
 
 impl super::Renderable for Triangle {
@@ -16,12 +22,21 @@ impl super::Renderable for Triangle {
     fn matrix(&self) -> TMat4<f32> {
         self.matrix
     }
+
+    fn layer(&self) -> i32 {
+        self.layer
+    }
+
+    fn texture(&self) -> Arc<Texture> {
+        self.texture.clone()
+    }
 }
 
 /// Triangle with color data. No texture data.
 pub struct ColorTriangle {
     pub vertices: [ColorVertex2D; 3],
     pub matrix: TMat4<f32>,
+    pub layer: i32,
 }
 
 impl super::ColorRenderable for ColorTriangle {
@@ -32,4 +47,8 @@ impl super::ColorRenderable for ColorTriangle {
     fn matrix(&self) -> TMat4<f32> {
         self.matrix
     }
+
+    fn layer(&self) -> i32 {
+        self.layer
+    }
 }
@@ -44,6 +44,6 @@ impl BasicVertex2D {
 #[derive(Clone, Copy, Debug, Default, Pod, Zeroable)]
 pub struct ColorVertex2D {
     pub position: [f32; 3],
-    pub color: [f32; 3],
+    pub color: [f32; 4],
 }
 vulkano::impl_vertex!(ColorVertex2D, position, color);
@@ -27,6 +27,39 @@ pub mod deferred_frag {
     }
 }
 
+pub mod textured_vert {
+    vulkano_shaders::shader! {
+        ty: "vertex",
+        path: "src/application/renderer/shaders/shaders/textured.vert"
+    }
+}
+
+pub mod textured_frag {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        path: "src/application/renderer/shaders/shaders/textured.frag"
+    }
+}
+
+pub mod translucent_frag {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        path: "src/application/renderer/shaders/shaders/translucent.frag",
+        types_meta: {
+            use bytemuck::{Pod, Zeroable};
+
+            #[derive(Clone, Copy, Pod, Zeroable)]
+        }
+    }
+}
+
+pub mod textured_translucent_frag {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        path: "src/application/renderer/shaders/shaders/textured_translucent.frag"
+    }
+}
+
 pub mod ambient_vert {
     vulkano_shaders::shader! {
         ty: "vertex",
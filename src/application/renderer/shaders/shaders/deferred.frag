@@ -1,9 +1,14 @@
 #version 450
 
-layout(location = 0) in vec3 in_color;
+layout(location = 0) in vec4 in_color;
 
 layout(location = 0) out vec4 f_color;
 
 void main() {
-    f_color = vec4(in_color, 1.0);
+    // opaque geometry is alpha tested, blending happens in the translucent pass
+    if (in_color.a < 0.5) {
+        discard;
+    }
+
+    f_color = vec4(in_color.rgb, 1.0);
 }
@@ -3,15 +3,31 @@
 layout(input_attachment_index = 0, set = 0, binding = 0) uniform subpassInput u_color;
 
 layout(set = 0, binding = 1) uniform PointData {
+    // framebuffer pixels to world coordinates, for the view being lit
+    mat4 screen_to_world;
     vec3 position;
+    float radius;
     vec3 color;
     float intensity;
 } point;
 
 layout(location = 0) out vec4 f_color;
 
+// fades to 0 at the radius; lights without a radius reach everywhere
+float falloff(vec2 world, vec2 position, float radius) {
+    if (radius <= 0.0) {
+        return 1.0;
+    }
+
+    float t = clamp(1.0 - distance(world, position) / radius, 0.0, 1.0);
+    return t * t;
+}
+
 void main() {
+    vec2 world = (point.screen_to_world * vec4(gl_FragCoord.xy, 0.0, 1.0)).xy;
+    float attenuation = falloff(world, point.position.xy, point.radius);
+
     vec3 result_color = subpassLoad(u_color).rgb;
-    vec3 point_color = point.color * point.intensity;
-    f_color = vec4(result_color, 1.0);
+    vec3 point_color = point.color * point.intensity * attenuation;
+    f_color = vec4(result_color * point_color, 1.0);
 }
@@ -0,0 +1,22 @@
+#version 450
+
+layout(location = 0) in vec2 in_uv;
+
+layout(set = 1, binding = 0) uniform sampler2D tex;
+
+layout(push_constant) uniform TintData {
+    vec4 tint;
+} draw;
+
+layout(location = 0) out vec4 f_color;
+
+void main() {
+    vec4 color = texture(tex, in_uv) * draw.tint;
+
+    // cutout, e.g. foliage
+    if (color.a < 0.5) {
+        discard;
+    }
+
+    f_color = vec4(color.rgb, 1.0);
+}
@@ -0,0 +1,58 @@
+#version 450
+
+layout(location = 0) in vec2 in_uv;
+
+#define MAX_POINT_LIGHTS 64
+
+struct PointLightData {
+    vec3 position;
+    float radius;
+    vec3 color;
+    float intensity;
+};
+
+// the frame's lights, evaluated per fragment as the lighting subpass does
+layout(set = 1, binding = 0) uniform LightData {
+    // framebuffer pixels to world coordinates, for the view being drawn
+    mat4 screen_to_world;
+    // ambient and directional lights, which light everything evenly
+    vec3 ambient;
+    uint point_count;
+    PointLightData points[MAX_POINT_LIGHTS];
+} light;
+
+layout(set = 2, binding = 0) uniform sampler2D tex;
+
+layout(push_constant) uniform TintData {
+    vec4 tint;
+} draw;
+
+layout(location = 0) out vec4 f_color;
+
+// fades to 0 at the radius; lights without a radius reach everywhere
+float falloff(vec2 world, vec2 position, float radius) {
+    if (radius <= 0.0) {
+        return 1.0;
+    }
+
+    float t = clamp(1.0 - distance(world, position) / radius, 0.0, 1.0);
+    return t * t;
+}
+
+vec3 frame_light() {
+    vec2 world = (light.screen_to_world * vec4(gl_FragCoord.xy, 0.0, 1.0)).xy;
+    vec3 total = light.ambient;
+
+    for (uint i = 0u; i < min(light.point_count, uint(MAX_POINT_LIGHTS)); i++) {
+        PointLightData point = light.points[i];
+        total += point.color * point.intensity
+            * falloff(world, point.position.xy, point.radius);
+    }
+
+    return total;
+}
+
+void main() {
+    vec4 color = texture(tex, in_uv) * draw.tint;
+    f_color = vec4(color.rgb * frame_light(), color.a);
+}
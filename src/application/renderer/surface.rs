@@ -0,0 +1,416 @@
+use vulkano::device::{Device, DeviceOwned};
+use vulkano::format::Format;
+use vulkano::image::view::{ImageView, ImageViewAbstract};
+use vulkano::image::{AttachmentImage, ImageAccess, ImageUsage, SampleCount, SwapchainImage};
+use vulkano::memory::allocator::StandardMemoryAllocator;
+use vulkano::render_pass::{Framebuffer, RenderPass};
+use vulkano::swapchain::{
+    PresentMode, Surface, Swapchain, SwapchainCreateInfo, SwapchainCreationError,
+};
+
+use winit::dpi::PhysicalPosition;
+use winit::window::{CursorGrabMode, Window, WindowId};
+
+use std::sync::Arc;
+
+use super::attachments::Attachments;
+use super::camera::Camera2D;
+use super::letterbox::Letterbox;
+use super::view::View;
+use crate::application::window::{self, FullscreenMode};
+
+/// A window the renderer draws to, with its own swapchain, size dependent
+/// attachments and camera. Windows share the renderer's device, pipelines
+/// and allocators.
+pub struct WindowSurface {
+    surface: Arc<Surface>,
+    swapchain: Arc<Swapchain>,
+    present_mode: PresentMode,
+    pub(super) images: Vec<Arc<SwapchainImage>>,
+    pub(super) attachments: Attachments,
+    pub(super) scene_image: Option<Arc<AttachmentImage>>,
+    virtual_resolution: Option<[u32; 2]>,
+    pub(super) letterbox: Letterbox,
+    pub(super) views: Vec<View>,
+    outdated: bool,
+}
+
+impl WindowSurface {
+    /// Creates the swapchain for a surface, rendering with the given render
+    /// pass.
+    /// * Panics if the surface does not support `image_format`, which every
+    ///   window has to share as the pipelines are built for it.
+    pub(super) fn new(
+        device: Arc<Device>,
+        surface: Arc<Surface>,
+        image_format: Format,
+        memory_allocator: &StandardMemoryAllocator,
+        render_pass: Arc<RenderPass>,
+        samples: SampleCount,
+        virtual_resolution: Option<[u32; 2]>,
+    ) -> WindowSurface {
+        let caps = device
+            .physical_device()
+            .surface_capabilities(&surface, Default::default())
+            .unwrap();
+
+        let image_usage = caps.supported_usage_flags;
+        let composite_alpha = caps.supported_composite_alpha.iter().next().unwrap();
+
+        let format_supported = device
+            .physical_device()
+            .surface_formats(&surface, Default::default())
+            .unwrap()
+            .iter()
+            .any(|&(format, _)| format == image_format);
+        assert!(
+            format_supported,
+            "Window surface does not support {:?}",
+            image_format
+        );
+
+        let window = surface.object().unwrap().downcast_ref::<Window>().unwrap();
+        let image_extent: [u32; 2] = window.inner_size().into();
+
+        let (swapchain, images) = Swapchain::new(
+            device,
+            surface.clone(),
+            SwapchainCreateInfo {
+                min_image_count: caps.min_image_count,
+                image_format: Some(image_format),
+                image_extent,
+                image_usage,
+                composite_alpha,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        // the swapchain defaults to fifo, the only mode guaranteed to be supported
+        let present_mode = swapchain.present_mode();
+
+        let (attachments, scene_image, letterbox) = window_size_dependent_setup(
+            memory_allocator,
+            &images,
+            render_pass,
+            samples,
+            virtual_resolution,
+        );
+
+        WindowSurface {
+            surface,
+            swapchain,
+            present_mode,
+            images,
+            attachments,
+            scene_image,
+            virtual_resolution,
+            letterbox,
+            views: vec![View::default()],
+            outdated: false,
+        }
+    }
+
+    pub fn id(&self) -> WindowId {
+        self.window().id()
+    }
+
+    pub fn window(&self) -> &Window {
+        self.surface
+            .object()
+            .unwrap()
+            .downcast_ref::<Window>()
+            .unwrap()
+    }
+
+    pub(super) fn swapchain(&self) -> Arc<Swapchain> {
+        self.swapchain.clone()
+    }
+
+    /// Switches between windowed, borderless and exclusive fullscreen on the
+    /// window's current monitor. The swapchain is recreated by the resize
+    /// that follows.
+    pub fn set_fullscreen(&mut self, mode: FullscreenMode) {
+        let window = self.window();
+        let size = window.inner_size().into();
+
+        window.set_fullscreen(window::fullscreen(mode, window.current_monitor(), size));
+    }
+
+    pub fn fullscreen(&self) -> FullscreenMode {
+        match self.window().fullscreen() {
+            None => FullscreenMode::Windowed,
+            Some(winit::window::Fullscreen::Borderless(_)) => FullscreenMode::Borderless,
+            Some(winit::window::Fullscreen::Exclusive(_)) => FullscreenMode::Exclusive,
+        }
+    }
+
+    /// Keeps the cursor inside the window, e.g. for mouse look.
+    /// * Returns false if the platform supports neither confining nor
+    ///   locking the cursor.
+    pub fn set_cursor_grab(&mut self, grab: bool) -> bool {
+        let window = self.window();
+
+        if !grab {
+            return window.set_cursor_grab(CursorGrabMode::None).is_ok();
+        }
+
+        window
+            .set_cursor_grab(CursorGrabMode::Confined)
+            .or_else(|_| window.set_cursor_grab(CursorGrabMode::Locked))
+            .is_ok()
+    }
+
+    pub fn set_cursor_visible(&mut self, visible: bool) {
+        self.window().set_cursor_visible(visible);
+    }
+
+    pub fn set_title(&mut self, title: &str) {
+        self.window().set_title(title);
+    }
+
+    /// Returns true if the window has no area to render to.
+    pub fn is_minimized(&self) -> bool {
+        let size = self.window().inner_size();
+        size.width == 0 || size.height == 0
+    }
+
+    /// Ratio of physical to logical pixels of the window's monitor.
+    pub fn scale_factor(&self) -> f64 {
+        self.window().scale_factor()
+    }
+
+    /// Marks the swapchain as out of date so it is recreated at the start of
+    /// the window's next frame. Several resizes in one frame then only
+    /// recreate it once, with the window's final size.
+    pub fn invalidate_swapchain(&mut self) {
+        self.outdated = true;
+    }
+
+    pub(super) fn is_outdated(&self) -> bool {
+        self.outdated
+    }
+
+    /// Recreates the swapchain and the size dependent attachments for the
+    /// window's current size.
+    /// * A minimized window has no area to render to, so the swapchain stays
+    ///   out of date until it is restored.
+    pub(super) fn recreate_swapchain(
+        &mut self,
+        memory_allocator: &StandardMemoryAllocator,
+        render_pass: Arc<RenderPass>,
+        samples: SampleCount,
+    ) {
+        self.outdated = true;
+
+        let image_extent: [u32; 2] = self.window().inner_size().into();
+        if image_extent.contains(&0) {
+            return;
+        }
+
+        let (new_swapchain, new_images) = match self.swapchain.recreate(SwapchainCreateInfo {
+            image_extent,
+            present_mode: self.present_mode,
+            ..self.swapchain.create_info()
+        }) {
+            Ok(r) => r,
+            Err(SwapchainCreationError::ImageExtentNotSupported { .. }) => return,
+            Err(err) => panic!("Failed to recreate swapchain: {:?}", err),
+        };
+
+        let (new_attachments, new_scene_image, new_letterbox) = window_size_dependent_setup(
+            memory_allocator,
+            &new_images,
+            render_pass,
+            samples,
+            self.virtual_resolution,
+        );
+
+        self.swapchain = new_swapchain;
+        self.images = new_images;
+        self.attachments = new_attachments;
+        self.scene_image = new_scene_image;
+        self.letterbox = new_letterbox;
+
+        self.outdated = false;
+    }
+
+    /// Returns the resolution the scene is rendered at. This is the virtual
+    /// resolution if one is set, otherwise the window size.
+    pub fn render_resolution(&self) -> [u32; 2] {
+        [
+            self.attachments.viewport.dimensions[0] as u32,
+            self.attachments.viewport.dimensions[1] as u32,
+        ]
+    }
+
+    /// Sets or clears the fixed virtual resolution. The size dependent
+    /// attachments are rebuilt at the start of the next frame.
+    pub fn set_virtual_resolution(&mut self, virtual_resolution: Option<[u32; 2]>) {
+        if virtual_resolution != self.virtual_resolution {
+            self.virtual_resolution = virtual_resolution;
+            self.invalidate_swapchain();
+        }
+    }
+
+    /// Converts a cursor position in window pixels into pixels of the render
+    /// resolution, accounting for upscaling and letterboxing.
+    /// * Returns None if the position falls on the letterbox bars.
+    pub fn window_to_render(&self, position: PhysicalPosition<f64>) -> Option<[f32; 2]> {
+        self.letterbox
+            .window_to_render(position.into(), self.render_resolution())
+    }
+
+    /// Converts a cursor position in window pixels into world coordinates
+    /// through the camera of the view under the cursor.
+    pub fn window_to_world(&self, position: PhysicalPosition<f64>) -> Option<[f32; 2]> {
+        let resolution = self.render_resolution();
+        let position = self.window_to_render(position)?;
+
+        self.views.iter().find_map(|view| {
+            view.render_to_view(position, resolution)
+                .map(|position| view.camera.view_to_world(position))
+        })
+    }
+
+    /// Sets the camera of the first view. Draws queued after this call in
+    /// that view are viewed through it.
+    pub fn set_camera(&mut self, camera: Camera2D) {
+        self.views[0].camera = camera;
+    }
+
+    pub fn camera(&self) -> Camera2D {
+        self.views[0].camera
+    }
+
+    /// Splits the window into views, each drawn through its own camera, e.g.
+    /// `View::split(2)` for two players. No views means one view covering
+    /// the whole window.
+    /// * Views should cover the window: what no view covers is undefined.
+    pub fn set_views(&mut self, views: Vec<View>) {
+        self.views = match views.is_empty() {
+            true => vec![View::default()],
+            false => views,
+        };
+    }
+
+    pub fn views(&self) -> &[View] {
+        &self.views
+    }
+
+    pub fn views_mut(&mut self) -> &mut [View] {
+        &mut self.views
+    }
+
+    /// Returns the present modes supported by the window surface.
+    pub fn supported_present_modes(&self) -> Vec<PresentMode> {
+        self.swapchain
+            .device()
+            .physical_device()
+            .surface_present_modes(&self.surface)
+            .unwrap()
+            .collect()
+    }
+
+    /// Returns the present mode currently requested for the swapchain.
+    pub fn present_mode(&self) -> PresentMode {
+        self.present_mode
+    }
+
+    /// Switches the swapchain to the given present mode from the next frame.
+    /// * Returns false and leaves the swapchain untouched if the surface does
+    ///   not support the requested mode.
+    pub fn set_present_mode(&mut self, present_mode: PresentMode) -> bool {
+        if !self.supported_present_modes().contains(&present_mode) {
+            return false;
+        }
+
+        if present_mode != self.present_mode {
+            self.present_mode = present_mode;
+            self.invalidate_swapchain();
+        }
+
+        true
+    }
+
+    /// Enables or disables vertical sync.
+    /// * Disabling vsync prefers mailbox, then immediate, and stays on fifo if
+    ///   neither is supported.
+    pub fn set_vsync(&mut self, enabled: bool) {
+        if enabled {
+            self.set_present_mode(PresentMode::Fifo);
+            return;
+        }
+
+        if !self.set_present_mode(PresentMode::Mailbox) {
+            self.set_present_mode(PresentMode::Immediate);
+        }
+    }
+
+    /// Returns true if the current present mode waits for vertical blanking.
+    pub fn vsync(&self) -> bool {
+        matches!(
+            self.present_mode,
+            PresentMode::Fifo | PresentMode::FifoRelaxed
+        )
+    }
+
+    pub(super) fn framebuffer(&self, image_index: u32) -> Arc<Framebuffer> {
+        // a virtual resolution renders every frame into the same scene image
+        match self.scene_image {
+            Some(_) => self.attachments.framebuffers[0].clone(),
+            None => self.attachments.framebuffers[image_index as usize].clone(),
+        }
+    }
+}
+
+fn window_size_dependent_setup(
+    memory_allocator: &StandardMemoryAllocator,
+    images: &[Arc<SwapchainImage>],
+    render_pass: Arc<RenderPass>,
+    samples: SampleCount,
+    virtual_resolution: Option<[u32; 2]>,
+) -> (Attachments, Option<Arc<AttachmentImage>>, Letterbox) {
+    let window_dimensions = images[0].dimensions().width_height();
+    let dimensions = virtual_resolution.unwrap_or(window_dimensions);
+
+    let letterbox = match virtual_resolution {
+        Some(resolution) => Letterbox::new(window_dimensions, resolution),
+        None => Letterbox::fill(window_dimensions),
+    };
+
+    // with a virtual resolution the scene is lit into an offscreen image
+    // which is blitted onto the swapchain image afterwards
+    let scene_image = virtual_resolution.map(|resolution| {
+        AttachmentImage::with_usage(
+            memory_allocator,
+            resolution,
+            images[0].format(),
+            ImageUsage {
+                color_attachment: true,
+                transfer_src: true,
+                ..ImageUsage::empty()
+            },
+        )
+        .unwrap()
+    });
+
+    let final_views: Vec<Arc<dyn ImageViewAbstract>> = match &scene_image {
+        Some(scene_image) => vec![ImageView::new_default(scene_image.clone()).unwrap()],
+        None => images
+            .iter()
+            .map(|image| ImageView::new_default(image.clone()).unwrap() as _)
+            .collect(),
+    };
+
+    let attachments = Attachments::new(
+        memory_allocator,
+        final_views,
+        dimensions,
+        images[0].format(),
+        render_pass,
+        samples,
+    );
+
+    (attachments, scene_image, letterbox)
+}
@@ -0,0 +1,122 @@
+use vulkano::format::Format;
+use vulkano::image::view::ImageView;
+use vulkano::image::{AttachmentImage, ImageUsage, SampleCount};
+use vulkano::memory::allocator::StandardMemoryAllocator;
+use vulkano::render_pass::{Framebuffer, RenderPass};
+
+use winit::window::WindowId;
+
+use std::sync::Arc;
+
+use super::attachments::Attachments;
+use super::camera::Camera2D;
+use super::texture::Texture;
+use super::view::View;
+
+/// Handle to a render target owned by the Renderer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct RenderTargetId(pub(super) usize);
+
+/// Where a frame is drawn: a window's swapchain or an offscreen render
+/// target.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Target {
+    Window(WindowId),
+    Texture(RenderTargetId),
+}
+
+/// An offscreen image the renderer can draw a frame into, lit like any
+/// window, through its own camera. Its result is sampled as a `Texture`,
+/// e.g. by a `Quad` showing a minimap or a security camera monitor.
+/// * The texture shows the last finished frame of the target. Render targets
+///   are drawn before the windows, so windows see the current frame.
+pub struct RenderTarget {
+    texture: Arc<Texture>,
+    pub(super) attachments: Attachments,
+    pub(super) views: Vec<View>,
+}
+
+impl RenderTarget {
+    pub(super) fn new(
+        memory_allocator: &StandardMemoryAllocator,
+        dimensions: [u32; 2],
+        format: Format,
+        render_pass: Arc<RenderPass>,
+        samples: SampleCount,
+    ) -> RenderTarget {
+        let image = AttachmentImage::with_usage(
+            memory_allocator,
+            dimensions,
+            format,
+            ImageUsage {
+                color_attachment: true,
+                sampled: true,
+                ..ImageUsage::empty()
+            },
+        )
+        .unwrap();
+
+        let attachments = Attachments::new(
+            memory_allocator,
+            vec![ImageView::new_default(image.clone()).unwrap()],
+            dimensions,
+            format,
+            render_pass,
+            samples,
+        );
+
+        // the lit image is opaque, whatever was drawn into it
+        let texture = Arc::new(Texture::new(
+            ImageView::new_default(image).unwrap(),
+            dimensions,
+            false,
+        ));
+
+        RenderTarget {
+            texture,
+            attachments,
+            views: vec![View::default()],
+        }
+    }
+
+    /// The target's image, for textured renderables to sample.
+    pub fn texture(&self) -> Arc<Texture> {
+        self.texture.clone()
+    }
+
+    pub fn dimensions(&self) -> [u32; 2] {
+        self.texture.dimensions()
+    }
+
+    /// Sets the camera of the first view. Draws queued after this call in
+    /// that view are viewed through it.
+    pub fn set_camera(&mut self, camera: Camera2D) {
+        self.views[0].camera = camera;
+    }
+
+    pub fn camera(&self) -> Camera2D {
+        self.views[0].camera
+    }
+
+    /// Splits the target into views, each drawn through its own camera. No
+    /// views means one view covering the whole target.
+    /// * Views should cover the target: what no view covers is undefined.
+    pub fn set_views(&mut self, views: Vec<View>) {
+        self.views = match views.is_empty() {
+            true => vec![View::default()],
+            false => views,
+        };
+    }
+
+    pub fn views(&self) -> &[View] {
+        &self.views
+    }
+
+    pub fn views_mut(&mut self) -> &mut [View] {
+        &mut self.views
+    }
+
+    pub(super) fn framebuffer(&self) -> Arc<Framebuffer> {
+        self.attachments.framebuffers[0].clone()
+    }
+}
@@ -0,0 +1,252 @@
+//! Reads AngelCode BMFont descriptions (.fnt), in the text or XML format, with
+//! their PNG pages.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use super::super::texture::load_png;
+use super::FontError;
+
+/// A bitmap font with its pages decoded on the CPU.
+pub(crate) struct BitmapFont {
+    pub line_height: f32,
+    /// Distance from the top of a line to the baseline.
+    pub base: f32,
+    /// RGBA8 pages and their dimensions.
+    pub pages: Vec<(Vec<u8>, [u32; 2])>,
+    pub glyphs: HashMap<char, BitmapGlyph>,
+    pub kerning: HashMap<(char, char), f32>,
+}
+
+#[derive(Clone, Copy)]
+pub(crate) struct BitmapGlyph {
+    pub page: usize,
+    pub position: [u32; 2],
+    pub size: [u32; 2],
+    /// Top left corner of the glyph image relative to the top of the line.
+    pub offset: [f32; 2],
+    pub advance: f32,
+}
+
+/// A line of the text format or an element of the XML format: a tag and its
+/// attributes.
+struct Entry {
+    tag: String,
+    attributes: HashMap<String, String>,
+}
+
+impl BitmapFont {
+    pub fn load(path: &Path) -> Result<BitmapFont, FontError> {
+        let dir = path.parent().unwrap_or_else(|| Path::new(""));
+        let bytes = std::fs::read(path)?;
+
+        BitmapFont::parse(&bytes, |file| {
+            Ok(load_png(&dir.join(file).to_string_lossy())?)
+        })
+    }
+
+    /// Reads a description, getting each page's RGBA8 image and dimensions
+    /// from `load_page` by its file name.
+    pub fn parse(
+        bytes: &[u8],
+        load_page: impl FnMut(&str) -> Result<(Vec<u8>, [u32; 2]), FontError>,
+    ) -> Result<BitmapFont, FontError> {
+        if bytes.starts_with(b"BMF") {
+            return Err(FontError::Unsupported(
+                "binary BMFont files; export as text or XML".to_string(),
+            ));
+        }
+
+        let text = std::str::from_utf8(bytes)
+            .map_err(|_| FontError::Invalid("BMFont file is not UTF-8".to_string()))?;
+        let entries = match text.trim_start().starts_with('<') {
+            true => parse_xml(text)?,
+            false => parse_text(text)?,
+        };
+
+        build(entries, load_page)
+    }
+
+    /// RGBA8 image of a glyph, cut from its page.
+    pub fn glyph_pixels(&self, glyph: &BitmapGlyph) -> Vec<u8> {
+        let (page, [page_width, _]) = &self.pages[glyph.page];
+        let [width, height] = glyph.size;
+        let mut pixels = Vec::with_capacity((width * height * 4) as usize);
+
+        for y in glyph.position[1]..glyph.position[1] + height {
+            let start = ((y * page_width + glyph.position[0]) * 4) as usize;
+            pixels.extend_from_slice(&page[start..start + (width * 4) as usize]);
+        }
+
+        pixels
+    }
+}
+
+fn build(
+    entries: Vec<Entry>,
+    mut load_page: impl FnMut(&str) -> Result<(Vec<u8>, [u32; 2]), FontError>,
+) -> Result<BitmapFont, FontError> {
+    let mut font = BitmapFont {
+        line_height: 0.0,
+        base: 0.0,
+        pages: Vec::new(),
+        glyphs: HashMap::new(),
+        kerning: HashMap::new(),
+    };
+    let mut page_files = Vec::new();
+
+    for entry in &entries {
+        match entry.tag.as_str() {
+            "common" => {
+                if entry
+                    .attributes
+                    .get("packed")
+                    .is_some_and(|packed| packed != "0")
+                {
+                    return Err(FontError::Unsupported(
+                        "BMFont glyphs packed into color channels".to_string(),
+                    ));
+                }
+                font.line_height = entry.get("lineHeight")?;
+                font.base = entry.get("base")?;
+            }
+            "page" => {
+                let id: usize = entry.get("id")?;
+                let file = entry.attributes.get("file").ok_or_else(|| {
+                    FontError::Invalid("BMFont page has no file attribute".to_string())
+                })?;
+                if page_files.len() <= id {
+                    page_files.resize(id + 1, String::new());
+                }
+                page_files[id] = file.clone();
+            }
+            "char" => {
+                // some tools write the fallback glyph with id -1
+                let ch = match to_char(entry.get("id")?) {
+                    Some(ch) => ch,
+                    None => continue,
+                };
+
+                font.glyphs.insert(
+                    ch,
+                    BitmapGlyph {
+                        page: entry.get("page")?,
+                        position: [entry.get("x")?, entry.get("y")?],
+                        size: [entry.get("width")?, entry.get("height")?],
+                        offset: [entry.get("xoffset")?, entry.get("yoffset")?],
+                        advance: entry.get("xadvance")?,
+                    },
+                );
+            }
+            "kerning" => {
+                let pair = (to_char(entry.get("first")?), to_char(entry.get("second")?));
+                if let (Some(first), Some(second)) = pair {
+                    font.kerning.insert((first, second), entry.get("amount")?);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    for file in &page_files {
+        font.pages.push(load_page(file)?);
+    }
+
+    for (ch, glyph) in &font.glyphs {
+        let fits = font
+            .pages
+            .get(glyph.page)
+            .is_some_and(|(_, [width, height])| {
+                glyph.position[0] + glyph.size[0] <= *width
+                    && glyph.position[1] + glyph.size[1] <= *height
+            });
+        if !fits {
+            return Err(FontError::Invalid(format!(
+                "glyph {:?} is outside its page",
+                ch
+            )));
+        }
+    }
+
+    Ok(font)
+}
+
+/// Reads the text format: one tag per line followed by `key=value` pairs,
+/// values optionally quoted.
+fn parse_text(text: &str) -> Result<Vec<Entry>, FontError> {
+    let mut entries = Vec::new();
+
+    for line in text.lines() {
+        let line = line.trim();
+        let (tag, mut rest) = match line.split_once(char::is_whitespace) {
+            Some((tag, rest)) => (tag, rest.trim_start()),
+            None if !line.is_empty() => (line, ""),
+            None => continue,
+        };
+
+        let mut attributes = HashMap::new();
+        while !rest.is_empty() {
+            let (key, after_key) = rest
+                .split_once('=')
+                .ok_or_else(|| FontError::Invalid(format!("expected key=value in {}", line)))?;
+
+            let (value, after_value) = match after_key.strip_prefix('"') {
+                Some(quoted) => quoted.split_once('"').ok_or_else(|| {
+                    FontError::Invalid(format!("unterminated string in {}", line))
+                })?,
+                None => after_key
+                    .split_once(char::is_whitespace)
+                    .unwrap_or((after_key, "")),
+            };
+
+            attributes.insert(key.trim().to_string(), value.to_string());
+            rest = after_value.trim_start();
+        }
+
+        entries.push(Entry {
+            tag: tag.to_string(),
+            attributes,
+        });
+    }
+
+    Ok(entries)
+}
+
+/// Reads the XML format, where every element is an entry.
+fn parse_xml(xml: &str) -> Result<Vec<Entry>, FontError> {
+    let document = roxmltree::Document::parse(xml)
+        .map_err(|err| FontError::Invalid(format!("bad BMFont XML: {}", err)))?;
+
+    Ok(document
+        .descendants()
+        .filter(|node| node.is_element())
+        .map(|node| {
+            let attributes = node
+                .attributes()
+                .map(|attribute| (attribute.name().to_string(), attribute.value().to_string()))
+                .collect();
+            Entry {
+                tag: node.tag_name().name().to_string(),
+                attributes,
+            }
+        })
+        .collect())
+}
+
+impl Entry {
+    fn get<T: std::str::FromStr>(&self, name: &str) -> Result<T, FontError> {
+        let value = self
+            .attributes
+            .get(name)
+            .ok_or_else(|| FontError::Invalid(format!("{} has no {} attribute", self.tag, name)))?;
+
+        value
+            .trim()
+            .parse()
+            .map_err(|_| FontError::Invalid(format!("unexpected value {} for {}", value, name)))
+    }
+}
+
+fn to_char(id: i64) -> Option<char> {
+    u32::try_from(id).ok().and_then(char::from_u32)
+}
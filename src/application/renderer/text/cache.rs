@@ -0,0 +1,277 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use super::super::texture::Texture;
+use super::super::Renderer;
+
+/// Side of a glyph page in pixels. Glyphs larger than a page get a page of
+/// their own.
+const PAGE_SIZE: u32 = 512;
+
+/// Transparent pixels between glyphs, so filtering never picks up a
+/// neighbour.
+const PADDING: u32 = 1;
+
+/// Pages a cache may fill before it is emptied and glyphs are drawn again as
+/// they are needed.
+const MAX_PAGES: usize = 4;
+
+/// A glyph placed on a cache page.
+#[derive(Clone, Copy, Debug)]
+pub struct CachedGlyph {
+    pub page: usize,
+    pub uv: [[f32; 2]; 2],
+    pub size: [f32; 2],
+    /// Top left corner of the glyph image relative to the pen position on the
+    /// baseline, y growing down.
+    pub offset: [f32; 2],
+}
+
+/// Glyph images packed onto RGBA pages as they are first drawn.
+/// * Glyphs are cached once, white or in the bitmap font's own colors, and
+///   spans are colored by tinting the draw.
+/// * Pages are kept on the CPU and uploaded again once glyphs were added.
+#[derive(Default)]
+pub struct GlyphCache {
+    pages: Vec<Page>,
+    /// None for glyphs without an image, e.g. spaces.
+    glyphs: HashMap<char, Option<CachedGlyph>>,
+}
+
+struct Page {
+    pixels: Vec<u8>,
+    size: u32,
+    /// Shelves as (y, height, next free x).
+    shelves: Vec<[u32; 3]>,
+    next_y: u32,
+    texture: Option<Arc<Texture>>,
+    dirty: bool,
+}
+
+impl GlyphCache {
+    /// The cached glyph, or None if it was never added.
+    pub fn get(&self, ch: char) -> Option<Option<CachedGlyph>> {
+        self.glyphs.get(&ch).copied()
+    }
+
+    /// Packs an RGBA8 glyph image. Empty images are remembered as glyphs
+    /// without an image.
+    pub fn insert(
+        &mut self,
+        ch: char,
+        pixels: &[u8],
+        size: [u32; 2],
+        offset: [f32; 2],
+    ) -> Option<CachedGlyph> {
+        let glyph = match size[0] == 0 || size[1] == 0 {
+            true => None,
+            false => Some(self.pack(pixels, size, offset)),
+        };
+
+        self.glyphs.insert(ch, glyph);
+        glyph
+    }
+
+    fn pack(&mut self, pixels: &[u8], size: [u32; 2], offset: [f32; 2]) -> CachedGlyph {
+        let [width, height] = size;
+
+        let placement = self.pages.iter_mut().enumerate().find_map(|(index, page)| {
+            let shelf = page
+                .shelves
+                .iter_mut()
+                .find(|[_, shelf_height, x]| height <= *shelf_height && x + width <= page.size);
+
+            if let Some([y, _, x]) = shelf {
+                let position = [*x, *y];
+                *x += width + PADDING;
+                return Some((index, position));
+            }
+
+            match page.next_y + height <= page.size && width <= page.size {
+                true => {
+                    let position = [0, page.next_y];
+                    page.shelves.push([page.next_y, height, width + PADDING]);
+                    page.next_y += height + PADDING;
+                    Some((index, position))
+                }
+                false => None,
+            }
+        });
+
+        let (index, position) = placement.unwrap_or_else(|| {
+            let page_size = PAGE_SIZE.max(width).max(height);
+            self.pages.push(Page {
+                pixels: vec![0; (page_size * page_size * 4) as usize],
+                size: page_size,
+                shelves: vec![[0, height, width + PADDING]],
+                next_y: height + PADDING,
+                texture: None,
+                dirty: true,
+            });
+            (self.pages.len() - 1, [0, 0])
+        });
+
+        let page = &mut self.pages[index];
+        for row in 0..height {
+            let source = (row * width * 4) as usize;
+            let dest = (((position[1] + row) * page.size + position[0]) * 4) as usize;
+            page.pixels[dest..dest + (width * 4) as usize]
+                .copy_from_slice(&pixels[source..source + (width * 4) as usize]);
+        }
+        page.dirty = true;
+
+        let page_size = page.size as f32;
+        CachedGlyph {
+            page: index,
+            uv: [
+                [
+                    position[0] as f32 / page_size,
+                    position[1] as f32 / page_size,
+                ],
+                [
+                    (position[0] + width) as f32 / page_size,
+                    (position[1] + height) as f32 / page_size,
+                ],
+            ],
+            size: [width as f32, height as f32],
+            offset,
+        }
+    }
+
+    /// Uploads pages that changed since they were last uploaded. Blocks until
+    /// the uploads are done.
+    pub fn upload(&mut self, renderer: &Renderer) {
+        for page in self.pages.iter_mut().filter(|page| page.dirty) {
+            page.texture = Some(renderer.create_texture(&page.pixels, [page.size; 2]));
+            page.dirty = false;
+        }
+    }
+
+    /// Texture of a page, once uploaded.
+    pub fn texture(&self, page: usize) -> Option<Arc<Texture>> {
+        self.pages.get(page).and_then(|page| page.texture.clone())
+    }
+
+    pub fn clear(&mut self) {
+        self.pages.clear();
+        self.glyphs.clear();
+    }
+
+    /// Empties the cache if it outgrew `MAX_PAGES`, e.g. after text that
+    /// went through a lot of characters once.
+    pub fn trim(&mut self) {
+        if self.pages.len() > MAX_PAGES {
+            self.clear();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A solid glyph image whose pixels all hold `value`.
+    fn image(size: [u32; 2], value: u8) -> Vec<u8> {
+        vec![value; (size[0] * size[1] * 4) as usize]
+    }
+
+    fn insert(cache: &mut GlyphCache, ch: char, size: [u32; 2]) -> CachedGlyph {
+        cache
+            .insert(ch, &image(size, 255), size, [0.0, 0.0])
+            .unwrap()
+    }
+
+    /// Top left pixel of a cached glyph on its page.
+    fn position(glyph: &CachedGlyph) -> [u32; 2] {
+        glyph.uv[0].map(|uv| (uv * PAGE_SIZE as f32).round() as u32)
+    }
+
+    #[test]
+    fn glyphs_fill_shelves_then_new_pages() {
+        let mut cache = GlyphCache::default();
+        let glyphs = ['a', 'b', 'c', 'd', 'e'].map(|ch| insert(&mut cache, ch, [200, 200]));
+
+        let places = glyphs.map(|glyph| (glyph.page, position(&glyph)));
+        assert_eq!(
+            places,
+            [
+                (0, [0, 0]),
+                (0, [201, 0]),
+                (0, [0, 201]),
+                (0, [201, 201]),
+                (1, [0, 0]),
+            ]
+        );
+        assert_eq!(cache.pages.len(), 2);
+    }
+
+    #[test]
+    fn glyphs_go_on_the_first_shelf_tall_enough() {
+        let mut cache = GlyphCache::default();
+        insert(&mut cache, 'a', [10, 20]);
+        insert(&mut cache, 'b', [10, 30]);
+
+        let short = insert(&mut cache, 'c', [10, 15]);
+        assert_eq!(position(&short), [11, 0]);
+        let tall = insert(&mut cache, 'd', [10, 25]);
+        assert_eq!(position(&tall), [11, 21]);
+        assert_eq!(cache.pages[0].shelves, [[0, 20, 22], [21, 30, 22]]);
+    }
+
+    #[test]
+    fn glyphs_larger_than_a_page_get_their_own() {
+        let mut cache = GlyphCache::default();
+        insert(&mut cache, 'a', [10, 10]);
+        let large = insert(&mut cache, 'b', [600, 20]);
+
+        assert_eq!(large.page, 1);
+        assert_eq!(cache.pages[1].size, 600);
+        assert_eq!(large.uv, [[0.0, 0.0], [1.0, 20.0 / 600.0]]);
+    }
+
+    #[test]
+    fn packing_copies_the_image_and_keeps_padding_clear() {
+        let mut cache = GlyphCache::default();
+        insert(&mut cache, 'a', [2, 2]);
+        let glyph = cache
+            .insert('b', &image([3, 2], 7), [3, 2], [1.0, -2.0])
+            .unwrap();
+
+        assert_eq!(glyph.size, [3.0, 2.0]);
+        assert_eq!(glyph.offset, [1.0, -2.0]);
+
+        let page = &cache.pages[0];
+        let pixel = |x: u32, y: u32| page.pixels[((y * page.size + x) * 4) as usize];
+        assert_eq!([pixel(0, 0), pixel(1, 1)], [255, 255]);
+        assert_eq!(pixel(2, 0), 0);
+        assert_eq!([pixel(3, 0), pixel(5, 1)], [7, 7]);
+        assert_eq!(pixel(6, 0), 0);
+        assert_eq!(pixel(3, 2), 0);
+    }
+
+    #[test]
+    fn empty_glyphs_are_remembered_without_an_image() {
+        let mut cache = GlyphCache::default();
+
+        assert!(cache.get(' ').is_none());
+        assert!(cache.insert(' ', &[], [0, 8], [0.0, 0.0]).is_none());
+        assert!(matches!(cache.get(' '), Some(None)));
+        assert!(cache.pages.is_empty());
+    }
+
+    #[test]
+    fn trim_empties_a_cache_past_its_page_limit() {
+        let mut cache = GlyphCache::default();
+        for ch in ['a', 'b', 'c', 'd'] {
+            insert(&mut cache, ch, [PAGE_SIZE, PAGE_SIZE]);
+        }
+
+        cache.trim();
+        assert_eq!(cache.pages.len(), MAX_PAGES);
+
+        insert(&mut cache, 'e', [PAGE_SIZE, PAGE_SIZE]);
+        cache.trim();
+        assert!(cache.pages.is_empty());
+        assert!(cache.get('a').is_none());
+    }
+}
@@ -0,0 +1,221 @@
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+
+use super::super::texture::Texture;
+use super::super::Renderer;
+use super::bmfont::BitmapFont;
+use super::cache::{CachedGlyph, GlyphCache};
+
+/// A typeface at one size, with the glyphs drawn so far cached on textures.
+/// * TrueType and OpenType fonts are rasterized as glyphs are first drawn.
+/// * Bitmap fonts come from BMFont descriptions and their PNG pages, and are
+///   scaled with the text's matrix rather than resized.
+/// * Sizes and metrics are in pixels, y growing down.
+pub struct Font {
+    source: FontSource,
+    cache: GlyphCache,
+}
+
+enum FontSource {
+    Outline { font: Arc<fontdue::Font>, size: f32 },
+    Bitmap(BitmapFont),
+}
+
+impl Font {
+    /// Loads a TrueType (.ttf) or OpenType (.otf) file, `size` pixels per em.
+    pub fn load(path: &str, size: f32) -> Result<Font, FontError> {
+        Font::from_bytes(&std::fs::read(path)?, size)
+    }
+
+    /// Reads a TrueType or OpenType font from memory, `size` pixels per em.
+    pub fn from_bytes(bytes: &[u8], size: f32) -> Result<Font, FontError> {
+        let settings = fontdue::FontSettings {
+            scale: size,
+            ..fontdue::FontSettings::default()
+        };
+        let font = fontdue::Font::from_bytes(bytes, settings)
+            .map_err(|err| FontError::Invalid(err.to_string()))?;
+
+        Ok(Font::new(FontSource::Outline {
+            font: Arc::new(font),
+            size,
+        }))
+    }
+
+    /// Loads an AngelCode BMFont description (.fnt) in the text or XML
+    /// format. Its pages are read from PNG files next to it.
+    pub fn load_bmfont(path: &str) -> Result<Font, FontError> {
+        Ok(Font::new(FontSource::Bitmap(BitmapFont::load(Path::new(
+            path,
+        ))?)))
+    }
+
+    fn new(source: FontSource) -> Font {
+        Font {
+            source,
+            cache: GlyphCache::default(),
+        }
+    }
+
+    /// The same outline font at another size, sharing the loaded file but
+    /// not the glyph cache. None for bitmap fonts.
+    pub fn at_size(&self, size: f32) -> Option<Font> {
+        match &self.source {
+            FontSource::Outline { font, .. } => Some(Font::new(FontSource::Outline {
+                font: font.clone(),
+                size,
+            })),
+            FontSource::Bitmap(_) => None,
+        }
+    }
+
+    /// Distance between the baselines of two lines.
+    pub fn line_height(&self) -> f32 {
+        match &self.source {
+            FontSource::Outline { font, size } => font
+                .horizontal_line_metrics(*size)
+                .map_or(*size, |metrics| metrics.new_line_size),
+            FontSource::Bitmap(font) => font.line_height,
+        }
+    }
+
+    /// Distance from the top of a line to its baseline.
+    pub fn ascent(&self) -> f32 {
+        match &self.source {
+            FontSource::Outline { font, size } => font
+                .horizontal_line_metrics(*size)
+                .map_or(*size, |metrics| metrics.ascent),
+            FontSource::Bitmap(font) => font.base,
+        }
+    }
+
+    /// How far the pen moves after drawing a character.
+    pub fn advance(&self, ch: char) -> f32 {
+        match &self.source {
+            FontSource::Outline { font, size } => font.metrics(ch, *size).advance_width,
+            FontSource::Bitmap(font) => font.glyphs.get(&ch).map_or(0.0, |glyph| glyph.advance),
+        }
+    }
+
+    /// Adjustment of the pen between two characters, usually negative to
+    /// tuck pairs like "AV" together.
+    pub fn kerning(&self, left: char, right: char) -> f32 {
+        match &self.source {
+            FontSource::Outline { font, size } => {
+                font.horizontal_kern(left, right, *size).unwrap_or(0.0)
+            }
+            FontSource::Bitmap(font) => font.kerning.get(&(left, right)).copied().unwrap_or(0.0),
+        }
+    }
+
+    /// True if the font has an image or metrics for the character, rather
+    /// than drawing a placeholder or nothing.
+    pub fn has_glyph(&self, ch: char) -> bool {
+        match &self.source {
+            FontSource::Outline { font, .. } => font.lookup_glyph_index(ch) != 0,
+            FontSource::Bitmap(font) => font.glyphs.contains_key(&ch),
+        }
+    }
+
+    /// Drops every cached glyph, e.g. after drawing a lot of one-off text.
+    pub fn clear_cache(&mut self) {
+        self.cache.clear();
+    }
+
+    /// A glyph, rasterized and cached if it is new. Outline glyphs are
+    /// white, to be tinted when drawn. None for glyphs without an image.
+    pub(crate) fn glyph(&mut self, ch: char) -> Option<CachedGlyph> {
+        if let Some(glyph) = self.cache.get(ch) {
+            return glyph;
+        }
+
+        match &self.source {
+            FontSource::Outline { font, size } => {
+                let (metrics, coverage) = font.rasterize(ch, *size);
+                let pixels = coverage
+                    .iter()
+                    .flat_map(|&coverage| [255, 255, 255, coverage])
+                    .collect::<Vec<_>>();
+
+                self.cache.insert(
+                    ch,
+                    &pixels,
+                    [metrics.width as u32, metrics.height as u32],
+                    [
+                        metrics.xmin as f32,
+                        -(metrics.ymin + metrics.height as i32) as f32,
+                    ],
+                )
+            }
+            FontSource::Bitmap(font) => match font.glyphs.get(&ch) {
+                Some(glyph) => {
+                    let pixels = font.glyph_pixels(glyph);
+                    let offset = [glyph.offset[0], glyph.offset[1] - font.base];
+                    self.cache.insert(ch, &pixels, glyph.size, offset)
+                }
+                None => self.cache.insert(ch, &[], [0, 0], [0.0, 0.0]),
+            },
+        }
+    }
+
+    /// Empties the glyph cache if it grew past its page limit. Glyphs looked
+    /// up before are no longer valid afterwards.
+    pub(crate) fn trim_cache(&mut self) {
+        self.cache.trim();
+    }
+
+    /// Uploads glyphs cached since the last upload.
+    pub(crate) fn upload(&mut self, renderer: &Renderer) {
+        self.cache.upload(renderer);
+    }
+
+    pub(crate) fn page_texture(&self, page: usize) -> Option<Arc<Texture>> {
+        self.cache.texture(page)
+    }
+}
+
+#[cfg(test)]
+impl Font {
+    /// Bitmap font read from a BMFont description, with blank 256 by 256
+    /// pages, for tests that need no files.
+    pub(crate) fn from_bmfont_description(description: &str) -> Font {
+        let font = BitmapFont::parse(description.as_bytes(), |_| {
+            Ok((vec![0; 256 * 256 * 4], [256, 256]))
+        });
+        Font::new(FontSource::Bitmap(font.unwrap()))
+    }
+}
+
+#[derive(Debug)]
+pub enum FontError {
+    Io(std::io::Error),
+    Png(png::DecodingError),
+    Invalid(String),
+    Unsupported(String),
+}
+
+impl fmt::Display for FontError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FontError::Io(err) => write!(f, "Failed to read font: {}", err),
+            FontError::Png(err) => write!(f, "Failed to load font page: {}", err),
+            FontError::Invalid(reason) => write!(f, "Invalid font: {}", reason),
+            FontError::Unsupported(feature) => write!(f, "Unsupported font: {}", feature),
+        }
+    }
+}
+
+impl std::error::Error for FontError {}
+
+impl From<std::io::Error> for FontError {
+    fn from(err: std::io::Error) -> FontError {
+        FontError::Io(err)
+    }
+}
+
+impl From<png::DecodingError> for FontError {
+    fn from(err: png::DecodingError) -> FontError {
+        FontError::Png(err)
+    }
+}
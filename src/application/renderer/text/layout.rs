@@ -0,0 +1,388 @@
+use super::font::Font;
+use super::TextSpan;
+
+/// Horizontal alignment of the lines of a text.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Align {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+/// A character placed by layout.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LayoutGlyph {
+    pub ch: char,
+    pub color: [f32; 4],
+    /// Pen position on the baseline, from the top left of the text.
+    pub position: [f32; 2],
+    pub line: usize,
+}
+
+/// Characters of a text placed in lines, whitespace left out.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TextLayout {
+    pub glyphs: Vec<LayoutGlyph>,
+    /// Width of the widest line and height of all lines.
+    pub size: [f32; 2],
+    pub lines: usize,
+}
+
+/// Lays out colored spans. Lines break at newlines and, past `max_width`,
+/// between words; a word wider than a line on its own is broken between
+/// characters. Lines are aligned within `max_width`, or within the widest
+/// line without one.
+pub fn layout(
+    spans: &[TextSpan],
+    font: &Font,
+    max_width: Option<f32>,
+    align: Align,
+    line_spacing: f32,
+) -> TextLayout {
+    let chars = spans
+        .iter()
+        .flat_map(|span| span.text.chars().map(move |ch| (ch, span.color)))
+        .filter(|(ch, _)| *ch != '\r')
+        .flat_map(|(ch, color)| match ch {
+            '\t' => vec![(' ', color); 4],
+            _ => vec![(ch, color)],
+        })
+        .collect::<Vec<_>>();
+
+    let lines = chars
+        .split(|(ch, _)| *ch == '\n')
+        .flat_map(|paragraph| wrap(paragraph, font, max_width))
+        .collect::<Vec<_>>();
+
+    // pen positions, and the width of each line without trailing whitespace
+    let placed = lines
+        .iter()
+        .map(|line| {
+            let mut pen = 0.0;
+            let mut width = 0.0;
+            let mut previous = None;
+
+            let positions = line
+                .iter()
+                .map(|&(ch, _)| {
+                    if let Some(previous) = previous {
+                        pen += font.kerning(previous, ch);
+                    }
+                    previous = Some(ch);
+
+                    let position = pen;
+                    pen += font.advance(ch);
+                    if !ch.is_whitespace() {
+                        width = pen;
+                    }
+                    position
+                })
+                .collect::<Vec<_>>();
+
+            (positions, width)
+        })
+        .collect::<Vec<_>>();
+
+    let widest = placed.iter().map(|(_, width)| *width).fold(0.0, f32::max);
+    let box_width = max_width.unwrap_or(widest);
+    let line_height = font.line_height() * line_spacing;
+
+    let mut glyphs = Vec::new();
+    for (index, (line, (positions, width))) in lines.iter().zip(&placed).enumerate() {
+        let x = match align {
+            Align::Left => 0.0,
+            Align::Center => (box_width - width) / 2.0,
+            Align::Right => box_width - width,
+        };
+        let baseline = font.ascent() + index as f32 * line_height;
+
+        for (&(ch, color), position) in line.iter().zip(positions) {
+            if !ch.is_whitespace() {
+                glyphs.push(LayoutGlyph {
+                    ch,
+                    color,
+                    position: [x + position, baseline],
+                    line: index,
+                });
+            }
+        }
+    }
+
+    TextLayout {
+        glyphs,
+        size: [widest, lines.len() as f32 * line_height],
+        lines: lines.len(),
+    }
+}
+
+/// Splits a paragraph into lines no wider than `max_width`, greedily by
+/// words. Whitespace after a word stays on its line.
+fn wrap(
+    paragraph: &[(char, [f32; 4])],
+    font: &Font,
+    max_width: Option<f32>,
+) -> Vec<Vec<(char, [f32; 4])>> {
+    let max_width = match max_width {
+        Some(max_width) => max_width,
+        None => return vec![paragraph.to_vec()],
+    };
+
+    let mut lines = Vec::new();
+    let mut line = Vec::new();
+    let mut width = 0.0;
+
+    for word in words(paragraph) {
+        let content = word
+            .iter()
+            .rposition(|(ch, _)| !ch.is_whitespace())
+            .map_or(0, |last| last + 1);
+        let content_width = measure(&word[..content], font);
+
+        if !line.is_empty() && width + content_width > max_width {
+            lines.push(std::mem::take(&mut line));
+            width = 0.0;
+        }
+
+        if line.is_empty() && content_width > max_width {
+            for &(ch, color) in word {
+                let advance = font.advance(ch);
+                if !line.is_empty() && !ch.is_whitespace() && width + advance > max_width {
+                    lines.push(std::mem::take(&mut line));
+                    width = 0.0;
+                }
+                line.push((ch, color));
+                width += advance;
+            }
+            continue;
+        }
+
+        line.extend_from_slice(word);
+        width += measure(word, font);
+    }
+
+    lines.push(line);
+    lines
+}
+
+/// Runs of non-whitespace characters, each with the whitespace after it.
+/// Leading whitespace forms a word of its own.
+fn words(paragraph: &[(char, [f32; 4])]) -> impl Iterator<Item = &[(char, [f32; 4])]> {
+    let mut rest = paragraph;
+
+    std::iter::from_fn(move || {
+        if rest.is_empty() {
+            return None;
+        }
+
+        let content = rest
+            .iter()
+            .position(|(ch, _)| ch.is_whitespace())
+            .unwrap_or(rest.len());
+        let end = rest[content..]
+            .iter()
+            .position(|(ch, _)| !ch.is_whitespace())
+            .map_or(rest.len(), |space| content + space);
+
+        let (word, remaining) = rest.split_at(end);
+        rest = remaining;
+        Some(word)
+    })
+}
+
+/// Width of characters set next to each other, with kerning.
+fn measure(chars: &[(char, [f32; 4])], font: &Font) -> f32 {
+    let advances = chars.iter().map(|&(ch, _)| font.advance(ch)).sum::<f32>();
+    let kerning = chars
+        .windows(2)
+        .map(|pair| font.kerning(pair[0].0, pair[1].0))
+        .sum::<f32>();
+
+    advances + kerning
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WHITE: [f32; 4] = [1.0; 4];
+    const RED: [f32; 4] = [1.0, 0.0, 0.0, 1.0];
+
+    /// Letters 4 by 6 pixels large advancing 5, spaces advancing 3, lines 10
+    /// pixels apart with the baseline 8 below their top.
+    fn font() -> Font {
+        let mut description = String::from(
+            "info face=\"Test\" size=8\n\
+             common lineHeight=10 base=8 scaleW=256 scaleH=256 pages=1 packed=0\n\
+             page id=0 file=\"test.png\"\n",
+        );
+        for ch in 'a'..='z' {
+            description += &format!(
+                "char id={} x=0 y=0 width=4 height=6 xoffset=0 yoffset=2 xadvance=5 page=0\n",
+                ch as u32
+            );
+        }
+        description +=
+            "char id=32 x=0 y=0 width=0 height=0 xoffset=0 yoffset=0 xadvance=3 page=0\n";
+        description += "kerning first=121 second=111 amount=-1\n";
+
+        Font::from_bmfont_description(&description)
+    }
+
+    fn spans(text: &str) -> Vec<TextSpan> {
+        vec![TextSpan {
+            text: text.to_string(),
+            color: WHITE,
+        }]
+    }
+
+    fn chars(text: &str) -> Vec<(char, [f32; 4])> {
+        text.chars().map(|ch| (ch, WHITE)).collect()
+    }
+
+    fn text(chars: &[(char, [f32; 4])]) -> String {
+        chars.iter().map(|(ch, _)| ch).collect()
+    }
+
+    /// Lines of a layout as their characters and the x of each line's first
+    /// glyph.
+    fn lines(layout: &TextLayout) -> Vec<(String, f32)> {
+        (0..layout.lines)
+            .map(|line| {
+                let glyphs = layout.glyphs.iter().filter(|glyph| glyph.line == line);
+                let start = glyphs.clone().next().map_or(0.0, |glyph| glyph.position[0]);
+                (glyphs.map(|glyph| glyph.ch).collect(), start)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn words_keep_their_trailing_whitespace() {
+        let paragraph = chars("  ab  cd e");
+        let split = words(&paragraph).map(text).collect::<Vec<_>>();
+
+        assert_eq!(split, ["  ", "ab  ", "cd ", "e"]);
+        assert_eq!(words(&[]).count(), 0);
+    }
+
+    #[test]
+    fn wrap_breaks_between_words_past_max_width() {
+        let font = font();
+        let paragraph = chars("abc def ghi");
+
+        // "abc def" is 33 pixels wide, the space after it left out
+        let wrapped = wrap(&paragraph, &font, Some(33.0));
+        assert_eq!(
+            wrapped.iter().map(|line| text(line)).collect::<Vec<_>>(),
+            ["abc def ", "ghi"]
+        );
+
+        let wrapped = wrap(&paragraph, &font, Some(32.0));
+        assert_eq!(
+            wrapped.iter().map(|line| text(line)).collect::<Vec<_>>(),
+            ["abc ", "def ", "ghi"]
+        );
+
+        assert_eq!(wrap(&paragraph, &font, None).len(), 1);
+    }
+
+    #[test]
+    fn wrap_breaks_a_word_wider_than_a_line() {
+        let font = font();
+        let wrapped = wrap(&chars("ab cdefghijk l"), &font, Some(22.0));
+
+        assert_eq!(
+            wrapped.iter().map(|line| text(line)).collect::<Vec<_>>(),
+            ["ab ", "cdef", "ghij", "k l"]
+        );
+    }
+
+    #[test]
+    fn layout_places_glyphs_on_baselines() {
+        let layout = layout(&spans("abc def ghi"), &font(), Some(35.0), Align::Left, 1.5);
+
+        assert_eq!(layout.lines, 2);
+        assert_eq!(
+            lines(&layout),
+            [("abcdef".to_string(), 0.0), ("ghi".to_string(), 0.0)]
+        );
+        assert_eq!(layout.size, [33.0, 30.0]);
+
+        let position = |ch| {
+            layout
+                .glyphs
+                .iter()
+                .find(|glyph| glyph.ch == ch)
+                .unwrap()
+                .position
+        };
+        assert_eq!(position('b'), [5.0, 8.0]);
+        assert_eq!(position('d'), [18.0, 8.0]);
+        assert_eq!(position('h'), [5.0, 23.0]);
+    }
+
+    #[test]
+    fn layout_applies_kerning() {
+        let layout = layout(&spans("yo"), &font(), None, Align::Left, 1.0);
+
+        assert_eq!(layout.glyphs[1].position, [4.0, 8.0]);
+        assert_eq!(layout.size[0], 9.0);
+    }
+
+    #[test]
+    fn alignment_ignores_trailing_whitespace() {
+        let font = font();
+        let starts = |align, max_width| {
+            let layout = layout(&spans("ab   \nabcd"), &font, max_width, align, 1.0);
+            lines(&layout)
+                .into_iter()
+                .map(|(_, start)| start)
+                .collect::<Vec<_>>()
+        };
+
+        // lines are 10 and 20 pixels wide
+        assert_eq!(starts(Align::Left, Some(40.0)), [0.0, 0.0]);
+        assert_eq!(starts(Align::Center, Some(40.0)), [15.0, 10.0]);
+        assert_eq!(starts(Align::Right, Some(40.0)), [30.0, 20.0]);
+
+        // without a max width, lines align within the widest
+        assert_eq!(starts(Align::Center, None), [5.0, 0.0]);
+        assert_eq!(starts(Align::Right, None), [10.0, 0.0]);
+    }
+
+    #[test]
+    fn tabs_expand_and_carriage_returns_are_dropped() {
+        let layout = layout(&spans("a\tb\r\nc"), &font(), None, Align::Left, 1.0);
+
+        assert_eq!(
+            lines(&layout),
+            [("ab".to_string(), 0.0), ("c".to_string(), 0.0)]
+        );
+        // a tab is four spaces
+        assert_eq!(layout.glyphs[1].position, [17.0, 8.0]);
+        assert_eq!(layout.size, [22.0, 20.0]);
+    }
+
+    #[test]
+    fn spans_keep_their_colors_across_lines() {
+        let spans = [
+            TextSpan {
+                text: "ab\nc".to_string(),
+                color: WHITE,
+            },
+            TextSpan {
+                text: "d".to_string(),
+                color: RED,
+            },
+        ];
+        let layout = layout(&spans, &font(), None, Align::Left, 1.0);
+
+        let colors = layout
+            .glyphs
+            .iter()
+            .map(|glyph| glyph.color)
+            .collect::<Vec<_>>();
+        assert_eq!(colors, [WHITE, WHITE, WHITE, RED]);
+        assert_eq!(layout.glyphs[3].position, [5.0, 18.0]);
+    }
+}
@@ -0,0 +1,251 @@
+mod bmfont;
+mod cache;
+pub mod font;
+pub mod layout;
+pub use font::{Font, FontError};
+pub use layout::{Align, LayoutGlyph, TextLayout};
+
+use nalgebra_glm::TMat4;
+
+use std::sync::Arc;
+
+use super::frame::Frame;
+use super::renderables::vertices::Vertex2D;
+use super::renderables::Renderable;
+use super::texture::Texture;
+use super::Renderer;
+
+/// A run of text in one color.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextSpan {
+    pub text: String,
+    pub color: [f32; 4],
+}
+
+/// A string drawn with a `Font`, made of colored spans.
+/// * Positions are in the font's pixels from the top left of the first line,
+///   y growing down; `matrix` places and scales the text.
+/// * Lines break at newlines and, with a `max_width`, between words.
+/// * Drawn as a translucent renderable, lit like the rest of the scene, or
+///   as an overlay placed in pixels that ignores camera and lighting, e.g.
+///   for a HUD.
+#[derive(Clone, Debug)]
+pub struct Text {
+    pub spans: Vec<TextSpan>,
+    pub max_width: Option<f32>,
+    pub align: Align,
+    /// Multiplies the font's line height.
+    pub line_spacing: f32,
+    pub matrix: TMat4<f32>,
+    pub layer: i32,
+}
+
+impl Default for Text {
+    fn default() -> Text {
+        Text {
+            spans: Vec::new(),
+            max_width: None,
+            align: Align::Left,
+            line_spacing: 1.0,
+            matrix: nalgebra_glm::identity(),
+            layer: 0,
+        }
+    }
+}
+
+impl Text {
+    /// White text.
+    pub fn new(text: &str) -> Text {
+        Text::default().with_span(text, [1.0; 4])
+    }
+
+    pub fn with_span(mut self, text: &str, color: [f32; 4]) -> Text {
+        self.push_span(text, color);
+        self
+    }
+
+    /// Colors every span.
+    pub fn with_color(mut self, color: [f32; 4]) -> Text {
+        for span in &mut self.spans {
+            span.color = color;
+        }
+        self
+    }
+
+    pub fn with_max_width(mut self, max_width: f32) -> Text {
+        self.max_width = Some(max_width);
+        self
+    }
+
+    pub fn with_align(mut self, align: Align) -> Text {
+        self.align = align;
+        self
+    }
+
+    pub fn with_line_spacing(mut self, line_spacing: f32) -> Text {
+        self.line_spacing = line_spacing;
+        self
+    }
+
+    pub fn with_matrix(mut self, matrix: TMat4<f32>) -> Text {
+        self.matrix = matrix;
+        self
+    }
+
+    pub fn with_layer(mut self, layer: i32) -> Text {
+        self.layer = layer;
+        self
+    }
+
+    pub fn push_span(&mut self, text: &str, color: [f32; 4]) {
+        self.spans.push(TextSpan {
+            text: text.to_string(),
+            color,
+        });
+    }
+
+    /// Replaces the text with one span in the color of the first, e.g. to
+    /// update a score.
+    pub fn set_text(&mut self, text: &str) {
+        let color = self.spans.first().map_or([1.0; 4], |span| span.color);
+        self.spans = vec![TextSpan {
+            text: text.to_string(),
+            color,
+        }];
+    }
+
+    /// The whole text, without colors.
+    pub fn text(&self) -> String {
+        self.spans.iter().map(|span| span.text.as_str()).collect()
+    }
+
+    /// Places the characters without drawing them.
+    pub fn layout(&self, font: &Font) -> TextLayout {
+        layout::layout(
+            &self.spans,
+            font,
+            self.max_width,
+            self.align,
+            self.line_spacing,
+        )
+    }
+
+    /// Size of the laid out text, before its matrix.
+    pub fn size(&self, font: &Font) -> [f32; 2] {
+        self.layout(font).size
+    }
+
+    /// Glyph quads of the text, one renderable per glyph page and color.
+    /// Glyphs not drawn before are rasterized and uploaded first.
+    pub fn glyph_runs(&self, renderer: &Renderer, font: &mut Font) -> Vec<GlyphRun> {
+        let layout = self.layout(font);
+        font.trim_cache();
+
+        // runs in the order their page and color first appear
+        let mut runs: Vec<(usize, [f32; 4], Vec<Vertex2D>)> = Vec::new();
+        for glyph in &layout.glyphs {
+            let cached = match font.glyph(glyph.ch) {
+                Some(cached) => cached,
+                None => continue,
+            };
+
+            // whole pixels keep unscaled text sharp
+            let left = (glyph.position[0] + cached.offset[0]).round();
+            let top = (glyph.position[1] + cached.offset[1]).round();
+            let [right, bottom] = [left + cached.size[0], top + cached.size[1]];
+            let [[u0, v0], [u1, v1]] = cached.uv;
+
+            let vertex = |x: f32, y: f32, u: f32, v: f32| Vertex2D {
+                position: [x, y, 0.0],
+                uv: [u, v],
+            };
+
+            let run = match runs
+                .iter()
+                .position(|(page, color, _)| *page == cached.page && *color == glyph.color)
+            {
+                Some(run) => run,
+                None => {
+                    runs.push((cached.page, glyph.color, Vec::new()));
+                    runs.len() - 1
+                }
+            };
+            runs[run].2.extend([
+                vertex(left, top, u0, v0),
+                vertex(left, bottom, u0, v1),
+                vertex(right, bottom, u1, v1),
+                vertex(left, top, u0, v0),
+                vertex(right, bottom, u1, v1),
+                vertex(right, top, u1, v0),
+            ]);
+        }
+
+        font.upload(renderer);
+
+        runs.into_iter()
+            .filter_map(|(page, color, vertices)| {
+                Some(GlyphRun {
+                    vertices,
+                    texture: font.page_texture(page)?,
+                    color,
+                    matrix: self.matrix,
+                    layer: self.layer,
+                })
+            })
+            .collect()
+    }
+
+    /// Queues the text in the scene, through the camera and tinted by the
+    /// frame's lights.
+    pub fn draw(&self, frame: &mut Frame, font: &mut Font) {
+        for run in self.glyph_runs(frame.renderer(), font) {
+            frame.draw(&run);
+        }
+    }
+
+    /// Queues the text over the view, unlit and ignoring the camera. The
+    /// matrix places it in pixels of the view, from its top left corner.
+    pub fn draw_overlay(&self, frame: &mut Frame, font: &mut Font) {
+        let pixel_matrix = frame.pixel_matrix();
+        for run in self.glyph_runs(frame.renderer(), font) {
+            frame.draw_overlay_with_matrix(&run, pixel_matrix * run.matrix);
+        }
+    }
+}
+
+/// Glyph quads of a text that share a glyph page and a color.
+pub struct GlyphRun {
+    pub vertices: Vec<Vertex2D>,
+    pub texture: Arc<Texture>,
+    /// Tints the glyphs, which are cached white.
+    pub color: [f32; 4],
+    pub matrix: TMat4<f32>,
+    pub layer: i32,
+}
+
+impl Renderable for GlyphRun {
+    fn vertices(&self) -> Vec<Vertex2D> {
+        self.vertices.clone()
+    }
+
+    fn matrix(&self) -> TMat4<f32> {
+        self.matrix
+    }
+
+    fn layer(&self) -> i32 {
+        self.layer
+    }
+
+    fn texture(&self) -> Arc<Texture> {
+        self.texture.clone()
+    }
+
+    fn tint(&self) -> [f32; 4] {
+        self.color
+    }
+
+    /// Glyph edges are antialiased, so runs are always blended.
+    fn translucent(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,70 @@
+use vulkano::image::view::ImageViewAbstract;
+
+use std::fs::File;
+use std::sync::Arc;
+
+/// An image on the GPU that textured renderables sample from.
+pub struct Texture {
+    pub(crate) view: Arc<dyn ImageViewAbstract>,
+    dimensions: [u32; 2],
+    translucent: bool,
+}
+
+impl Texture {
+    pub(crate) fn new(
+        view: Arc<dyn ImageViewAbstract>,
+        dimensions: [u32; 2],
+        translucent: bool,
+    ) -> Texture {
+        Texture {
+            view,
+            dimensions,
+            translucent,
+        }
+    }
+
+    pub fn dimensions(&self) -> [u32; 2] {
+        self.dimensions
+    }
+
+    /// True if any pixel is partially transparent. Fully transparent pixels
+    /// are cut out instead, so they don't make a texture translucent.
+    pub fn translucent(&self) -> bool {
+        self.translucent
+    }
+}
+
+/// Returns true if any pixel of the RGBA8 data is partially transparent.
+pub fn has_partial_alpha(pixels: &[u8]) -> bool {
+    pixels
+        .chunks_exact(4)
+        .any(|pixel| pixel[3] != 0 && pixel[3] != 255)
+}
+
+/// Decodes a PNG file into RGBA8 pixels and its dimensions.
+pub fn load_png(path: &str) -> Result<(Vec<u8>, [u32; 2]), png::DecodingError> {
+    let mut decoder = png::Decoder::new(File::open(path)?);
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+
+    let mut reader = decoder.read_info()?;
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buffer)?;
+    buffer.truncate(info.buffer_size());
+
+    let pixels = match info.color_type {
+        png::ColorType::Rgba => buffer,
+        png::ColorType::Rgb => buffer
+            .chunks_exact(3)
+            .flat_map(|p| [p[0], p[1], p[2], 255])
+            .collect(),
+        png::ColorType::GrayscaleAlpha => buffer
+            .chunks_exact(2)
+            .flat_map(|p| [p[0], p[0], p[0], p[1]])
+            .collect(),
+        png::ColorType::Grayscale => buffer.iter().flat_map(|&p| [p, p, p, 255]).collect(),
+        // normalize_to_color8 expands palettes
+        png::ColorType::Indexed => unreachable!(),
+    };
+
+    Ok((pixels, [info.width, info.height]))
+}
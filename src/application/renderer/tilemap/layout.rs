@@ -0,0 +1,362 @@
+/// How the cells of a tilemap are arranged.
+/// * Positions are in map space, before the map's matrix: the map's
+///   bounding box starts at the origin and y grows downwards, as in Tiled.
+/// * Cells are `tile_size` large boxes, which diamonds and hexagons are
+///   inscribed in.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum TileLayout {
+    /// Rows and columns of rectangular cells.
+    #[default]
+    Orthogonal,
+    /// Diamond cells: x runs down to the right and y down to the left, with
+    /// cell `[0, 0]` at the top.
+    Isometric,
+    /// Diamond cells in a rectangular map, every other row (or column)
+    /// shifted by half a cell to fill the gaps.
+    Staggered {
+        axis: StaggerAxis,
+        index: StaggerIndex,
+    },
+    /// Hexagonal cells, every other row (or column) shifted by half a cell.
+    /// `side_length` is the length of the sides parallel to the stagger
+    /// axis, e.g. `width / √3` for regular pointy hexagons `2 * width / √3`
+    /// tall.
+    Hexagonal {
+        axis: StaggerAxis,
+        index: StaggerIndex,
+        side_length: f32,
+    },
+}
+
+/// Whether rows or columns of a staggered or hexagonal map are shifted. Y
+/// shifts rows, giving hexagons a pointy top; X shifts columns, giving them
+/// a flat top.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum StaggerAxis {
+    X,
+    #[default]
+    Y,
+}
+
+/// Whether the odd or the even rows (or columns) are shifted.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum StaggerIndex {
+    #[default]
+    Odd,
+    Even,
+}
+
+impl TileLayout {
+    /// Hexagons with a corner at the top, in rows with the odd ones shifted
+    /// right. `side_length` is the height of their vertical sides.
+    pub fn pointy_hex(side_length: f32) -> TileLayout {
+        TileLayout::Hexagonal {
+            axis: StaggerAxis::Y,
+            index: StaggerIndex::Odd,
+            side_length,
+        }
+    }
+
+    /// Hexagons with a flat top, in columns with the odd ones shifted down.
+    /// `side_length` is the width of their horizontal sides.
+    pub fn flat_hex(side_length: f32) -> TileLayout {
+        TileLayout::Hexagonal {
+            axis: StaggerAxis::X,
+            index: StaggerIndex::Odd,
+            side_length,
+        }
+    }
+
+    /// Bounding box of a cell as its minimum and maximum corners, for a map
+    /// of `map_size` cells each `tile_size` large. Cells outside the map are
+    /// extrapolated.
+    pub fn cell_bounds(
+        &self,
+        cell: [i32; 2],
+        map_size: [u32; 2],
+        tile_size: [f32; 2],
+    ) -> [[f32; 2]; 2] {
+        let [width, height] = tile_size;
+        let [x, y] = [cell[0] as f32, cell[1] as f32];
+
+        let min = match self.stagger(tile_size) {
+            Some(stagger) => match stagger.axis {
+                StaggerAxis::X => [
+                    x * stagger.column_width,
+                    y * height + stagger.shift(cell[0]) * stagger.row_height,
+                ],
+                StaggerAxis::Y => [
+                    x * width + stagger.shift(cell[1]) * stagger.column_width,
+                    y * stagger.row_height,
+                ],
+            },
+            None => match self {
+                TileLayout::Isometric => [
+                    (x - y + map_size[1] as f32 - 1.0) * width / 2.0,
+                    (x + y) * height / 2.0,
+                ],
+                _ => [x * width, y * height],
+            },
+        };
+
+        [min, [min[0] + width, min[1] + height]]
+    }
+
+    /// Center of a cell.
+    pub fn cell_center(&self, cell: [i32; 2], map_size: [u32; 2], tile_size: [f32; 2]) -> [f32; 2] {
+        let [min, max] = self.cell_bounds(cell, map_size, tile_size);
+        [(min[0] + max[0]) / 2.0, (min[1] + max[1]) / 2.0]
+    }
+
+    /// Cell containing a point. The cell may be outside the map.
+    pub fn point_to_cell(
+        &self,
+        point: [f32; 2],
+        map_size: [u32; 2],
+        tile_size: [f32; 2],
+    ) -> [i32; 2] {
+        let [width, height] = tile_size;
+
+        match self {
+            TileLayout::Orthogonal => [
+                (point[0] / width).floor() as i32,
+                (point[1] / height).floor() as i32,
+            ],
+            TileLayout::Isometric => {
+                // relative to the top corner of cell [0, 0]
+                let x = point[0] - map_size[1] as f32 * width / 2.0;
+                let y = point[1];
+
+                [
+                    (y / height + x / width).floor() as i32,
+                    (y / height - x / width).floor() as i32,
+                ]
+            }
+            TileLayout::Staggered { .. } | TileLayout::Hexagonal { .. } => {
+                let stagger = self.stagger(tile_size).unwrap();
+                let estimate = match stagger.axis {
+                    StaggerAxis::X => [point[0] / stagger.column_width, point[1] / height],
+                    StaggerAxis::Y => [point[0] / width, point[1] / stagger.row_height],
+                }
+                .map(|estimate| estimate.floor() as i32);
+
+                // cells overlap their neighbours' bounding boxes, so take the
+                // nearest of the cells around the estimate
+                let distance = |cell: [i32; 2]| {
+                    let center = self.cell_center(cell, map_size, tile_size);
+                    let [dx, dy] = [point[0] - center[0], point[1] - center[1]];
+
+                    match self {
+                        // inside a diamond within a sum of half its diagonals
+                        TileLayout::Staggered { .. } => {
+                            dx.abs() / (width / 2.0) + dy.abs() / (height / 2.0)
+                        }
+                        _ => dx * dx + dy * dy,
+                    }
+                };
+
+                (-2..=1)
+                    .flat_map(|dy| (-2..=1).map(move |dx| [dx, dy]))
+                    .map(|[dx, dy]| [estimate[0] + dx, estimate[1] + dy])
+                    .min_by(|a, b| distance(*a).total_cmp(&distance(*b)))
+                    .unwrap()
+            }
+        }
+    }
+
+    /// Where an image of `size` is drawn for a cell with the given bounds:
+    /// resting on the bottom of the cell, at its left, or centered in
+    /// isometric maps, as Tiled draws tiles larger than the grid.
+    pub fn image_bounds(&self, cell_bounds: [[f32; 2]; 2], size: [f32; 2]) -> [[f32; 2]; 2] {
+        let [min, max] = cell_bounds;
+
+        let left = match self {
+            TileLayout::Isometric => (min[0] + max[0] - size[0]) / 2.0,
+            _ => min[0],
+        };
+
+        [[left, max[1] - size[1]], [left + size[0], max[1]]]
+    }
+
+    /// Drawing order of a cell: cells in front of others, lower on the
+    /// screen, have a greater depth. Cells of the same depth do not overlap.
+    pub fn depth(&self, cell: [i32; 2]) -> i32 {
+        let [x, y] = cell;
+
+        match self {
+            TileLayout::Orthogonal => y,
+            TileLayout::Isometric => x + y,
+            TileLayout::Staggered { axis, index } | TileLayout::Hexagonal { axis, index, .. } => {
+                match axis {
+                    // shifted columns sit half a row lower
+                    StaggerAxis::X => 2 * y + is_shifted(*index, x) as i32,
+                    StaggerAxis::Y => y,
+                }
+            }
+        }
+    }
+
+    fn stagger(&self, tile_size: [f32; 2]) -> Option<Stagger> {
+        let (axis, index, side_length) = match *self {
+            TileLayout::Staggered { axis, index } => (axis, index, 0.0),
+            TileLayout::Hexagonal {
+                axis,
+                index,
+                side_length,
+            } => (axis, index, side_length),
+            _ => return None,
+        };
+
+        let [width, height] = tile_size;
+        let (column_width, row_height) = match axis {
+            StaggerAxis::X => ((width + side_length) / 2.0, height / 2.0),
+            StaggerAxis::Y => (width / 2.0, (height + side_length) / 2.0),
+        };
+
+        Some(Stagger {
+            axis,
+            index,
+            column_width,
+            row_height,
+        })
+    }
+}
+
+/// Spacing of a staggered or hexagonal grid. Columns of cells along the x
+/// axis are `column_width` apart and shifted by half a cell height, rows along
+/// the y axis `row_height` apart and shifted by half a cell width.
+struct Stagger {
+    axis: StaggerAxis,
+    index: StaggerIndex,
+    column_width: f32,
+    row_height: f32,
+}
+
+impl Stagger {
+    /// 1 for a shifted row or column, else 0.
+    fn shift(&self, row: i32) -> f32 {
+        is_shifted(self.index, row) as i32 as f32
+    }
+}
+
+fn is_shifted(index: StaggerIndex, row: i32) -> bool {
+    match index {
+        StaggerIndex::Odd => row.rem_euclid(2) == 1,
+        StaggerIndex::Even => row.rem_euclid(2) == 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAP_SIZE: [u32; 2] = [7, 6];
+
+    /// Every layout with a tile size that suits it.
+    fn layouts() -> Vec<(TileLayout, [f32; 2])> {
+        let mut layouts = vec![
+            (TileLayout::Orthogonal, [16.0, 12.0]),
+            (TileLayout::Isometric, [32.0, 16.0]),
+            (TileLayout::pointy_hex(16.0), [28.0, 32.0]),
+            (TileLayout::flat_hex(16.0), [32.0, 28.0]),
+        ];
+        for axis in [StaggerAxis::X, StaggerAxis::Y] {
+            for index in [StaggerIndex::Odd, StaggerIndex::Even] {
+                layouts.push((TileLayout::Staggered { axis, index }, [32.0, 16.0]));
+                layouts.push((
+                    TileLayout::Hexagonal {
+                        axis,
+                        index,
+                        side_length: 14.0,
+                    },
+                    [30.0, 30.0],
+                ));
+            }
+        }
+        layouts
+    }
+
+    /// Cells of the map and a border around it.
+    fn cells() -> impl Iterator<Item = [i32; 2]> {
+        (-2..MAP_SIZE[1] as i32 + 2).flat_map(|y| (-2..MAP_SIZE[0] as i32 + 2).map(move |x| [x, y]))
+    }
+
+    #[test]
+    fn cell_centers_map_back_to_their_cells() {
+        for (layout, tile_size) in layouts() {
+            for cell in cells() {
+                let center = layout.cell_center(cell, MAP_SIZE, tile_size);
+                assert_eq!(
+                    layout.point_to_cell(center, MAP_SIZE, tile_size),
+                    cell,
+                    "{:?} at {:?}",
+                    layout,
+                    center
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn points_near_a_center_stay_in_its_cell() {
+        for (layout, tile_size) in layouts() {
+            let [dx, dy] = [tile_size[0] / 8.0, tile_size[1] / 8.0];
+
+            for cell in cells() {
+                let [x, y] = layout.cell_center(cell, MAP_SIZE, tile_size);
+                for point in [
+                    [x - dx, y - dy],
+                    [x + dx, y - dy],
+                    [x + dx, y + dy],
+                    [x - dx, y + dy],
+                ] {
+                    assert_eq!(
+                        layout.point_to_cell(point, MAP_SIZE, tile_size),
+                        cell,
+                        "{:?} at {:?}",
+                        layout,
+                        point
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn depth_increases_down_the_screen() {
+        for (layout, tile_size) in layouts() {
+            for a in cells() {
+                for b in cells() {
+                    let a_y = layout.cell_center(a, MAP_SIZE, tile_size)[1];
+                    let b_y = layout.cell_center(b, MAP_SIZE, tile_size)[1];
+
+                    if a_y < b_y {
+                        assert!(
+                            layout.depth(a) < layout.depth(b),
+                            "{:?}: {:?} is above {:?}",
+                            layout,
+                            a,
+                            b
+                        );
+                    } else if a_y == b_y {
+                        assert_eq!(layout.depth(a), layout.depth(b), "{:?}", layout);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn images_rest_on_the_bottom_of_their_cell() {
+        let bounds = [[0.0, 0.0], [32.0, 16.0]];
+
+        assert_eq!(
+            TileLayout::Orthogonal.image_bounds(bounds, [40.0, 24.0]),
+            [[0.0, -8.0], [40.0, 16.0]]
+        );
+        assert_eq!(
+            TileLayout::Isometric.image_bounds(bounds, [40.0, 24.0]),
+            [[-4.0, -8.0], [36.0, 16.0]]
+        );
+    }
+}
@@ -0,0 +1,692 @@
+pub mod layout;
+pub use layout::{StaggerAxis, StaggerIndex, TileLayout};
+
+use vulkano::buffer::CpuAccessibleBuffer;
+
+use nalgebra_glm::{self as glm, TMat4};
+
+use std::sync::Arc;
+
+use super::atlas::TextureAtlas;
+use super::camera::Camera2D;
+use super::renderables::vertices::Vertex2D;
+use super::renderables::MAX_LAYER;
+use super::Frame;
+
+/// Tiles per side of a chunk, unless set with `Tilemap::with_chunk_size`.
+pub const DEFAULT_CHUNK_SIZE: u32 = 32;
+
+/// A tile of a tileset placed in a map cell, optionally mirrored.
+/// * Flips are applied like Tiled's: diagonally first (swapping x and y),
+///   then horizontally, then vertically.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Tile {
+    /// Index of the tile in its tileset.
+    pub index: u32,
+    pub flip_x: bool,
+    pub flip_y: bool,
+    pub flip_diagonal: bool,
+}
+
+impl Tile {
+    pub fn new(index: u32) -> Tile {
+        Tile {
+            index,
+            ..Default::default()
+        }
+    }
+}
+
+/// Tile images, as regions of an atlas.
+/// * Tiles fill their cell, unless the tileset has a grid size. Then tiles
+///   of another size are drawn at their own size relative to the grid,
+///   resting on the bottom of their cell.
+pub struct Tileset {
+    atlas: Arc<TextureAtlas>,
+    tiles: Vec<usize>,
+    grid: Option<[u32; 2]>,
+}
+
+impl Tileset {
+    /// Tileset whose tile `i` is the atlas region with index `tiles[i]`.
+    pub fn new(atlas: Arc<TextureAtlas>, tiles: Vec<usize>) -> Tileset {
+        Tileset {
+            atlas,
+            tiles,
+            grid: None,
+        }
+    }
+
+    /// Tileset with one tile per atlas region, in the atlas' order, e.g. over
+    /// an atlas made with `TextureAtlas::from_grid`.
+    pub fn from_atlas(atlas: Arc<TextureAtlas>) -> Tileset {
+        let tiles = (0..atlas.len()).collect();
+        Tileset::new(atlas, tiles)
+    }
+
+    /// Sets the size in pixels of a map cell, for tiles that are larger or
+    /// smaller than the cells.
+    pub fn with_grid_size(mut self, grid: [u32; 2]) -> Tileset {
+        self.grid = Some(grid);
+        self
+    }
+
+    pub fn atlas(&self) -> &Arc<TextureAtlas> {
+        &self.atlas
+    }
+
+    pub fn len(&self) -> usize {
+        self.tiles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tiles.is_empty()
+    }
+
+    /// Size of a tile's image in world units, for cells of `tile_size`.
+    fn image_size(&self, region: usize, tile_size: [f32; 2]) -> [f32; 2] {
+        match (self.grid, self.atlas.region_at(region)) {
+            (Some(grid), Some(region)) => [
+                region.size[0] as f32 * tile_size[0] / grid[0] as f32,
+                region.size[1] as f32 * tile_size[1] / grid[1] as f32,
+            ],
+            _ => tile_size,
+        }
+    }
+
+    /// Largest tile image in world units, for cells of `tile_size`.
+    fn max_image_size(&self, tile_size: [f32; 2]) -> [f32; 2] {
+        self.tiles
+            .iter()
+            .map(|&region| self.image_size(region, tile_size))
+            .fold(tile_size, |[w, h], [x, y]| [w.max(x), h.max(y)])
+    }
+
+    /// Atlas page and texture coordinates of the top left, top right, bottom
+    /// right and bottom left corners of a placed tile.
+    fn corners(&self, tile: Tile) -> Option<(usize, [[f32; 2]; 4])> {
+        let region = self.atlas.region_at(self.region(tile)?)?;
+        let [[u0, v0], [u1, v1]] = region.uv;
+        let uvs = [[u0, v0], [u1, v0], [u1, v1], [u0, v1]];
+
+        // corners are numbered clockwise from the top left, and each flip
+        // swaps pairs of them
+        let corners = [0, 1, 2, 3].map(|mut corner: usize| {
+            if tile.flip_y {
+                corner = 3 - corner;
+            }
+            if tile.flip_x {
+                corner ^= 1;
+            }
+            if tile.flip_diagonal && corner % 2 == 1 {
+                corner = 4 - corner;
+            }
+            uvs[corner]
+        });
+
+        Some((region.page, corners))
+    }
+
+    fn region(&self, tile: Tile) -> Option<usize> {
+        self.tiles.get(tile.index as usize).copied()
+    }
+}
+
+/// Vertex buffers of a square of tiles of one layer, in drawing order.
+struct Chunk {
+    dirty: bool,
+    buffers: Vec<ChunkBuffer>,
+}
+
+/// Tiles of a chunk from one atlas page, all of one depth if their layer is
+/// depth sorted.
+struct ChunkBuffer {
+    page: usize,
+    depth: i32,
+    buffer: Arc<CpuAccessibleBuffer<[Vertex2D]>>,
+}
+
+/// A grid of tiles drawn on one render layer.
+pub struct TilemapLayer {
+    pub name: String,
+    pub visible: bool,
+    /// Render layer, deciding what the tiles are drawn in front of.
+    pub layer: i32,
+    depth_sorted: bool,
+    tiles: Vec<Option<Tile>>,
+    chunks: Vec<Chunk>,
+}
+
+impl TilemapLayer {
+    pub fn tiles(&self) -> &[Option<Tile>] {
+        &self.tiles
+    }
+
+    pub fn is_depth_sorted(&self) -> bool {
+        self.depth_sorted
+    }
+}
+
+/// A grid of tiles in one or more layers, all from one tileset.
+/// * Cells are arranged by the map's layout. In an orthogonal map, tile
+///   `[x, y]` covers `x * tile_size[0]` to `(x + 1) * tile_size[0]`
+///   horizontally and likewise vertically, before `matrix` places the map.
+///   Row 0 is at the top.
+/// * Tiles are grouped into square chunks with their own vertex buffers,
+///   built once and rebuilt only after one of their tiles changed. Chunks
+///   outside the camera's view are not drawn.
+/// * Tiles lower on the screen are drawn over the ones above them. Tiles of
+///   a depth sorted layer are spread over render layers by their depth, so
+///   sprites placed with `Tilemap::depth_layer` go between them.
+pub struct Tilemap {
+    size: [u32; 2],
+    tile_size: [f32; 2],
+    layout: TileLayout,
+    chunk_size: u32,
+    tileset: Arc<Tileset>,
+    layers: Vec<TilemapLayer>,
+    /// Places the map in the world.
+    pub matrix: TMat4<f32>,
+}
+
+impl Tilemap {
+    /// Empty map of `size` tiles, each `tile_size` world units large. Add
+    /// layers to place tiles.
+    pub fn new(size: [u32; 2], tile_size: [f32; 2], tileset: Arc<Tileset>) -> Tilemap {
+        Tilemap {
+            size,
+            tile_size,
+            layout: TileLayout::Orthogonal,
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            tileset,
+            layers: Vec::new(),
+            matrix: glm::identity(),
+        }
+    }
+
+    /// Sets how cells are arranged. Depth sorted layers whose render layers
+    /// would run past `MAX_LAYER` in the new layout are left unsorted, as by
+    /// `set_layer_depth_sorted`.
+    pub fn with_layout(mut self, layout: TileLayout) -> Tilemap {
+        self.layout = layout;
+        self.layers
+            .iter_mut()
+            .flat_map(|layer| layer.chunks.iter_mut())
+            .for_each(|chunk| chunk.dirty = true);
+
+        for layer in 0..self.layers.len() {
+            if self.layers[layer].depth_sorted {
+                self.set_layer_depth_sorted(layer, true);
+            }
+        }
+        self
+    }
+
+    /// Sets how many tiles per side go into a chunk. Smaller chunks cull
+    /// more closely and rebuild faster but need more draws.
+    pub fn with_chunk_size(mut self, chunk_size: u32) -> Tilemap {
+        self.chunk_size = chunk_size.max(1);
+        let count = self.chunk_count();
+        for layer in &mut self.layers {
+            layer.chunks = (0..count).map(|_| Chunk::dirty()).collect();
+        }
+        self
+    }
+
+    pub fn size(&self) -> [u32; 2] {
+        self.size
+    }
+
+    pub fn tile_size(&self) -> [f32; 2] {
+        self.tile_size
+    }
+
+    pub fn layout(&self) -> TileLayout {
+        self.layout
+    }
+
+    pub fn tileset(&self) -> &Arc<Tileset> {
+        &self.tileset
+    }
+
+    /// Adds an empty layer on top of the others and returns its index.
+    pub fn add_layer(&mut self, name: &str, layer: i32) -> usize {
+        self.layers.push(TilemapLayer {
+            name: name.to_string(),
+            visible: true,
+            layer,
+            depth_sorted: false,
+            tiles: vec![None; (self.size[0] * self.size[1]) as usize],
+            chunks: (0..self.chunk_count()).map(|_| Chunk::dirty()).collect(),
+        });
+        self.layers.len() - 1
+    }
+
+    pub fn layers(&self) -> &[TilemapLayer] {
+        &self.layers
+    }
+
+    pub fn layer(&self, index: usize) -> Option<&TilemapLayer> {
+        self.layers.get(index)
+    }
+
+    pub fn layer_index(&self, name: &str) -> Option<usize> {
+        self.layers.iter().position(|layer| layer.name == name)
+    }
+
+    /// Shows or hides a layer. Hidden layers keep their buffers.
+    pub fn set_layer_visible(&mut self, layer: usize, visible: bool) {
+        self.layers[layer].visible = visible;
+    }
+
+    /// Spreads a layer's tiles over render layers by their depth: a tile of
+    /// depth `d` is drawn on render layer `layer + 2 * d`, e.g. for walls and
+    /// trees that characters walk behind. Takes twice as many render layers
+    /// above the layer's own as the map has rows of depth.
+    /// * Returns whether the layer is depth sorted now: a layer is left
+    ///   unsorted if its render layers would run past `MAX_LAYER`, where
+    ///   they could no longer be told apart.
+    pub fn set_layer_depth_sorted(&mut self, layer: usize, sorted: bool) -> bool {
+        let max_depth = self.max_depth();
+        let layer = &mut self.layers[layer];
+
+        let sorted = sorted && layer.layer.saturating_add(2 * max_depth + 1) <= MAX_LAYER;
+        if layer.depth_sorted != sorted {
+            layer.depth_sorted = sorted;
+            layer.chunks.iter_mut().for_each(|chunk| chunk.dirty = true);
+        }
+
+        sorted
+    }
+
+    /// Depth of the cells in front of all others. Depth grows down the map,
+    /// so they are on the last row.
+    fn max_depth(&self) -> i32 {
+        let [width, height] = self.size;
+
+        (0..width as i32)
+            .map(|x| self.layout.depth([x, height as i32 - 1]))
+            .max()
+            .unwrap_or(0)
+            .max(0)
+    }
+
+    /// Render layer for something standing at a world position among the
+    /// tiles of a depth sorted layer: in front of the tiles of its own cell's
+    /// depth and behind those of the next.
+    pub fn depth_layer(&self, layer: usize, position: [f32; 2]) -> i32 {
+        let [x, y] = self.to_map(position);
+        let cell = self.layout.point_to_cell([x, y], self.size, self.tile_size);
+
+        self.layers[layer].layer + 2 * self.layout.depth(cell) + 1
+    }
+
+    pub fn tile(&self, layer: usize, position: [u32; 2]) -> Option<Tile> {
+        let index = self.tile_index(position)?;
+        self.layers.get(layer)?.tiles[index]
+    }
+
+    /// Places or clears a tile. Positions outside the map are ignored.
+    pub fn set_tile(&mut self, layer: usize, position: [u32; 2], tile: Option<Tile>) {
+        let index = match self.tile_index(position) {
+            Some(index) => index,
+            None => return,
+        };
+        let chunk = self.chunk_index(position);
+
+        let layer = &mut self.layers[layer];
+        if layer.tiles[index] != tile {
+            layer.tiles[index] = tile;
+            layer.chunks[chunk].dirty = true;
+        }
+    }
+
+    /// Fills a layer from tiles listed row by row, e.g. as loaded from a
+    /// level file. Extra tiles are ignored.
+    pub fn set_tiles(&mut self, layer: usize, tiles: impl IntoIterator<Item = Option<Tile>>) {
+        let layer = &mut self.layers[layer];
+        for (slot, tile) in layer.tiles.iter_mut().zip(tiles) {
+            *slot = tile;
+        }
+        layer.chunks.iter_mut().for_each(|chunk| chunk.dirty = true);
+    }
+
+    /// The tile cell under a point in the world, if it is on the map.
+    pub fn world_to_tile(&self, position: [f32; 2]) -> Option<[u32; 2]> {
+        let [x, y] = self
+            .layout
+            .point_to_cell(self.to_map(position), self.size, self.tile_size);
+
+        match x >= 0 && y >= 0 && x < self.size[0] as i32 && y < self.size[1] as i32 {
+            true => Some([x as u32, y as u32]),
+            false => None,
+        }
+    }
+
+    /// World position of the center of a tile cell.
+    pub fn tile_to_world(&self, position: [u32; 2]) -> [f32; 2] {
+        let [x, y] = self.layout.cell_center(
+            [position[0] as i32, position[1] as i32],
+            self.size,
+            self.tile_size,
+        );
+        let world = self.matrix * glm::vec4(x, y, 0.0, 1.0);
+        [world.x, world.y]
+    }
+
+    /// The tile cell shown at a position on the screen, in the normalized
+    /// device coordinates of a view seen through `camera`.
+    pub fn screen_to_tile(&self, camera: &Camera2D, position: [f32; 2]) -> Option<[u32; 2]> {
+        self.world_to_tile(camera.view_to_world(position))
+    }
+
+    /// Where the center of a tile cell is on the screen, in the normalized
+    /// device coordinates of a view seen through `camera`.
+    pub fn tile_to_screen(&self, camera: &Camera2D, position: [u32; 2]) -> [f32; 2] {
+        camera.world_to_view(self.tile_to_world(position))
+    }
+
+    /// Queues the visible chunks of every visible layer, first rebuilding
+    /// the vertex buffers of chunks that changed.
+    pub fn draw(&mut self, frame: &mut Frame) {
+        let view = self.view_bounds(frame.camera().visible_bounds());
+        let chunks_x = self.chunks_per_row();
+
+        let mut visible = (0..self.chunk_count())
+            .filter(|&chunk| {
+                let [min, max] =
+                    self.chunk_bounds([chunk as u32 % chunks_x, chunk as u32 / chunks_x]);
+                min[0] <= view[1][0]
+                    && max[0] >= view[0][0]
+                    && min[1] <= view[1][1]
+                    && max[1] >= view[0][1]
+            })
+            .collect::<Vec<_>>();
+
+        // back to front, as draws on one render layer are drawn in order
+        visible.sort_by_key(|&chunk| {
+            let [x, y] = [chunk as u32 % chunks_x, chunk as u32 / chunks_x]
+                .map(|position| (position * self.chunk_size) as i32);
+            (self.layout.depth([x, y]), x)
+        });
+
+        for layer in 0..self.layers.len() {
+            if !self.layers[layer].visible {
+                continue;
+            }
+
+            for &chunk in &visible {
+                if self.layers[layer].chunks[chunk].dirty {
+                    let position = [chunk as u32 % chunks_x, chunk as u32 / chunks_x];
+                    let buffers = self.build_chunk(frame, layer, position);
+                    self.layers[layer].chunks[chunk] = Chunk {
+                        dirty: false,
+                        buffers,
+                    };
+                }
+
+                let map_layer = &self.layers[layer];
+                for buffer in &map_layer.chunks[chunk].buffers {
+                    let texture = self.tileset.atlas.pages()[buffer.page].clone();
+                    let translucent = texture.translucent();
+
+                    frame.draw_vertex_buffer(
+                        buffer.buffer.clone(),
+                        texture,
+                        [1.0; 4],
+                        self.matrix,
+                        map_layer.layer + 2 * buffer.depth,
+                        translucent,
+                    );
+                }
+            }
+        }
+    }
+
+    /// Builds the vertex buffers of one chunk of a layer, with tiles sorted
+    /// by depth. Consecutive tiles share a buffer while they are on the same
+    /// page, and of the same depth in a depth sorted layer.
+    fn build_chunk(&self, frame: &Frame, layer: usize, chunk: [u32; 2]) -> Vec<ChunkBuffer> {
+        let mut tiles = Vec::new();
+
+        let start = [chunk[0] * self.chunk_size, chunk[1] * self.chunk_size];
+        let end = [
+            (start[0] + self.chunk_size).min(self.size[0]),
+            (start[1] + self.chunk_size).min(self.size[1]),
+        ];
+
+        for y in start[1]..end[1] {
+            for x in start[0]..end[0] {
+                let tile = match self.layers[layer].tiles[(y * self.size[0] + x) as usize] {
+                    Some(tile) => tile,
+                    None => continue,
+                };
+                let (page, [tl, tr, br, bl]) = match self.tileset.corners(tile) {
+                    Some(corners) => corners,
+                    None => continue,
+                };
+
+                let cell = self
+                    .layout
+                    .cell_bounds([x as i32, y as i32], self.size, self.tile_size);
+                let size = self
+                    .tileset
+                    .image_size(self.tileset.region(tile).unwrap(), self.tile_size);
+                let [[left, top], [right, bottom]] = self.layout.image_bounds(cell, size);
+                let vertex = |x: f32, y: f32, uv: [f32; 2]| Vertex2D {
+                    position: [x, y, 0.0],
+                    uv,
+                };
+
+                tiles.push((
+                    self.layout.depth([x as i32, y as i32]),
+                    page,
+                    [
+                        vertex(left, top, tl),
+                        vertex(left, bottom, bl),
+                        vertex(right, bottom, br),
+                        vertex(left, top, tl),
+                        vertex(right, bottom, br),
+                        vertex(right, top, tr),
+                    ],
+                ));
+            }
+        }
+
+        // stable, so tiles of one depth stay in order from left to right
+        tiles.sort_by_key(|(depth, _, _)| *depth);
+
+        let sorted = self.layers[layer].depth_sorted;
+        let mut runs: Vec<(usize, i32, Vec<Vertex2D>)> = Vec::new();
+        for (depth, page, vertices) in tiles {
+            let depth = if sorted { depth } else { 0 };
+
+            match runs.last_mut() {
+                Some(run) if run.0 == page && run.1 == depth => run.2.extend(vertices),
+                _ => runs.push((page, depth, vertices.into())),
+            }
+        }
+
+        runs.into_iter()
+            .map(|(page, depth, vertices)| ChunkBuffer {
+                page,
+                depth,
+                buffer: frame.renderer().create_vertex_buffer(&vertices),
+            })
+            .collect()
+    }
+
+    /// Converts a world position into map space.
+    fn to_map(&self, position: [f32; 2]) -> [f32; 2] {
+        let local = glm::inverse(&self.matrix) * glm::vec4(position[0], position[1], 0.0, 1.0);
+        [local.x, local.y]
+    }
+
+    /// Bounding box in map space of a world rectangle, as the map may be
+    /// rotated.
+    fn view_bounds(&self, bounds: [[f32; 2]; 2]) -> [[f32; 2]; 2] {
+        let [[x0, y0], [x1, y1]] = bounds;
+
+        let mut view = [[f32::INFINITY; 2], [f32::NEG_INFINITY; 2]];
+        for corner in [[x0, y0], [x1, y0], [x1, y1], [x0, y1]] {
+            let [x, y] = self.to_map(corner);
+            view[0] = [view[0][0].min(x), view[0][1].min(y)];
+            view[1] = [view[1][0].max(x), view[1][1].max(y)];
+        }
+        view
+    }
+
+    /// Bounding box in map space of everything a chunk can draw: its corner
+    /// cells, grown by how far the largest tile image can stick out.
+    fn chunk_bounds(&self, chunk: [u32; 2]) -> [[f32; 2]; 2] {
+        let start = [chunk[0] * self.chunk_size, chunk[1] * self.chunk_size];
+        let end = [
+            (start[0] + self.chunk_size).min(self.size[0]) - 1,
+            (start[1] + self.chunk_size).min(self.size[1]) - 1,
+        ];
+
+        let mut bounds = [[f32::INFINITY; 2], [f32::NEG_INFINITY; 2]];
+        for [x, y] in [start, [end[0], start[1]], end, [start[0], end[1]]] {
+            let [min, max] =
+                self.layout
+                    .cell_bounds([x as i32, y as i32], self.size, self.tile_size);
+            bounds[0] = [bounds[0][0].min(min[0]), bounds[0][1].min(min[1])];
+            bounds[1] = [bounds[1][0].max(max[0]), bounds[1][1].max(max[1])];
+        }
+
+        let [width, height] = self.tileset.max_image_size(self.tile_size);
+        let overhang = [width - self.tile_size[0], height - self.tile_size[1]];
+        [
+            [bounds[0][0] - overhang[0], bounds[0][1] - overhang[1]],
+            [bounds[1][0] + overhang[0], bounds[1][1]],
+        ]
+    }
+
+    fn tile_index(&self, position: [u32; 2]) -> Option<usize> {
+        match position[0] < self.size[0] && position[1] < self.size[1] {
+            true => Some((position[1] * self.size[0] + position[0]) as usize),
+            false => None,
+        }
+    }
+
+    fn chunk_index(&self, position: [u32; 2]) -> usize {
+        let [x, y] = position.map(|p| p / self.chunk_size);
+        (y * self.chunks_per_row() + x) as usize
+    }
+
+    fn chunks_per_row(&self) -> u32 {
+        self.size[0].div_ceil(self.chunk_size)
+    }
+
+    fn chunks_per_column(&self) -> u32 {
+        self.size[1].div_ceil(self.chunk_size)
+    }
+
+    fn chunk_count(&self) -> usize {
+        (self.chunks_per_row() * self.chunks_per_column()) as usize
+    }
+}
+
+impl Chunk {
+    fn dirty() -> Chunk {
+        Chunk {
+            dirty: true,
+            buffers: Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::application::renderer::atlas::AtlasRegion;
+
+    const TL: [f32; 2] = [0.25, 0.5];
+    const TR: [f32; 2] = [0.75, 0.5];
+    const BR: [f32; 2] = [0.75, 1.0];
+    const BL: [f32; 2] = [0.25, 1.0];
+
+    fn tileset() -> Tileset {
+        let region = AtlasRegion {
+            page: 1,
+            uv: [TL, BR],
+            size: [16, 16],
+        };
+        let atlas = TextureAtlas::new(Vec::new(), vec![("tile".to_string(), region)]);
+        Tileset::from_atlas(Arc::new(atlas))
+    }
+
+    fn corners(flip_x: bool, flip_y: bool, flip_diagonal: bool) -> [[f32; 2]; 4] {
+        let tile = Tile {
+            index: 0,
+            flip_x,
+            flip_y,
+            flip_diagonal,
+        };
+        let (page, corners) = tileset().corners(tile).unwrap();
+        assert_eq!(page, 1);
+        corners
+    }
+
+    fn map(size: [u32; 2]) -> Tilemap {
+        let atlas = Arc::new(TextureAtlas::new(Vec::new(), Vec::new()));
+        Tilemap::new(size, [16.0, 16.0], Arc::new(Tileset::from_atlas(atlas)))
+    }
+
+    #[test]
+    fn depth_sorted_layers_stay_within_max_layer() {
+        // 100 rows of depth take render layers 0 to 198 above the layer's own
+        let mut map = map([4, 100]);
+        let fits = map.add_layer("fits", MAX_LAYER - 199);
+        let too_high = map.add_layer("too high", MAX_LAYER - 198);
+
+        assert!(map.set_layer_depth_sorted(fits, true));
+        assert!(map.layers()[fits].is_depth_sorted());
+        assert_eq!(map.depth_layer(fits, [0.0, 99.5 * 16.0]), MAX_LAYER);
+
+        assert!(!map.set_layer_depth_sorted(too_high, true));
+        assert!(!map.layers()[too_high].is_depth_sorted());
+
+        assert!(!map.set_layer_depth_sorted(fits, false));
+        assert!(!map.layers()[fits].is_depth_sorted());
+    }
+
+    #[test]
+    fn flips_swap_texture_corners() {
+        assert_eq!(corners(false, false, false), [TL, TR, BR, BL]);
+        assert_eq!(corners(true, false, false), [TR, TL, BL, BR]);
+        assert_eq!(corners(false, true, false), [BL, BR, TR, TL]);
+        assert_eq!(corners(true, true, false), [BR, BL, TL, TR]);
+        // mirrored along the top left to bottom right diagonal
+        assert_eq!(corners(false, false, true), [TL, BL, BR, TR]);
+    }
+
+    #[test]
+    fn diagonal_flips_combine_into_rotations() {
+        // a quarter turn clockwise
+        assert_eq!(corners(true, false, true), [BL, TL, TR, BR]);
+        // a quarter turn counter-clockwise
+        assert_eq!(corners(false, true, true), [TR, BR, BL, TL]);
+        // mirrored along the top right to bottom left diagonal
+        assert_eq!(corners(true, true, true), [BR, TR, TL, BL]);
+    }
+
+    #[test]
+    fn tiles_outside_the_tileset_have_no_corners() {
+        assert_eq!(tileset().corners(Tile::new(1)), None);
+    }
+
+    #[test]
+    fn changing_layout_unsorts_layers_past_max_layer() {
+        // isometric maps have twice the rows of depth of orthogonal ones
+        let mut map = map([100, 100]);
+        let ground = map.add_layer("ground", 0);
+        let walls = map.add_layer("walls", MAX_LAYER - 199);
+        map.set_layer_depth_sorted(ground, true);
+        map.set_layer_depth_sorted(walls, true);
+
+        let map = map.with_layout(TileLayout::Isometric);
+        assert!(map.layers()[ground].is_depth_sorted());
+        assert!(!map.layers()[walls].is_depth_sorted());
+    }
+}
@@ -0,0 +1,238 @@
+use vulkano::pipeline::graphics::viewport::{Scissor, Viewport};
+
+use super::camera::Camera2D;
+
+/// A region of a window or render target drawn through its own camera, with
+/// its own deferred and lighting passes, e.g. one player's part of a split
+/// screen.
+/// * `rect` is `[x, y, width, height]` as fractions of the render resolution,
+///   from the top left, so views follow the window when it is resized.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct View {
+    pub rect: [f32; 4],
+    pub camera: Camera2D,
+}
+
+impl Default for View {
+    fn default() -> View {
+        View::new([0.0, 0.0, 1.0, 1.0])
+    }
+}
+
+impl View {
+    pub fn new(rect: [f32; 4]) -> View {
+        View {
+            rect,
+            camera: Camera2D::default(),
+        }
+    }
+
+    /// Tiles the whole area with `count` views in a grid, left to right and
+    /// top to bottom: two side by side, four in quadrants. A last row with
+    /// fewer views is stretched across the full width, so three players get
+    /// two views on top and one below.
+    pub fn split(count: usize) -> Vec<View> {
+        let count = count.max(1);
+        let columns = (count as f32).sqrt().ceil() as usize;
+        let rows = count.div_ceil(columns);
+        let height = 1.0 / rows as f32;
+
+        (0..count)
+            .map(|i| {
+                let row = i / columns;
+                let in_row = match row == rows - 1 {
+                    true => count - columns * (rows - 1),
+                    false => columns,
+                };
+                let width = 1.0 / in_row as f32;
+
+                View::new([
+                    (i % columns) as f32 * width,
+                    row as f32 * height,
+                    width,
+                    height,
+                ])
+            })
+            .collect()
+    }
+
+    /// Pixel rectangle of the view for the given render resolution, as
+    /// offset and extent. Never empty, so a tiny view still renders.
+    pub(super) fn pixels(&self, resolution: [u32; 2]) -> ([u32; 2], [u32; 2]) {
+        let [x, y, width, height] = self.rect.map(|v| v.clamp(0.0, 1.0));
+
+        let offset = [
+            ((x * resolution[0] as f32) as u32).min(resolution[0] - 1),
+            ((y * resolution[1] as f32) as u32).min(resolution[1] - 1),
+        ];
+        let extent = [
+            ((width * resolution[0] as f32).round() as u32).clamp(1, resolution[0] - offset[0]),
+            ((height * resolution[1] as f32).round() as u32).clamp(1, resolution[1] - offset[1]),
+        ];
+
+        (offset, extent)
+    }
+
+    /// Viewport and scissor restricting draws to the view.
+    pub(super) fn viewport(&self, resolution: [u32; 2]) -> (Viewport, Scissor) {
+        let (origin, dimensions) = self.pixels(resolution);
+
+        let viewport = Viewport {
+            origin: [origin[0] as f32, origin[1] as f32],
+            dimensions: [dimensions[0] as f32, dimensions[1] as f32],
+            depth_range: 0.0..1.0,
+        };
+
+        (viewport, Scissor { origin, dimensions })
+    }
+
+    /// Converts a position in pixels of the render resolution into the
+    /// view's normalized device coordinates, None if it is outside the view.
+    pub(super) fn render_to_view(
+        &self,
+        position: [f32; 2],
+        resolution: [u32; 2],
+    ) -> Option<[f32; 2]> {
+        let (offset, extent) = self.pixels(resolution);
+
+        let x = (position[0] - offset[0] as f32) / extent[0] as f32;
+        let y = (position[1] - offset[1] as f32) / extent[1] as f32;
+
+        match (0.0..=1.0).contains(&x) && (0.0..=1.0).contains(&y) {
+            true => Some([x * 2.0 - 1.0, y * 2.0 - 1.0]),
+            false => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn overlap(a: [f32; 4], b: [f32; 4]) -> f32 {
+        let width = (a[0] + a[2]).min(b[0] + b[2]) - a[0].max(b[0]);
+        let height = (a[1] + a[3]).min(b[1] + b[3]) - a[1].max(b[1]);
+        width.max(0.0) * height.max(0.0)
+    }
+
+    #[test]
+    fn split_tiles_the_whole_area() {
+        for count in 1..=5 {
+            let views = View::split(count);
+            assert_eq!(views.len(), count);
+
+            for (i, view) in views.iter().enumerate() {
+                let [x, y, width, height] = view.rect;
+                assert!(x >= 0.0 && y >= 0.0, "{} views: {:?}", count, view.rect);
+                assert!(x + width <= 1.0 + 1e-6 && y + height <= 1.0 + 1e-6);
+
+                for other in &views[i + 1..] {
+                    assert!(
+                        overlap(view.rect, other.rect) < 1e-6,
+                        "{} views overlap",
+                        count
+                    );
+                }
+            }
+
+            let area = views
+                .iter()
+                .map(|view| view.rect[2] * view.rect[3])
+                .sum::<f32>();
+            assert!((area - 1.0).abs() < 1e-5, "{} views cover {}", count, area);
+        }
+    }
+
+    #[test]
+    fn split_widens_a_short_last_row() {
+        assert_eq!(View::split(0), [View::default()]);
+        assert_eq!(
+            View::split(3)
+                .iter()
+                .map(|view| view.rect)
+                .collect::<Vec<_>>(),
+            [
+                [0.0, 0.0, 0.5, 0.5],
+                [0.5, 0.0, 0.5, 0.5],
+                [0.0, 0.5, 1.0, 0.5]
+            ]
+        );
+
+        let views = View::split(5);
+        assert!(views[..3].iter().all(|view| view.rect[2] == 1.0 / 3.0));
+        assert_eq!(views[3].rect, [0.0, 0.5, 0.5, 0.5]);
+        assert_eq!(views[4].rect, [0.5, 0.5, 0.5, 0.5]);
+    }
+
+    #[test]
+    fn pixels_are_never_empty_and_stay_in_bounds() {
+        let rects = [
+            [0.0, 0.0, 1.0, 1.0],
+            [0.5, 0.5, 0.5, 0.5],
+            [0.999, 0.999, 0.0001, 0.0001],
+            [1.0, 1.0, 0.0, 0.0],
+            [-0.5, -0.5, 2.0, 2.0],
+            [0.25, 0.75, 1.0, 1.0],
+        ];
+
+        for resolution in [[1, 1], [7, 5], [320, 180], [1920, 1080]] {
+            for rect in rects {
+                let (offset, extent) = View::new(rect).pixels(resolution);
+
+                assert!(
+                    extent[0] >= 1 && extent[1] >= 1,
+                    "{:?} at {:?}",
+                    rect,
+                    resolution
+                );
+                assert!(
+                    offset[0] + extent[0] <= resolution[0],
+                    "{:?} at {:?}",
+                    rect,
+                    resolution
+                );
+                assert!(
+                    offset[1] + extent[1] <= resolution[1],
+                    "{:?} at {:?}",
+                    rect,
+                    resolution
+                );
+            }
+        }
+
+        assert_eq!(
+            View::new([0.5, 0.0, 0.5, 0.5]).pixels([800, 600]),
+            ([400, 0], [400, 300])
+        );
+    }
+
+    #[test]
+    fn render_to_view_maps_the_view_corners_to_the_unit_square() {
+        let view = View::new([0.5, 0.0, 0.5, 0.5]);
+        let resolution = [800, 600];
+
+        assert_eq!(
+            view.render_to_view([400.0, 0.0], resolution),
+            Some([-1.0, -1.0])
+        );
+        assert_eq!(
+            view.render_to_view([800.0, 300.0], resolution),
+            Some([1.0, 1.0])
+        );
+        assert_eq!(
+            view.render_to_view([800.0, 0.0], resolution),
+            Some([1.0, -1.0])
+        );
+        assert_eq!(
+            view.render_to_view([400.0, 300.0], resolution),
+            Some([-1.0, 1.0])
+        );
+        assert_eq!(
+            view.render_to_view([600.0, 150.0], resolution),
+            Some([0.0, 0.0])
+        );
+
+        assert_eq!(view.render_to_view([399.0, 150.0], resolution), None);
+        assert_eq!(view.render_to_view([600.0, 301.0], resolution), None);
+    }
+}
@@ -0,0 +1,304 @@
+pub mod transform;
+pub use transform::Transform2D;
+
+use nalgebra_glm::{self as glm, TMat4};
+
+use super::renderer::renderables::lights::{DirectionalLight, PointLight};
+use super::renderer::renderables::{ColorRenderable, Renderable};
+
+/// Handle to a node in a Scene. Handles of removed nodes are never reused,
+/// so a stale handle can not alias a newer node.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct NodeId {
+    index: usize,
+    generation: u32,
+}
+
+/// Something carried by a node. Renderable matrices and light positions are
+/// relative to the node they are attached to.
+pub enum Attachment {
+    Renderable(Box<dyn Renderable>),
+    ColorRenderable(Box<dyn ColorRenderable>),
+    PointLight(PointLight),
+    DirectionalLight(DirectionalLight),
+}
+
+struct Node {
+    transform: Transform2D,
+    visible: bool,
+    parent: Option<NodeId>,
+    children: Vec<NodeId>,
+    attachments: Vec<Attachment>,
+    world: TMat4<f32>,
+    dirty: bool,
+}
+
+struct Slot {
+    generation: u32,
+    node: Option<Node>,
+}
+
+/// Hierarchy of transformed nodes. A node's world matrix is its parent's
+/// world matrix times its own local transform, so moving a node moves
+/// everything attached below it.
+/// * World matrices are cached and only recomputed for changed subtrees
+///   when `update` is called.
+#[derive(Default)]
+pub struct Scene {
+    slots: Vec<Slot>,
+    free: Vec<usize>,
+    roots: Vec<NodeId>,
+}
+
+impl Scene {
+    pub fn new() -> Scene {
+        Scene::default()
+    }
+
+    /// Adds a node at the top of the hierarchy.
+    pub fn add(&mut self, transform: Transform2D) -> NodeId {
+        let id = self.insert(transform, None);
+        self.roots.push(id);
+        id
+    }
+
+    /// Adds a node as the last child of `parent`.
+    pub fn add_child(&mut self, parent: NodeId, transform: Transform2D) -> NodeId {
+        let id = self.insert(transform, Some(parent));
+        self.node_mut(parent).children.push(id);
+        id
+    }
+
+    fn insert(&mut self, transform: Transform2D, parent: Option<NodeId>) -> NodeId {
+        let node = Node {
+            transform,
+            visible: true,
+            parent,
+            children: Vec::new(),
+            attachments: Vec::new(),
+            world: glm::identity(),
+            dirty: true,
+        };
+
+        match self.free.pop() {
+            Some(index) => {
+                let slot = &mut self.slots[index];
+                slot.node = Some(node);
+                NodeId {
+                    index,
+                    generation: slot.generation,
+                }
+            }
+            None => {
+                self.slots.push(Slot {
+                    generation: 0,
+                    node: Some(node),
+                });
+                NodeId {
+                    index: self.slots.len() - 1,
+                    generation: 0,
+                }
+            }
+        }
+    }
+
+    /// Removes a node along with all of its descendants and attachments.
+    pub fn remove(&mut self, id: NodeId) {
+        if !self.contains(id) {
+            return;
+        }
+
+        self.detach_from_parent(id);
+
+        let mut stack = vec![id];
+        while let Some(id) = stack.pop() {
+            let slot = &mut self.slots[id.index];
+            let node = slot.node.take().unwrap();
+            slot.generation += 1;
+            self.free.push(id.index);
+            stack.extend(node.children);
+        }
+    }
+
+    /// Returns true if the handle refers to a node that has not been removed.
+    pub fn contains(&self, id: NodeId) -> bool {
+        self.get(id).is_some()
+    }
+
+    /// Moves a node, along with its descendants, under a new parent, or to
+    /// the top of the hierarchy if `parent` is None. The local transform is
+    /// kept, so the node moves with its new parent from then on.
+    /// * Returns false and changes nothing if `parent` is the node itself or
+    ///   one of its descendants.
+    pub fn set_parent(&mut self, id: NodeId, parent: Option<NodeId>) -> bool {
+        if let Some(parent) = parent {
+            let mut ancestor = Some(parent);
+            while let Some(current) = ancestor {
+                if current == id {
+                    return false;
+                }
+                ancestor = self.node(current).parent;
+            }
+        }
+
+        self.detach_from_parent(id);
+
+        match parent {
+            Some(parent) => self.node_mut(parent).children.push(id),
+            None => self.roots.push(id),
+        }
+
+        let node = self.node_mut(id);
+        node.parent = parent;
+        node.dirty = true;
+
+        true
+    }
+
+    fn detach_from_parent(&mut self, id: NodeId) {
+        let siblings = match self.node(id).parent {
+            Some(parent) => &mut self.node_mut(parent).children,
+            None => &mut self.roots,
+        };
+        siblings.retain(|&sibling| sibling != id);
+    }
+
+    pub fn parent(&self, id: NodeId) -> Option<NodeId> {
+        self.node(id).parent
+    }
+
+    pub fn children(&self, id: NodeId) -> &[NodeId] {
+        &self.node(id).children
+    }
+
+    pub fn roots(&self) -> &[NodeId] {
+        &self.roots
+    }
+
+    pub fn transform(&self, id: NodeId) -> &Transform2D {
+        &self.node(id).transform
+    }
+
+    /// Returns the local transform for editing. The node's subtree gets new
+    /// world matrices on the next `update`.
+    pub fn transform_mut(&mut self, id: NodeId) -> &mut Transform2D {
+        let node = self.node_mut(id);
+        node.dirty = true;
+        &mut node.transform
+    }
+
+    pub fn set_transform(&mut self, id: NodeId, transform: Transform2D) {
+        *self.transform_mut(id) = transform;
+    }
+
+    /// Hidden nodes are skipped when drawing, along with their descendants.
+    pub fn set_visible(&mut self, id: NodeId, visible: bool) {
+        self.node_mut(id).visible = visible;
+    }
+
+    pub fn visible(&self, id: NodeId) -> bool {
+        self.node(id).visible
+    }
+
+    pub fn attach(&mut self, id: NodeId, attachment: Attachment) {
+        self.node_mut(id).attachments.push(attachment);
+    }
+
+    pub fn attachments(&self, id: NodeId) -> &[Attachment] {
+        &self.node(id).attachments
+    }
+
+    pub fn attachments_mut(&mut self, id: NodeId) -> &mut Vec<Attachment> {
+        &mut self.node_mut(id).attachments
+    }
+
+    /// Returns the cached world matrix of a node, as of the last `update`.
+    pub fn world_matrix(&self, id: NodeId) -> TMat4<f32> {
+        self.node(id).world
+    }
+
+    /// Recomputes the world matrices of every node whose transform, or an
+    /// ancestor's transform, changed since the last update.
+    pub fn update(&mut self) {
+        let mut stack = self
+            .roots
+            .iter()
+            .map(|&id| (id, glm::identity(), false))
+            .collect::<Vec<(NodeId, TMat4<f32>, bool)>>();
+
+        while let Some((id, parent_world, parent_changed)) = stack.pop() {
+            let node = self.node_mut(id);
+            let changed = parent_changed || node.dirty;
+
+            if changed {
+                node.world = parent_world * node.transform.matrix();
+                node.dirty = false;
+            }
+
+            let world = node.world;
+            stack.extend(node.children.iter().map(|&child| (child, world, changed)));
+        }
+    }
+
+    /// Returns the visible nodes, parents before their children. Descendants
+    /// of hidden nodes are left out.
+    pub fn visible_nodes(&self) -> Vec<NodeId> {
+        let mut nodes = Vec::new();
+        let mut stack = self.roots.iter().rev().copied().collect::<Vec<_>>();
+
+        while let Some(id) = stack.pop() {
+            let node = self.node(id);
+            if !node.visible {
+                continue;
+            }
+
+            nodes.push(id);
+            stack.extend(node.children.iter().rev());
+        }
+
+        nodes
+    }
+
+    fn get(&self, id: NodeId) -> Option<&Node> {
+        self.slots
+            .get(id.index)
+            .filter(|slot| slot.generation == id.generation)
+            .and_then(|slot| slot.node.as_ref())
+    }
+
+    fn node(&self, id: NodeId) -> &Node {
+        self.get(id).expect("Scene node was removed")
+    }
+
+    fn node_mut(&mut self, id: NodeId) -> &mut Node {
+        self.slots
+            .get_mut(id.index)
+            .filter(|slot| slot.generation == id.generation)
+            .and_then(|slot| slot.node.as_mut())
+            .expect("Scene node was removed")
+    }
+}
+
+/// Transforms a point light's position into world space.
+pub fn world_point_light(world: &TMat4<f32>, light: &PointLight) -> PointLight {
+    let position = world * glm::vec4(light.position[0], light.position[1], light.position[2], 1.0);
+
+    // the radius grows with the node's scale along x
+    let scale = (world[(0, 0)].powi(2) + world[(1, 0)].powi(2)).sqrt();
+
+    PointLight {
+        position: [position.x, position.y, light.position[2]],
+        radius: light.radius * scale,
+        ..*light
+    }
+}
+
+/// Rotates and scales a directional light's direction into world space.
+pub fn world_directional_light(world: &TMat4<f32>, light: &DirectionalLight) -> DirectionalLight {
+    let direction = world * glm::vec4(light.direction[0], light.direction[1], 0.0, 0.0);
+
+    DirectionalLight {
+        direction: [direction.x, direction.y],
+        ..*light
+    }
+}
@@ -0,0 +1,135 @@
+use nalgebra_glm::{self as glm, TMat4};
+
+/// Position, rotation and scale of something in 2D, relative to its parent.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Transform2D {
+    pub position: [f32; 2],
+    /// Counter-clockwise rotation in radians.
+    pub rotation: f32,
+    pub scale: [f32; 2],
+    /// Point, in local coordinates, that rotation and scale happen around and
+    /// that ends up at `position`.
+    pub pivot: [f32; 2],
+}
+
+impl Default for Transform2D {
+    fn default() -> Transform2D {
+        Transform2D {
+            position: [0.0, 0.0],
+            rotation: 0.0,
+            scale: [1.0, 1.0],
+            pivot: [0.0, 0.0],
+        }
+    }
+}
+
+impl Transform2D {
+    pub fn from_position(position: [f32; 2]) -> Transform2D {
+        Transform2D {
+            position,
+            ..Default::default()
+        }
+    }
+
+    /// Local matrix: translate to position, rotate, scale, then offset by the
+    /// pivot.
+    pub fn matrix(&self) -> TMat4<f32> {
+        glm::translation(&glm::vec3(self.position[0], self.position[1], 0.0))
+            * glm::rotation(self.rotation, &glm::vec3(0.0, 0.0, 1.0))
+            * glm::scaling(&glm::vec3(self.scale[0], self.scale[1], 1.0))
+            * glm::translation(&glm::vec3(-self.pivot[0], -self.pivot[1], 0.0))
+    }
+
+    /// Blends between two transforms, e.g. two simulation states. Rotation
+    /// turns the shorter way round, so an angle wrapping from just under a
+    /// full turn to just over 0 doesn't spin back through every other angle.
+    pub fn lerp(&self, other: &Transform2D, t: f32) -> Transform2D {
+        let mix = |a: f32, b: f32| a + (b - a) * t;
+
+        // the turn from one rotation to the other, within (-pi, pi]
+        let turn = (other.rotation - self.rotation).rem_euclid(std::f32::consts::TAU);
+        let turn = match turn > std::f32::consts::PI {
+            true => turn - std::f32::consts::TAU,
+            false => turn,
+        };
+
+        Transform2D {
+            position: [
+                mix(self.position[0], other.position[0]),
+                mix(self.position[1], other.position[1]),
+            ],
+            rotation: self.rotation + turn * t,
+            scale: [
+                mix(self.scale[0], other.scale[0]),
+                mix(self.scale[1], other.scale[1]),
+            ],
+            pivot: [
+                mix(self.pivot[0], other.pivot[0]),
+                mix(self.pivot[1], other.pivot[1]),
+            ],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::{FRAC_PI_2, PI, TAU};
+
+    use super::*;
+
+    fn rotated(rotation: f32) -> Transform2D {
+        Transform2D {
+            rotation,
+            ..Default::default()
+        }
+    }
+
+    fn assert_close(a: f32, b: f32) {
+        assert!((a - b).abs() < 1e-5, "{} is not {}", a, b);
+    }
+
+    #[test]
+    fn lerp_blends_every_field() {
+        let from = Transform2D {
+            position: [0.0, 2.0],
+            rotation: 0.0,
+            scale: [1.0, 1.0],
+            pivot: [0.0, 0.0],
+        };
+        let to = Transform2D {
+            position: [4.0, -2.0],
+            rotation: FRAC_PI_2,
+            scale: [3.0, 2.0],
+            pivot: [1.0, 1.0],
+        };
+
+        let half = from.lerp(&to, 0.5);
+        assert_eq!(half.position, [2.0, 0.0]);
+        assert_close(half.rotation, FRAC_PI_2 / 2.0);
+        assert_eq!(half.scale, [2.0, 1.5]);
+        assert_eq!(half.pivot, [0.5, 0.5]);
+    }
+
+    #[test]
+    fn lerp_turns_the_short_way_round() {
+        // across the wrap from just under a full turn to just over 0
+        let half = rotated(TAU - 0.1).lerp(&rotated(0.1), 0.5);
+        assert_close(half.rotation.rem_euclid(TAU), 0.0);
+
+        let half = rotated(0.1).lerp(&rotated(TAU - 0.1), 0.5);
+        assert_close(half.rotation, 0.0);
+
+        // angles more than a turn apart
+        let quarter = rotated(0.0).lerp(&rotated(3.0 * TAU + 0.4), 0.25);
+        assert_close(quarter.rotation, 0.1);
+    }
+
+    #[test]
+    fn lerp_turns_half_a_turn_counter_clockwise() {
+        let half = rotated(0.0).lerp(&rotated(PI), 0.5);
+        assert_close(half.rotation, FRAC_PI_2);
+
+        let half = rotated(0.0).lerp(&rotated(-PI), 0.5);
+        assert_close(half.rotation, FRAC_PI_2);
+    }
+}
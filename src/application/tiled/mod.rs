@@ -0,0 +1,832 @@
+//! Import of maps made with the Tiled editor, from its XML (.tmx) or JSON
+//! (.tmj) format, with tilesets embedded or in .tsx or .tsj files.
+//!
+//! * Orthogonal, isometric, staggered and hexagonal maps are supported.
+//! * Tile layers become the layers of one `Tilemap`, whose tileset holds the
+//!   tiles of every tileset of the map. Tiles larger than the map's grid
+//!   keep their size, resting on the bottom of their cell as in Tiled.
+//! * Layers are drawn in the order Tiled lists them: the `n`th layer, counting
+//!   from the bottom with groups flattened, is on render layer `n`.
+//! * Map units are Tiled's pixels. Scale the map with `tilemap.matrix`;
+//!   objects and image layers follow it.
+//! * Objects keep their shape and custom properties. `TiledMap::spawn` turns
+//!   them into entities, with a Sprite for tile objects and a PointLight for
+//!   objects of class `light`.
+//! * Layer opacity and tint colors, parallax origins and tile animations are
+//!   ignored. Features that would change what is where, like infinite maps,
+//!   tile offsets, parallax or repeating images, are refused with
+//!   `TiledError::Unsupported`.
+
+mod model;
+mod tmj;
+mod tmx;
+
+use nalgebra_glm as glm;
+
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::application::ecs::components::{PointLight, Sprite, Transform};
+use crate::application::ecs::{Entity, World};
+use crate::application::renderer::atlas::{AtlasRegion, TextureAtlas};
+use crate::application::renderer::renderables::quad::Quad;
+use crate::application::renderer::texture::Texture;
+use crate::application::renderer::tilemap::{
+    StaggerAxis, StaggerIndex, Tile, TileLayout, Tilemap, Tileset,
+};
+use crate::application::renderer::{Frame, Renderer};
+use model::{ImageData, LayerKind, MapData, ObjectData, TilesetData};
+
+/// Custom properties of a map, layer or object, by name.
+pub type Properties = HashMap<String, Property>;
+
+/// Value of a custom property.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Property {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    String(String),
+    /// RGBA between 0 and 1.
+    Color([f32; 4]),
+    /// Path as written in Tiled, relative to the map.
+    File(String),
+    /// Id of another object, 0 if none.
+    Object(u32),
+    /// Members of a custom class.
+    Class(Properties),
+}
+
+impl Property {
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Property::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Int or float properties as a float.
+    pub fn as_f32(&self) -> Option<f32> {
+        match self {
+            Property::Int(value) => Some(*value as f32),
+            Property::Float(value) => Some(*value as f32),
+            _ => None,
+        }
+    }
+
+    /// String or file properties as a string.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Property::String(value) | Property::File(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_color(&self) -> Option<[f32; 4]> {
+        match self {
+            Property::Color(color) => Some(*color),
+            _ => None,
+        }
+    }
+}
+
+/// Outline of an object, relative to its position.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ObjectShape {
+    /// Extends right and down from the position, or up from it for tile
+    /// objects.
+    Rectangle {
+        size: [f32; 2],
+    },
+    /// Ellipse within the rectangle of `size`.
+    Ellipse {
+        size: [f32; 2],
+    },
+    Point,
+    /// Closed outline.
+    Polygon(Vec<[f32; 2]>),
+    /// Open line.
+    Polyline(Vec<[f32; 2]>),
+}
+
+/// An object of an object layer. Spawned entities carry it as a component.
+/// * Positions are in map space, before the map's matrix. In isometric maps
+///   they are already projected: rectangles become polygons of their four
+///   projected corners.
+#[derive(Clone, Debug)]
+pub struct TiledObject {
+    pub id: u32,
+    pub name: String,
+    pub class: String,
+    /// Name of the object layer.
+    pub layer: String,
+    /// Render layer of the object layer.
+    pub render_layer: i32,
+    pub position: [f32; 2],
+    /// Clockwise around the position, in radians.
+    pub rotation: f32,
+    pub shape: ObjectShape,
+    /// Tile shown by a tile object, from the map's tileset.
+    pub tile: Option<Tile>,
+    pub visible: bool,
+    pub properties: Properties,
+}
+
+/// An image layer, drawn as one image with its top left corner at
+/// `position` in map space.
+pub struct ImageLayer {
+    pub name: String,
+    pub texture: Arc<Texture>,
+    pub position: [f32; 2],
+    pub layer: i32,
+    pub visible: bool,
+    pub properties: Properties,
+}
+
+/// A map loaded from Tiled.
+pub struct TiledMap {
+    pub tilemap: Tilemap,
+    pub image_layers: Vec<ImageLayer>,
+    pub objects: Vec<TiledObject>,
+    /// Custom properties of the map itself.
+    pub properties: Properties,
+}
+
+impl TiledMap {
+    /// Loads a .tmx or .tmj map, with the tilesets and images it refers to.
+    /// Other extensions are read as JSON.
+    pub fn load(renderer: &Renderer, path: &str) -> Result<TiledMap, TiledError> {
+        let path = Path::new(path);
+        let dir = path.parent().unwrap_or_else(|| Path::new(""));
+        let text = std::fs::read_to_string(path)?;
+
+        let map = match is_xml(path) {
+            true => tmx::parse_map(&text, dir)?,
+            false => tmj::parse_map(&text, dir)?,
+        };
+
+        Builder {
+            renderer,
+            textures: HashMap::new(),
+        }
+        .build(map)
+    }
+
+    /// The first object with the given name.
+    pub fn object(&self, name: &str) -> Option<&TiledObject> {
+        self.objects.iter().find(|object| object.name == name)
+    }
+
+    pub fn objects_of_class<'a>(
+        &'a self,
+        class: &'a str,
+    ) -> impl Iterator<Item = &'a TiledObject> + 'a {
+        self.objects
+            .iter()
+            .filter(move |object| object.class == class)
+    }
+
+    /// World position of a point in map space.
+    pub fn to_world(&self, point: [f32; 2]) -> [f32; 2] {
+        let world = self.tilemap.matrix * glm::vec4(point[0], point[1], 0.0, 1.0);
+        [world.x, world.y]
+    }
+
+    /// Queues the tilemap and the visible image layers.
+    pub fn draw(&mut self, frame: &mut Frame) {
+        self.tilemap.draw(frame);
+
+        for image in self.image_layers.iter().filter(|image| image.visible) {
+            let size = image.texture.dimensions().map(|d| d as f32);
+            let center = [
+                image.position[0] + size[0] / 2.0,
+                image.position[1] + size[1] / 2.0,
+            ];
+
+            frame.draw(&Quad {
+                vertices: Quad::rect(size, [[0.0, 0.0], [1.0, 1.0]]),
+                matrix: self.tilemap.matrix
+                    * glm::translation(&glm::vec3(center[0], center[1], 0.0)),
+                layer: image.layer,
+                texture: image.texture.clone(),
+            });
+        }
+    }
+
+    /// Spawns an entity for every object, placed by the map's matrix, with
+    /// the object as a component. Visible tile objects get a Sprite, and
+    /// objects of class `light` a PointLight from their `color`, `intensity`,
+    /// `radius` and `z` properties, at their center. Without a `radius`,
+    /// ellipses and rectangles light the circle filling them.
+    pub fn spawn(&self, world: &mut World) -> Vec<Entity> {
+        self.objects
+            .iter()
+            .map(|object| {
+                let entity = world.spawn();
+                let mut transform = self.world_transform(object.position, object.rotation);
+
+                if let (Some(tile), ObjectShape::Rectangle { size }, true) =
+                    (object.tile, &object.shape, object.visible)
+                {
+                    if let Some(sprite) = self.tile_sprite(tile, *size, object.render_layer) {
+                        // tile objects hang from their bottom left corner, or
+                        // bottom center in isometric maps
+                        transform.pivot = match self.tilemap.layout() {
+                            TileLayout::Isometric => [0.0, size[1] / 2.0],
+                            _ => [-size[0] / 2.0, size[1] / 2.0],
+                        };
+                        world.insert(entity, sprite);
+                    }
+                }
+
+                if object.class == "light" {
+                    world.insert(entity, object_light(object));
+                }
+
+                world.insert(entity, transform);
+                world.insert(entity, object.clone());
+                entity
+            })
+            .collect()
+    }
+
+    /// Transform placing a point of map space, rotated clockwise by
+    /// `rotation`, in the world.
+    fn world_transform(&self, position: [f32; 2], rotation: f32) -> Transform {
+        let matrix = self.tilemap.matrix;
+        let x_axis = matrix * glm::vec4(1.0, 0.0, 0.0, 0.0);
+        let y_axis = matrix * glm::vec4(0.0, 1.0, 0.0, 0.0);
+
+        Transform {
+            position: self.to_world(position),
+            rotation: rotation + x_axis.y.atan2(x_axis.x),
+            scale: [x_axis.xy().norm(), y_axis.xy().norm()],
+            pivot: [0.0, 0.0],
+        }
+    }
+
+    fn tile_sprite(&self, tile: Tile, size: [f32; 2], layer: i32) -> Option<Sprite> {
+        let atlas = self.tilemap.tileset().atlas();
+        let region = atlas.region_at(tile.index as usize)?;
+        let [[u0, v0], [u1, v1]] = region.uv;
+
+        let mut sprite = Sprite::new(atlas.texture(region), size);
+        sprite.uv = [
+            [
+                if tile.flip_x { u1 } else { u0 },
+                if tile.flip_y { v1 } else { v0 },
+            ],
+            [
+                if tile.flip_x { u0 } else { u1 },
+                if tile.flip_y { v0 } else { v1 },
+            ],
+        ];
+        sprite.layer = layer;
+        Some(sprite)
+    }
+}
+
+/// Point light of a `light` object, at the center of its shape.
+fn object_light(object: &TiledObject) -> PointLight {
+    let property = |name: &str| object.properties.get(name);
+    let color = property("color")
+        .and_then(Property::as_color)
+        .unwrap_or([1.0; 4]);
+
+    let center = match &object.shape {
+        ObjectShape::Rectangle { size } | ObjectShape::Ellipse { size } => {
+            [size[0] / 2.0, size[1] / 2.0]
+        }
+        ObjectShape::Point => [0.0, 0.0],
+        ObjectShape::Polygon(points) | ObjectShape::Polyline(points) => {
+            let count = points.len().max(1) as f32;
+            let [x, y] = points
+                .iter()
+                .fold([0.0, 0.0], |[x, y], point| [x + point[0], y + point[1]]);
+            [x / count, y / count]
+        }
+    };
+
+    PointLight {
+        position: [
+            center[0],
+            center[1],
+            property("z").and_then(Property::as_f32).unwrap_or(0.0),
+        ],
+        color: [color[0], color[1], color[2]],
+        intensity: property("intensity")
+            .and_then(Property::as_f32)
+            .unwrap_or(1.0),
+        radius: property("radius")
+            .and_then(Property::as_f32)
+            .unwrap_or(match &object.shape {
+                ObjectShape::Rectangle { size } | ObjectShape::Ellipse { size } => {
+                    size[0].max(size[1]) / 2.0
+                }
+                _ => 0.0,
+            }),
+    }
+}
+
+fn is_xml(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|extension| extension.to_str()),
+        Some("tmx" | "tsx" | "xml")
+    )
+}
+
+/// Reads an external tileset, in either format.
+pub(crate) fn load_tileset(path: &Path, first_gid: u32) -> Result<TilesetData, TiledError> {
+    let dir = path.parent().unwrap_or_else(|| Path::new(""));
+    let text = std::fs::read_to_string(path)?;
+
+    match is_xml(path) {
+        true => tmx::parse_tileset(&text, first_gid, dir),
+        false => tmj::parse_tileset(&text, first_gid, dir),
+    }
+}
+
+/// Tile flags in the top bits of global tile ids.
+const FLIP_X: u32 = 0x8000_0000;
+const FLIP_Y: u32 = 0x4000_0000;
+const FLIP_DIAGONAL: u32 = 0x2000_0000;
+const ROTATE_HEX: u32 = 0x1000_0000;
+
+/// Turns a parsed map into a TiledMap, loading each image once.
+struct Builder<'a> {
+    renderer: &'a Renderer,
+    textures: HashMap<PathBuf, Arc<Texture>>,
+}
+
+/// Maps the tile ids of one tileset to tiles of the combined tileset.
+struct GidRange {
+    first_gid: u32,
+    tiles: HashMap<u32, u32>,
+}
+
+impl Builder<'_> {
+    fn build(mut self, mut map: MapData) -> Result<TiledMap, TiledError> {
+        let axis = match map.stagger_axis.as_str() {
+            "x" => StaggerAxis::X,
+            _ => StaggerAxis::Y,
+        };
+        let index = match map.stagger_index.as_str() {
+            "even" => StaggerIndex::Even,
+            _ => StaggerIndex::Odd,
+        };
+        let layout = match map.orientation.as_str() {
+            "orthogonal" => TileLayout::Orthogonal,
+            "isometric" => TileLayout::Isometric,
+            "staggered" => TileLayout::Staggered { axis, index },
+            "hexagonal" => TileLayout::Hexagonal {
+                axis,
+                index,
+                side_length: map.hex_side_length as f32,
+            },
+            other => return Err(TiledError::Unsupported(format!("{} maps", other))),
+        };
+        if map.infinite {
+            return Err(TiledError::Unsupported("infinite maps".to_string()));
+        }
+
+        let (tileset, ranges) = self.tileset(&map)?;
+        let tile_size = map.tile_size.map(|size| size as f32);
+        let mut tilemap = Tilemap::new(map.size, tile_size, Arc::new(tileset)).with_layout(layout);
+
+        let mut image_layers = Vec::new();
+        let mut objects = Vec::new();
+
+        for (index, layer) in std::mem::take(&mut map.layers).into_iter().enumerate() {
+            if layer.parallax != [1.0, 1.0] {
+                return Err(TiledError::Unsupported(format!(
+                    "parallax scrolling of layer {}",
+                    layer.name
+                )));
+            }
+
+            match layer.kind {
+                LayerKind::Tiles { size, gids } => {
+                    if layer.offset != [0.0, 0.0] {
+                        return Err(TiledError::Unsupported(format!(
+                            "offset of tile layer {}",
+                            layer.name
+                        )));
+                    }
+                    if size != map.size || !(gids.is_empty() || gids.len() == tile_count(size)) {
+                        return Err(TiledError::Invalid(format!(
+                            "tile layer {} does not match the map's size",
+                            layer.name
+                        )));
+                    }
+
+                    let tiles = gids
+                        .into_iter()
+                        .map(|gid| tile(&ranges, gid))
+                        .collect::<Result<Vec<_>, _>>()?;
+
+                    let map_layer = tilemap.add_layer(&layer.name, index as i32);
+                    tilemap.set_tiles(map_layer, tiles);
+                    tilemap.set_layer_visible(map_layer, layer.visible);
+                }
+                LayerKind::Objects(layer_objects) => {
+                    for object in layer_objects {
+                        let tile = match object.gid {
+                            Some(gid) => tile(&ranges, gid)?,
+                            None => None,
+                        };
+                        let (position, shape) = object_shape(&object, tile.is_some(), &map)?;
+
+                        objects.push(TiledObject {
+                            id: object.id,
+                            name: object.name,
+                            class: object.class,
+                            layer: layer.name.clone(),
+                            render_layer: index as i32,
+                            position: [
+                                position[0] + layer.offset[0],
+                                position[1] + layer.offset[1],
+                            ],
+                            rotation: object.rotation.to_radians(),
+                            shape,
+                            tile,
+                            visible: object.visible && layer.visible,
+                            properties: object.properties,
+                        });
+                    }
+                }
+                LayerKind::Image { image, repeat } => {
+                    if repeat {
+                        return Err(TiledError::Unsupported(format!(
+                            "repeating image layer {}",
+                            layer.name
+                        )));
+                    }
+
+                    image_layers.push(ImageLayer {
+                        name: layer.name,
+                        texture: self.texture(&image)?,
+                        position: layer.offset,
+                        layer: index as i32,
+                        visible: layer.visible,
+                        properties: layer.properties,
+                    });
+                }
+            }
+        }
+
+        Ok(TiledMap {
+            tilemap,
+            image_layers,
+            objects,
+            properties: map.properties,
+        })
+    }
+
+    /// Combines every tileset of a map into one, each image tileset and each
+    /// image of a collection becoming an atlas page. Regions are named
+    /// `<tileset name>/<tile id>`.
+    fn tileset(&mut self, map: &MapData) -> Result<(Tileset, Vec<GidRange>), TiledError> {
+        let mut pages = Vec::new();
+        let mut regions = Vec::new();
+        let mut ranges = Vec::new();
+
+        for tileset in &map.tilesets {
+            if tileset.tile_offset != [0, 0] {
+                return Err(TiledError::Unsupported(format!(
+                    "tile offset of tileset {}",
+                    tileset.name
+                )));
+            }
+
+            let mut tiles = HashMap::new();
+            let mut add = |id: u32, page: usize, region: AtlasRegion| {
+                tiles.insert(id, regions.len() as u32);
+                regions.push((
+                    format!("{}/{}", tileset.name, id),
+                    AtlasRegion { page, ..region },
+                ));
+            };
+
+            match &tileset.image {
+                Some(image) => {
+                    let texture = self.texture(image)?;
+                    let grid = TextureAtlas::from_grid(
+                        texture.clone(),
+                        tileset.tile_size,
+                        tileset.margin,
+                        tileset.spacing,
+                    )
+                    .map_err(|_| {
+                        TiledError::Invalid(format!("tileset {} has no tile size", tileset.name))
+                    })?;
+                    for id in 0..grid.len() {
+                        add(id as u32, pages.len(), *grid.region_at(id).unwrap());
+                    }
+                    pages.push(texture);
+                }
+                None => {
+                    for tile in &tileset.tiles {
+                        if let Some(image) = &tile.image {
+                            let texture = self.texture(image)?;
+                            let region = AtlasRegion {
+                                page: 0,
+                                uv: [[0.0, 0.0], [1.0, 1.0]],
+                                size: texture.dimensions(),
+                            };
+                            add(tile.id, pages.len(), region);
+                            pages.push(texture);
+                        }
+                    }
+                }
+            }
+
+            ranges.push(GidRange {
+                first_gid: tileset.first_gid,
+                tiles,
+            });
+        }
+
+        ranges.sort_by_key(|range| range.first_gid);
+        let atlas = TextureAtlas::new(pages, regions);
+        Ok((
+            Tileset::from_atlas(Arc::new(atlas)).with_grid_size(map.tile_size),
+            ranges,
+        ))
+    }
+
+    fn texture(&mut self, image: &ImageData) -> Result<Arc<Texture>, TiledError> {
+        if let Some(color) = &image.transparent {
+            return Err(TiledError::Unsupported(format!(
+                "transparent color {} of {}; give the image an alpha channel",
+                color,
+                image.source.display()
+            )));
+        }
+
+        if let Some(texture) = self.textures.get(&image.source) {
+            return Ok(texture.clone());
+        }
+
+        let texture = self
+            .renderer
+            .load_texture(&image.source.to_string_lossy())?;
+        self.textures.insert(image.source.clone(), texture.clone());
+        Ok(texture)
+    }
+}
+
+/// Position and shape of an object in map space. Objects in isometric maps
+/// are measured in tile heights along the map's axes, so are projected.
+fn object_shape(
+    object: &ObjectData,
+    is_tile: bool,
+    map: &MapData,
+) -> Result<([f32; 2], ObjectShape), TiledError> {
+    if map.orientation != "isometric" {
+        return Ok((object.position, object.shape.clone()));
+    }
+
+    let project = |[x, y]: [f32; 2]| {
+        let [width, height] = map.tile_size.map(|size| size as f32);
+        let [x, y] = [x / height, y / height];
+        [
+            (x - y + map.size[1] as f32) * width / 2.0,
+            (x + y) * height / 2.0,
+        ]
+    };
+    let origin = project(object.position);
+    let relative = |points: &[[f32; 2]]| {
+        points
+            .iter()
+            .map(|[x, y]| {
+                let [px, py] = project([object.position[0] + x, object.position[1] + y]);
+                [px - origin[0], py - origin[1]]
+            })
+            .collect()
+    };
+
+    let shape = match &object.shape {
+        // tile objects stand upright instead of lying on the map
+        ObjectShape::Rectangle { .. } if is_tile => object.shape.clone(),
+        ObjectShape::Rectangle { size } => ObjectShape::Polygon(relative(&[
+            [0.0, 0.0],
+            [size[0], 0.0],
+            [size[0], size[1]],
+            [0.0, size[1]],
+        ])),
+        ObjectShape::Ellipse { .. } => {
+            return Err(TiledError::Unsupported(format!(
+                "ellipse object {} in an isometric map",
+                object.id
+            )))
+        }
+        ObjectShape::Point => ObjectShape::Point,
+        ObjectShape::Polygon(points) => ObjectShape::Polygon(relative(points)),
+        ObjectShape::Polyline(points) => ObjectShape::Polyline(relative(points)),
+    };
+
+    Ok((origin, shape))
+}
+
+/// Looks up a global tile id, with its flip flags. Id 0 is no tile.
+fn tile(ranges: &[GidRange], gid: u32) -> Result<Option<Tile>, TiledError> {
+    if gid & ROTATE_HEX != 0 {
+        return Err(TiledError::Unsupported(
+            "rotated hexagonal tiles".to_string(),
+        ));
+    }
+
+    let id = gid & !(FLIP_X | FLIP_Y | FLIP_DIAGONAL | ROTATE_HEX);
+    if id == 0 {
+        return Ok(None);
+    }
+
+    let index = ranges
+        .iter()
+        .rev()
+        .find(|range| range.first_gid <= id)
+        .and_then(|range| range.tiles.get(&(id - range.first_gid)))
+        .ok_or_else(|| TiledError::Invalid(format!("tile {} is in no tileset", id)))?;
+
+    Ok(Some(Tile {
+        index: *index,
+        flip_x: gid & FLIP_X != 0,
+        flip_y: gid & FLIP_Y != 0,
+        flip_diagonal: gid & FLIP_DIAGONAL != 0,
+    }))
+}
+
+fn tile_count(size: [u32; 2]) -> usize {
+    (size[0] * size[1]) as usize
+}
+
+#[derive(Debug)]
+pub enum TiledError {
+    Io(std::io::Error),
+    Xml(roxmltree::Error),
+    Json(serde_json::Error),
+    Png(png::DecodingError),
+    Invalid(String),
+    /// The map uses a Tiled feature flintlock can not show.
+    Unsupported(String),
+}
+
+impl fmt::Display for TiledError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TiledError::Io(err) => write!(f, "Failed to read Tiled map: {}", err),
+            TiledError::Xml(err) => write!(f, "Invalid Tiled XML: {}", err),
+            TiledError::Json(err) => write!(f, "Invalid Tiled JSON: {}", err),
+            TiledError::Png(err) => write!(f, "Failed to load Tiled image: {}", err),
+            TiledError::Invalid(reason) => write!(f, "Invalid Tiled map: {}", reason),
+            TiledError::Unsupported(feature) => {
+                write!(f, "Unsupported Tiled feature: {}", feature)
+            }
+        }
+    }
+}
+
+impl std::error::Error for TiledError {}
+
+impl From<std::io::Error> for TiledError {
+    fn from(err: std::io::Error) -> TiledError {
+        TiledError::Io(err)
+    }
+}
+
+impl From<roxmltree::Error> for TiledError {
+    fn from(err: roxmltree::Error) -> TiledError {
+        TiledError::Xml(err)
+    }
+}
+
+impl From<serde_json::Error> for TiledError {
+    fn from(err: serde_json::Error) -> TiledError {
+        TiledError::Json(err)
+    }
+}
+
+impl From<png::DecodingError> for TiledError {
+    fn from(err: png::DecodingError) -> TiledError {
+        TiledError::Png(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ranges() -> Vec<GidRange> {
+        // a tileset of three tiles at gid 1 and a collection with tiles 0 and 4
+        // at gid 10, combined into tiles 0 to 4
+        vec![
+            GidRange {
+                first_gid: 1,
+                tiles: HashMap::from([(0, 0), (1, 1), (2, 2)]),
+            },
+            GidRange {
+                first_gid: 10,
+                tiles: HashMap::from([(0, 3), (4, 4)]),
+            },
+        ]
+    }
+
+    #[test]
+    fn looks_up_tiles_by_first_gid() {
+        let ranges = ranges();
+        let index = |gid| tile(&ranges, gid).unwrap().map(|tile| tile.index);
+
+        assert_eq!(index(0), None);
+        assert_eq!(index(1), Some(0));
+        assert_eq!(index(3), Some(2));
+        assert_eq!(index(10), Some(3));
+        assert_eq!(index(14), Some(4));
+
+        // past the first tileset, and a hole in the collection
+        assert!(tile(&ranges, 4).is_err());
+        assert!(tile(&ranges, 12).is_err());
+    }
+
+    #[test]
+    fn reads_flip_bits() {
+        let ranges = ranges();
+
+        let flipped = tile(&ranges, 2 | FLIP_X | FLIP_DIAGONAL).unwrap().unwrap();
+        assert_eq!(flipped.index, 1);
+        assert!(flipped.flip_x && !flipped.flip_y && flipped.flip_diagonal);
+
+        let flipped = tile(&ranges, 14 | FLIP_Y).unwrap().unwrap();
+        assert_eq!(flipped.index, 4);
+        assert!(!flipped.flip_x && flipped.flip_y && !flipped.flip_diagonal);
+
+        // flip bits on no tile are still no tile
+        assert!(tile(&ranges, FLIP_X).unwrap().is_none());
+        assert!(matches!(
+            tile(&ranges, 1 | ROTATE_HEX),
+            Err(TiledError::Unsupported(_))
+        ));
+    }
+
+    fn isometric_objects() -> (MapData, Vec<ObjectData>) {
+        let xml = r#"<map orientation="isometric" width="4" height="4" tilewidth="32" tileheight="16">
+            <objectgroup name="objects">
+                <object id="1" x="16" y="0"><point/></object>
+                <object id="2" x="0" y="0" width="16" height="16"/>
+                <object id="3" x="16" y="16"><polyline points="0,0 16,0"/></object>
+                <object id="4" x="32" y="16" width="32" height="16" gid="1"/>
+                <object id="5" x="0" y="0" width="16" height="16"><ellipse/></object>
+            </objectgroup>
+        </map>"#;
+
+        let mut map = tmx::parse_map(xml, Path::new("")).unwrap();
+        let objects = match map.layers.pop().map(|layer| layer.kind) {
+            Some(LayerKind::Objects(objects)) => objects,
+            _ => panic!("no object layer"),
+        };
+        (map, objects)
+    }
+
+    #[test]
+    fn projects_isometric_objects() {
+        let (map, objects) = isometric_objects();
+        let shape = |index: usize, is_tile| object_shape(&objects[index], is_tile, &map);
+
+        // the map's top corner is 4 half tiles right of the origin, and a
+        // tile height along the map's x axis is half a tile right and down
+        assert_eq!(shape(0, false).unwrap(), ([80.0, 8.0], ObjectShape::Point));
+
+        // rectangles become polygons of their projected corners
+        let (position, rectangle) = shape(1, false).unwrap();
+        assert_eq!(position, [64.0, 0.0]);
+        assert_eq!(
+            rectangle,
+            ObjectShape::Polygon(vec![[0.0, 0.0], [16.0, 8.0], [0.0, 16.0], [-16.0, 8.0]])
+        );
+
+        let (position, polyline) = shape(2, false).unwrap();
+        assert_eq!(position, [64.0, 16.0]);
+        assert_eq!(
+            polyline,
+            ObjectShape::Polyline(vec![[0.0, 0.0], [16.0, 8.0]])
+        );
+
+        // tile objects stand upright at their projected position
+        let (position, upright) = shape(3, true).unwrap();
+        assert_eq!(position, [80.0, 24.0]);
+        assert_eq!(upright, ObjectShape::Rectangle { size: [32.0, 16.0] });
+
+        assert!(matches!(shape(4, false), Err(TiledError::Unsupported(_))));
+    }
+
+    #[test]
+    fn keeps_orthogonal_objects() {
+        let (mut map, objects) = isometric_objects();
+        map.orientation = "orthogonal".to_string();
+
+        let (position, shape) = object_shape(&objects[1], false, &map).unwrap();
+        assert_eq!(position, [0.0, 0.0]);
+        assert_eq!(shape, ObjectShape::Rectangle { size: [16.0, 16.0] });
+    }
+}
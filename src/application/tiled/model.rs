@@ -0,0 +1,264 @@
+//! What the TMX and TMJ readers agree on: a Tiled map as written, before it
+//! is turned into a Tilemap and objects.
+
+use base64::Engine;
+use flate2::read::{GzDecoder, ZlibDecoder};
+
+use std::io::Read;
+use std::path::PathBuf;
+
+use super::{ObjectShape, Properties, TiledError};
+
+pub(crate) struct MapData {
+    pub orientation: String,
+    /// `x` or `y`, for staggered and hexagonal maps.
+    pub stagger_axis: String,
+    /// `odd` or `even`, for staggered and hexagonal maps.
+    pub stagger_index: String,
+    pub hex_side_length: u32,
+    pub infinite: bool,
+    pub size: [u32; 2],
+    pub tile_size: [u32; 2],
+    pub tilesets: Vec<TilesetData>,
+    /// Layers from back to front, with groups flattened.
+    pub layers: Vec<LayerData>,
+    pub properties: Properties,
+}
+
+pub(crate) struct TilesetData {
+    pub first_gid: u32,
+    pub name: String,
+    pub tile_size: [u32; 2],
+    pub margin: u32,
+    pub spacing: u32,
+    pub tile_offset: [i32; 2],
+    /// Image cut into tiles, unless the tileset is a collection of images.
+    pub image: Option<ImageData>,
+    pub tiles: Vec<TileData>,
+}
+
+pub(crate) struct TileData {
+    pub id: u32,
+    pub image: Option<ImageData>,
+}
+
+pub(crate) struct ImageData {
+    /// Path of the image file, resolved against the file referencing it.
+    pub source: PathBuf,
+    pub transparent: Option<String>,
+}
+
+pub(crate) struct LayerData {
+    pub name: String,
+    pub visible: bool,
+    pub offset: [f32; 2],
+    pub parallax: [f32; 2],
+    pub properties: Properties,
+    pub kind: LayerKind,
+}
+
+pub(crate) enum LayerKind {
+    /// Global tile ids with their flip bits, row by row.
+    Tiles {
+        size: [u32; 2],
+        gids: Vec<u32>,
+    },
+    Objects(Vec<ObjectData>),
+    Image {
+        image: ImageData,
+        repeat: bool,
+    },
+}
+
+pub(crate) struct ObjectData {
+    pub id: u32,
+    pub name: String,
+    pub class: String,
+    pub position: [f32; 2],
+    /// Clockwise, in degrees.
+    pub rotation: f32,
+    pub visible: bool,
+    pub gid: Option<u32>,
+    /// Shape as written: rectangles and ellipses are `size` large, and
+    /// polygon points are relative to `position`.
+    pub shape: ObjectShape,
+    pub properties: Properties,
+}
+
+/// Combines a group's offset, parallax and visibility with one of its layers'.
+pub(crate) fn nest(group: &LayerData, mut layer: LayerData) -> LayerData {
+    layer.visible &= group.visible;
+    layer.offset = [
+        group.offset[0] + layer.offset[0],
+        group.offset[1] + layer.offset[1],
+    ];
+    layer.parallax = [
+        group.parallax[0] * layer.parallax[0],
+        group.parallax[1] * layer.parallax[1],
+    ];
+    layer
+}
+
+/// Decodes the tiles of a tile layer written as text: CSV, or base64 of
+/// little endian ids, optionally compressed.
+pub(crate) fn decode_tiles(
+    encoding: &str,
+    compression: Option<&str>,
+    data: &str,
+) -> Result<Vec<u32>, TiledError> {
+    match encoding {
+        "csv" => data
+            .split(',')
+            .map(str::trim)
+            .filter(|gid| !gid.is_empty())
+            .map(|gid| {
+                gid.parse()
+                    .map_err(|_| TiledError::Invalid(format!("tile id {} is not a number", gid)))
+            })
+            .collect(),
+        "base64" => {
+            let bytes = base64::engine::general_purpose::STANDARD
+                .decode(data.trim())
+                .map_err(|err| TiledError::Invalid(format!("bad base64 tile data: {}", err)))?;
+
+            let bytes = match compression.unwrap_or("") {
+                "" => bytes,
+                "zlib" => inflate(ZlibDecoder::new(&bytes[..]))?,
+                "gzip" => inflate(GzDecoder::new(&bytes[..]))?,
+                other => {
+                    return Err(TiledError::Unsupported(format!(
+                        "{} compressed tile data; use zlib, gzip or none",
+                        other
+                    )))
+                }
+            };
+
+            if bytes.len() % 4 != 0 {
+                return Err(TiledError::Invalid(
+                    "tile data is not a whole number of ids".to_string(),
+                ));
+            }
+
+            Ok(bytes
+                .chunks_exact(4)
+                .map(|gid| u32::from_le_bytes([gid[0], gid[1], gid[2], gid[3]]))
+                .collect())
+        }
+        other => Err(TiledError::Unsupported(format!(
+            "{} encoded tile data",
+            other
+        ))),
+    }
+}
+
+fn inflate(mut decoder: impl Read) -> Result<Vec<u8>, TiledError> {
+    let mut bytes = Vec::new();
+    decoder
+        .read_to_end(&mut bytes)
+        .map_err(|err| TiledError::Invalid(format!("bad compressed tile data: {}", err)))?;
+    Ok(bytes)
+}
+
+/// Parses a `#RRGGBB` or `#AARRGGBB` color into RGBA between 0 and 1. Tiled
+/// writes unset colors as an empty string, read as transparent black.
+pub(crate) fn parse_color(color: &str) -> Result<[f32; 4], TiledError> {
+    let hex = color.trim_start_matches('#');
+    if hex.is_empty() {
+        return Ok([0.0; 4]);
+    }
+
+    let value = u32::from_str_radix(hex, 16)
+        .ok()
+        .filter(|_| hex.len() == 6 || hex.len() == 8)
+        .ok_or_else(|| TiledError::Invalid(format!("{} is not a color", color)))?;
+    let channel = |shift: u32| ((value >> shift) & 0xff) as f32 / 255.0;
+
+    Ok(match hex.len() {
+        6 => [channel(16), channel(8), channel(0), 1.0],
+        _ => [channel(16), channel(8), channel(0), channel(24)],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use flate2::write::{GzEncoder, ZlibEncoder};
+    use flate2::Compression;
+
+    use std::io::Write;
+
+    use super::*;
+
+    const GIDS: [u32; 4] = [1, 0, 0x8000_0002, 70000];
+
+    fn encode(bytes: &[u8]) -> String {
+        base64::engine::general_purpose::STANDARD.encode(bytes)
+    }
+
+    fn gid_bytes() -> Vec<u8> {
+        GIDS.iter().flat_map(|gid| gid.to_le_bytes()).collect()
+    }
+
+    #[test]
+    fn decodes_csv() {
+        let data = "\n1,0,\n2147483650,70000\n";
+        assert_eq!(decode_tiles("csv", None, data).unwrap(), GIDS);
+    }
+
+    #[test]
+    fn decodes_base64() {
+        let data = encode(&gid_bytes());
+        assert_eq!(decode_tiles("base64", None, &data).unwrap(), GIDS);
+        assert_eq!(decode_tiles("base64", Some(""), &data).unwrap(), GIDS);
+    }
+
+    #[test]
+    fn decodes_zlib() {
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&gid_bytes()).unwrap();
+        let data = encode(&encoder.finish().unwrap());
+
+        assert_eq!(decode_tiles("base64", Some("zlib"), &data).unwrap(), GIDS);
+    }
+
+    #[test]
+    fn decodes_gzip() {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&gid_bytes()).unwrap();
+        let data = encode(&encoder.finish().unwrap());
+
+        assert_eq!(decode_tiles("base64", Some("gzip"), &data).unwrap(), GIDS);
+    }
+
+    #[test]
+    fn rejects_bad_tile_data() {
+        assert!(matches!(
+            decode_tiles("csv", None, "1,x"),
+            Err(TiledError::Invalid(_))
+        ));
+        assert!(matches!(
+            decode_tiles("base64", None, &encode(&[1, 0, 0])),
+            Err(TiledError::Invalid(_))
+        ));
+        assert!(matches!(
+            decode_tiles("base64", Some("zstd"), ""),
+            Err(TiledError::Unsupported(_))
+        ));
+        assert!(matches!(
+            decode_tiles("xml", None, ""),
+            Err(TiledError::Unsupported(_))
+        ));
+    }
+
+    #[test]
+    fn parses_colors() {
+        assert_eq!(parse_color("#ff0000").unwrap(), [1.0, 0.0, 0.0, 1.0]);
+        assert_eq!(
+            parse_color("#8000ff00").unwrap(),
+            [0.0, 1.0, 0.0, 128.0 / 255.0]
+        );
+        assert_eq!(parse_color("0000ff").unwrap(), [0.0, 0.0, 1.0, 1.0]);
+        assert_eq!(parse_color("").unwrap(), [0.0; 4]);
+        assert!(parse_color("#fff").is_err());
+        assert!(parse_color("#gggggg").is_err());
+    }
+}
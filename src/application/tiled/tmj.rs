@@ -0,0 +1,569 @@
+//! Reads Tiled's JSON formats: maps (.tmj) and tilesets (.tsj).
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use std::path::Path;
+
+use super::model::{
+    decode_tiles, nest, parse_color, ImageData, LayerData, LayerKind, MapData, ObjectData,
+    TileData, TilesetData,
+};
+use super::{load_tileset, ObjectShape, Properties, Property, TiledError};
+
+#[derive(Deserialize)]
+struct MapJson {
+    #[serde(default = "orthogonal")]
+    orientation: String,
+    #[serde(default = "y")]
+    staggeraxis: String,
+    #[serde(default = "odd")]
+    staggerindex: String,
+    #[serde(default)]
+    hexsidelength: u32,
+    #[serde(default)]
+    infinite: bool,
+    width: u32,
+    height: u32,
+    tilewidth: u32,
+    tileheight: u32,
+    #[serde(default)]
+    tilesets: Vec<TilesetJson>,
+    #[serde(default)]
+    layers: Vec<LayerJson>,
+    #[serde(default)]
+    properties: Vec<PropertyJson>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct TilesetJson {
+    firstgid: u32,
+    source: Option<String>,
+    name: String,
+    tilewidth: u32,
+    tileheight: u32,
+    margin: u32,
+    spacing: u32,
+    image: Option<String>,
+    transparentcolor: Option<String>,
+    tileoffset: Option<OffsetJson>,
+    tiles: Vec<TileJson>,
+}
+
+#[derive(Deserialize)]
+struct OffsetJson {
+    x: i32,
+    y: i32,
+}
+
+#[derive(Deserialize)]
+struct TileJson {
+    id: u32,
+    image: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct LayerJson {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    name: String,
+    #[serde(default = "visible")]
+    visible: bool,
+    #[serde(default)]
+    offsetx: f32,
+    #[serde(default)]
+    offsety: f32,
+    #[serde(default = "one")]
+    parallaxx: f32,
+    #[serde(default = "one")]
+    parallaxy: f32,
+    #[serde(default)]
+    properties: Vec<PropertyJson>,
+    // tile layers
+    #[serde(default)]
+    width: u32,
+    #[serde(default)]
+    height: u32,
+    data: Option<DataJson>,
+    encoding: Option<String>,
+    compression: Option<String>,
+    chunks: Option<Value>,
+    // object layers
+    #[serde(default)]
+    objects: Vec<ObjectJson>,
+    // image layers
+    image: Option<String>,
+    #[serde(default)]
+    repeatx: bool,
+    #[serde(default)]
+    repeaty: bool,
+    // groups
+    #[serde(default)]
+    layers: Vec<LayerJson>,
+}
+
+/// Tile ids as an array, or as base64 text.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum DataJson {
+    Gids(Vec<u32>),
+    Encoded(String),
+}
+
+#[derive(Deserialize)]
+struct ObjectJson {
+    #[serde(default)]
+    id: u32,
+    #[serde(default)]
+    name: String,
+    // Tiled 1.9 renamed the object type to class
+    #[serde(default, alias = "type")]
+    class: String,
+    #[serde(default)]
+    x: f32,
+    #[serde(default)]
+    y: f32,
+    #[serde(default)]
+    width: f32,
+    #[serde(default)]
+    height: f32,
+    #[serde(default)]
+    rotation: f32,
+    #[serde(default = "visible")]
+    visible: bool,
+    gid: Option<u32>,
+    #[serde(default)]
+    point: bool,
+    #[serde(default)]
+    ellipse: bool,
+    polygon: Option<Vec<PointJson>>,
+    polyline: Option<Vec<PointJson>>,
+    text: Option<Value>,
+    template: Option<String>,
+    #[serde(default)]
+    properties: Vec<PropertyJson>,
+}
+
+#[derive(Deserialize)]
+struct PointJson {
+    x: f32,
+    y: f32,
+}
+
+#[derive(Deserialize)]
+struct PropertyJson {
+    name: String,
+    #[serde(default = "string", rename = "type")]
+    kind: String,
+    value: Value,
+}
+
+fn orthogonal() -> String {
+    "orthogonal".to_string()
+}
+
+fn y() -> String {
+    "y".to_string()
+}
+
+fn odd() -> String {
+    "odd".to_string()
+}
+
+fn string() -> String {
+    "string".to_string()
+}
+
+fn visible() -> bool {
+    true
+}
+
+fn one() -> f32 {
+    1.0
+}
+
+/// Reads a map. `dir` is the directory of the map file, which paths in it are
+/// relative to.
+pub(crate) fn parse_map(json: &str, dir: &Path) -> Result<MapData, TiledError> {
+    let map: MapJson = serde_json::from_str(json)?;
+
+    let tilesets = map
+        .tilesets
+        .into_iter()
+        .map(|tileset| match &tileset.source {
+            Some(source) => load_tileset(&dir.join(source), tileset.firstgid),
+            None => Ok(convert_tileset(tileset, dir)),
+        })
+        .collect::<Result<_, _>>()?;
+
+    Ok(MapData {
+        orientation: map.orientation,
+        stagger_axis: map.staggeraxis,
+        stagger_index: map.staggerindex,
+        hex_side_length: map.hexsidelength,
+        infinite: map.infinite,
+        size: [map.width, map.height],
+        tile_size: [map.tilewidth, map.tileheight],
+        tilesets,
+        layers: convert_layers(map.layers, dir)?,
+        properties: convert_properties(map.properties)?,
+    })
+}
+
+/// Reads an external tileset file.
+pub(crate) fn parse_tileset(
+    json: &str,
+    first_gid: u32,
+    dir: &Path,
+) -> Result<TilesetData, TiledError> {
+    let tileset: TilesetJson = serde_json::from_str(json)?;
+    Ok(TilesetData {
+        first_gid,
+        ..convert_tileset(tileset, dir)
+    })
+}
+
+fn convert_tileset(tileset: TilesetJson, dir: &Path) -> TilesetData {
+    let image = |source: String, transparent: Option<String>| ImageData {
+        source: dir.join(source),
+        transparent,
+    };
+
+    TilesetData {
+        first_gid: tileset.firstgid,
+        name: tileset.name,
+        tile_size: [tileset.tilewidth, tileset.tileheight],
+        margin: tileset.margin,
+        spacing: tileset.spacing,
+        tile_offset: tileset
+            .tileoffset
+            .map_or([0, 0], |offset| [offset.x, offset.y]),
+        image: tileset
+            .image
+            .map(|source| image(source, tileset.transparentcolor)),
+        tiles: tileset
+            .tiles
+            .into_iter()
+            .map(|tile| TileData {
+                id: tile.id,
+                image: tile.image.map(|source| image(source, None)),
+            })
+            .collect(),
+    }
+}
+
+fn convert_layers(layers: Vec<LayerJson>, dir: &Path) -> Result<Vec<LayerData>, TiledError> {
+    let mut converted = Vec::new();
+
+    for mut layer in layers {
+        let kind = match layer.kind.as_str() {
+            "tilelayer" => {
+                if layer.chunks.is_some() {
+                    return Err(TiledError::Unsupported("infinite maps".to_string()));
+                }
+
+                let gids = match layer.data.take() {
+                    Some(DataJson::Gids(gids)) => gids,
+                    Some(DataJson::Encoded(data)) => decode_tiles(
+                        layer.encoding.as_deref().unwrap_or("base64"),
+                        layer.compression.as_deref(),
+                        &data,
+                    )?,
+                    None => Vec::new(),
+                };
+
+                LayerKind::Tiles {
+                    size: [layer.width, layer.height],
+                    gids,
+                }
+            }
+            "objectgroup" => LayerKind::Objects(
+                std::mem::take(&mut layer.objects)
+                    .into_iter()
+                    .map(convert_object)
+                    .collect::<Result<_, _>>()?,
+            ),
+            "imagelayer" => match layer.image.take().filter(|image| !image.is_empty()) {
+                Some(image) => LayerKind::Image {
+                    image: ImageData {
+                        source: dir.join(image),
+                        transparent: None,
+                    },
+                    repeat: layer.repeatx || layer.repeaty,
+                },
+                // an image layer without an image shows nothing
+                None => continue,
+            },
+            "group" => {
+                let children = std::mem::take(&mut layer.layers);
+                let group = convert_layer(layer, LayerKind::Objects(Vec::new()))?;
+                for child in convert_layers(children, dir)? {
+                    converted.push(nest(&group, child));
+                }
+                continue;
+            }
+            other => return Err(TiledError::Invalid(format!("unknown layer type {}", other))),
+        };
+
+        converted.push(convert_layer(layer, kind)?);
+    }
+
+    Ok(converted)
+}
+
+fn convert_layer(layer: LayerJson, kind: LayerKind) -> Result<LayerData, TiledError> {
+    Ok(LayerData {
+        name: layer.name,
+        visible: layer.visible,
+        offset: [layer.offsetx, layer.offsety],
+        parallax: [layer.parallaxx, layer.parallaxy],
+        properties: convert_properties(layer.properties)?,
+        kind,
+    })
+}
+
+fn convert_object(object: ObjectJson) -> Result<ObjectData, TiledError> {
+    if object.template.is_some() {
+        return Err(TiledError::Unsupported(
+            "object templates; detach the objects from their template".to_string(),
+        ));
+    }
+    if object.text.is_some() {
+        return Err(TiledError::Unsupported("text objects".to_string()));
+    }
+
+    let size = [object.width, object.height];
+    let points = |points: Vec<PointJson>| points.into_iter().map(|p| [p.x, p.y]).collect();
+    let shape = if object.point {
+        ObjectShape::Point
+    } else if object.ellipse {
+        ObjectShape::Ellipse { size }
+    } else if let Some(polygon) = object.polygon {
+        ObjectShape::Polygon(points(polygon))
+    } else if let Some(polyline) = object.polyline {
+        ObjectShape::Polyline(points(polyline))
+    } else {
+        ObjectShape::Rectangle { size }
+    };
+
+    Ok(ObjectData {
+        id: object.id,
+        name: object.name,
+        class: object.class,
+        position: [object.x, object.y],
+        rotation: object.rotation,
+        visible: object.visible,
+        gid: object.gid,
+        shape,
+        properties: convert_properties(object.properties)?,
+    })
+}
+
+fn convert_properties(properties: Vec<PropertyJson>) -> Result<Properties, TiledError> {
+    properties
+        .into_iter()
+        .map(|property| {
+            let value = match property.kind.as_str() {
+                "color" => Property::Color(parse_color(property.value.as_str().unwrap_or(""))?),
+                "float" => Property::Float(property.value.as_f64().unwrap_or(0.0)),
+                "file" => Property::File(property.value.as_str().unwrap_or("").to_string()),
+                "object" => Property::Object(property.value.as_u64().unwrap_or(0) as u32),
+                _ => convert_value(property.value),
+            };
+            Ok((property.name, value))
+        })
+        .collect()
+}
+
+/// Converts a JSON value by its own type, as the members of class properties
+/// are written without theirs.
+fn convert_value(value: Value) -> Property {
+    match value {
+        Value::Bool(value) => Property::Bool(value),
+        Value::Number(number) => match number.as_i64() {
+            Some(value) => Property::Int(value),
+            None => Property::Float(number.as_f64().unwrap_or(0.0)),
+        },
+        Value::Object(members) => Property::Class(
+            members
+                .into_iter()
+                .map(|(name, value)| (name, convert_value(value)))
+                .collect(),
+        ),
+        Value::String(value) => Property::String(value),
+        other => Property::String(other.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn map(fields: &str) -> MapData {
+        let json = format!(
+            r#"{{
+                "orientation": "orthogonal",
+                "width": 2,
+                "height": 2,
+                "tilewidth": 16,
+                "tileheight": 8,
+                {}
+            }}"#,
+            fields
+        );
+        parse_map(&json, Path::new("")).unwrap()
+    }
+
+    fn objects(layer: &LayerData) -> &[ObjectData] {
+        match &layer.kind {
+            LayerKind::Objects(objects) => objects,
+            _ => panic!("{} is not an object layer", layer.name),
+        }
+    }
+
+    #[test]
+    fn reads_tile_layers() {
+        let map = map(r#"
+            "tilesets": [{
+                "firstgid": 5, "name": "ground", "tilewidth": 16, "tileheight": 8,
+                "image": "ground.png"
+            }],
+            "layers": [
+                { "type": "tilelayer", "name": "array", "width": 2, "height": 2,
+                  "data": [5, 6, 0, 1073741829] },
+                { "type": "tilelayer", "name": "base64", "width": 2, "height": 2,
+                  "encoding": "base64", "data": "BQAAAAAAAAAAAAAABgAAAA==" }
+            ]"#);
+
+        assert_eq!(map.tilesets[0].first_gid, 5);
+        assert_eq!(map.tilesets[0].name, "ground");
+
+        let gids = map
+            .layers
+            .iter()
+            .map(|layer| match &layer.kind {
+                LayerKind::Tiles { gids, .. } => gids.clone(),
+                _ => panic!("{} is not a tile layer", layer.name),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(gids, [vec![5, 6, 0, 0x4000_0005], vec![5, 0, 0, 6]]);
+    }
+
+    #[test]
+    fn flattens_groups_with_their_offsets() {
+        let map = map(r#"
+            "layers": [{
+                "type": "group", "name": "outer", "offsetx": 10, "offsety": 5,
+                "parallaxy": 0.5,
+                "layers": [
+                    { "type": "group", "name": "inner", "offsetx": 1, "visible": false,
+                      "layers": [
+                          { "type": "objectgroup", "name": "things", "offsetx": 2, "offsety": 3 }
+                      ] },
+                    { "type": "objectgroup", "name": "shown" }
+                ]
+            }]"#);
+
+        let names = map.layers.iter().map(|layer| layer.name.as_str());
+        assert_eq!(names.collect::<Vec<_>>(), ["things", "shown"]);
+
+        assert_eq!(map.layers[0].offset, [13.0, 8.0]);
+        assert_eq!(map.layers[0].parallax, [1.0, 0.5]);
+        assert!(!map.layers[0].visible);
+
+        assert_eq!(map.layers[1].offset, [10.0, 5.0]);
+        assert!(map.layers[1].visible);
+    }
+
+    #[test]
+    fn reads_class_or_type() {
+        let map = map(r#"
+            "layers": [{
+                "type": "objectgroup", "name": "objects",
+                "objects": [
+                    { "id": 1, "class": "light", "x": 4, "y": 2 },
+                    { "id": 2, "type": "door" }
+                ]
+            }]"#);
+
+        let classes = objects(&map.layers[0])
+            .iter()
+            .map(|object| object.class.as_str());
+        assert_eq!(classes.collect::<Vec<_>>(), ["light", "door"]);
+    }
+
+    #[test]
+    fn reads_object_shapes() {
+        let map = map(r#"
+            "layers": [{
+                "type": "objectgroup", "name": "objects",
+                "objects": [
+                    { "id": 1, "x": 1, "y": 2, "width": 3, "height": 4 },
+                    { "id": 2, "width": 3, "height": 4, "ellipse": true },
+                    { "id": 3, "point": true },
+                    { "id": 4, "polyline": [{ "x": 0, "y": 0 }, { "x": 4, "y": 2 }] },
+                    { "id": 5, "gid": 2147483650, "rotation": 90 }
+                ]
+            }]"#);
+
+        let objects = objects(&map.layers[0]);
+        assert_eq!(objects[0].position, [1.0, 2.0]);
+        assert_eq!(
+            objects[0].shape,
+            ObjectShape::Rectangle { size: [3.0, 4.0] }
+        );
+        assert_eq!(objects[1].shape, ObjectShape::Ellipse { size: [3.0, 4.0] });
+        assert_eq!(objects[2].shape, ObjectShape::Point);
+        assert_eq!(
+            objects[3].shape,
+            ObjectShape::Polyline(vec![[0.0, 0.0], [4.0, 2.0]])
+        );
+        assert_eq!(objects[4].gid, Some(0x8000_0002));
+        assert_eq!(objects[4].rotation, 90.0);
+    }
+
+    #[test]
+    fn reads_properties() {
+        let map = map(r##"
+            "properties": [
+                { "name": "name", "value": "cave" },
+                { "name": "dark", "type": "bool", "value": true },
+                { "name": "depth", "type": "int", "value": -3 },
+                { "name": "scale", "type": "float", "value": 2 },
+                { "name": "tint", "type": "color", "value": "#ff00ff00" },
+                { "name": "target", "type": "object", "value": 7 },
+                { "name": "spawn", "type": "class", "propertytype": "Spawn",
+                  "value": { "count": 2, "kind": "bat", "odds": 0.5 } }
+            ]"##);
+
+        let properties = &map.properties;
+        assert_eq!(properties["name"], Property::String("cave".to_string()));
+        assert_eq!(properties["dark"], Property::Bool(true));
+        assert_eq!(properties["depth"], Property::Int(-3));
+        assert_eq!(properties["scale"], Property::Float(2.0));
+        assert_eq!(properties["tint"], Property::Color([0.0, 1.0, 0.0, 1.0]));
+        assert_eq!(properties["target"], Property::Object(7));
+
+        let spawn = Properties::from([
+            ("count".to_string(), Property::Int(2)),
+            ("kind".to_string(), Property::String("bat".to_string())),
+            ("odds".to_string(), Property::Float(0.5)),
+        ]);
+        assert_eq!(properties["spawn"], Property::Class(spawn));
+    }
+
+    #[test]
+    fn refuses_infinite_layers() {
+        let json = r#"{
+            "width": 2, "height": 2, "tilewidth": 8, "tileheight": 8,
+            "layers": [{ "type": "tilelayer", "chunks": [] }]
+        }"#;
+
+        assert!(matches!(
+            parse_map(json, Path::new("")),
+            Err(TiledError::Unsupported(_))
+        ));
+    }
+}
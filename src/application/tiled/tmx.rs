@@ -0,0 +1,463 @@
+//! Reads Tiled's XML formats: maps (.tmx) and tilesets (.tsx).
+
+use roxmltree::{Document, Node};
+
+use std::path::Path;
+use std::str::FromStr;
+
+use super::model::{
+    decode_tiles, nest, parse_color, ImageData, LayerData, LayerKind, MapData, ObjectData,
+    TileData, TilesetData,
+};
+use super::{load_tileset, ObjectShape, Properties, Property, TiledError};
+
+/// Reads a map. `dir` is the directory of the map file, which paths in it are
+/// relative to.
+pub(crate) fn parse_map(xml: &str, dir: &Path) -> Result<MapData, TiledError> {
+    let document = Document::parse(xml)?;
+    let map = document.root_element();
+    if !map.has_tag_name("map") {
+        return Err(TiledError::Invalid("no map element".to_string()));
+    }
+
+    let mut tilesets = Vec::new();
+    for tileset in children(map, "tileset") {
+        let first_gid = required(tileset, "firstgid")?;
+        tilesets.push(match tileset.attribute("source") {
+            Some(source) => load_tileset(&dir.join(source), first_gid)?,
+            None => parse_tileset_node(tileset, first_gid, dir)?,
+        });
+    }
+
+    Ok(MapData {
+        orientation: map
+            .attribute("orientation")
+            .unwrap_or("orthogonal")
+            .to_string(),
+        stagger_axis: map.attribute("staggeraxis").unwrap_or("y").to_string(),
+        stagger_index: map.attribute("staggerindex").unwrap_or("odd").to_string(),
+        hex_side_length: attribute(map, "hexsidelength", 0)?,
+        infinite: attribute(map, "infinite", 0u32)? != 0,
+        size: [required(map, "width")?, required(map, "height")?],
+        tile_size: [required(map, "tilewidth")?, required(map, "tileheight")?],
+        tilesets,
+        layers: parse_layers(map, dir)?,
+        properties: parse_properties(map)?,
+    })
+}
+
+/// Reads an external tileset file.
+pub(crate) fn parse_tileset(
+    xml: &str,
+    first_gid: u32,
+    dir: &Path,
+) -> Result<TilesetData, TiledError> {
+    let document = Document::parse(xml)?;
+    parse_tileset_node(document.root_element(), first_gid, dir)
+}
+
+fn parse_tileset_node(
+    tileset: Node,
+    first_gid: u32,
+    dir: &Path,
+) -> Result<TilesetData, TiledError> {
+    let tile_offset = match child(tileset, "tileoffset") {
+        Some(offset) => [attribute(offset, "x", 0)?, attribute(offset, "y", 0)?],
+        None => [0, 0],
+    };
+
+    let tiles = children(tileset, "tile")
+        .map(|tile| {
+            Ok(TileData {
+                id: required(tile, "id")?,
+                image: child(tile, "image").map(|image| parse_image(image, dir)),
+            })
+        })
+        .collect::<Result<Vec<_>, TiledError>>()?;
+
+    Ok(TilesetData {
+        first_gid,
+        name: tileset.attribute("name").unwrap_or_default().to_string(),
+        tile_size: [
+            attribute(tileset, "tilewidth", 0)?,
+            attribute(tileset, "tileheight", 0)?,
+        ],
+        margin: attribute(tileset, "margin", 0)?,
+        spacing: attribute(tileset, "spacing", 0)?,
+        tile_offset,
+        image: child(tileset, "image").map(|image| parse_image(image, dir)),
+        tiles,
+    })
+}
+
+/// Reads the layers of a map or group, flattening nested groups.
+fn parse_layers(parent: Node, dir: &Path) -> Result<Vec<LayerData>, TiledError> {
+    let mut layers = Vec::new();
+
+    for node in parent.children().filter(Node::is_element) {
+        let kind = match node.tag_name().name() {
+            "layer" => parse_tile_layer(node)?,
+            "objectgroup" => LayerKind::Objects(
+                children(node, "object")
+                    .map(parse_object)
+                    .collect::<Result<_, _>>()?,
+            ),
+            "imagelayer" => match child(node, "image") {
+                Some(image) => LayerKind::Image {
+                    image: parse_image(image, dir),
+                    repeat: attribute(node, "repeatx", 0u32)? != 0
+                        || attribute(node, "repeaty", 0u32)? != 0,
+                },
+                // an image layer without an image shows nothing
+                None => continue,
+            },
+            "group" => {
+                let group = parse_layer(node, LayerKind::Objects(Vec::new()))?;
+                for layer in parse_layers(node, dir)? {
+                    layers.push(nest(&group, layer));
+                }
+                continue;
+            }
+            _ => continue,
+        };
+
+        layers.push(parse_layer(node, kind)?);
+    }
+
+    Ok(layers)
+}
+
+fn parse_layer(node: Node, kind: LayerKind) -> Result<LayerData, TiledError> {
+    Ok(LayerData {
+        name: node.attribute("name").unwrap_or_default().to_string(),
+        visible: attribute(node, "visible", 1u32)? != 0,
+        offset: [
+            attribute(node, "offsetx", 0.0)?,
+            attribute(node, "offsety", 0.0)?,
+        ],
+        parallax: [
+            attribute(node, "parallaxx", 1.0)?,
+            attribute(node, "parallaxy", 1.0)?,
+        ],
+        properties: parse_properties(node)?,
+        kind,
+    })
+}
+
+fn parse_tile_layer(layer: Node) -> Result<LayerKind, TiledError> {
+    let size = [required(layer, "width")?, required(layer, "height")?];
+    let data = match child(layer, "data") {
+        Some(data) => data,
+        None => {
+            return Ok(LayerKind::Tiles {
+                size,
+                gids: Vec::new(),
+            })
+        }
+    };
+
+    if child(data, "chunk").is_some() {
+        return Err(TiledError::Unsupported("infinite maps".to_string()));
+    }
+
+    let gids = match data.attribute("encoding") {
+        Some(encoding) => decode_tiles(
+            encoding,
+            data.attribute("compression"),
+            data.text().unwrap_or_default(),
+        )?,
+        None => children(data, "tile")
+            .map(|tile| attribute(tile, "gid", 0))
+            .collect::<Result<_, _>>()?,
+    };
+
+    Ok(LayerKind::Tiles { size, gids })
+}
+
+fn parse_object(object: Node) -> Result<ObjectData, TiledError> {
+    if object.attribute("template").is_some() {
+        return Err(TiledError::Unsupported(
+            "object templates; detach the objects from their template".to_string(),
+        ));
+    }
+    if child(object, "text").is_some() {
+        return Err(TiledError::Unsupported("text objects".to_string()));
+    }
+
+    let size = [
+        attribute(object, "width", 0.0)?,
+        attribute(object, "height", 0.0)?,
+    ];
+    let shape = if child(object, "point").is_some() {
+        ObjectShape::Point
+    } else if child(object, "ellipse").is_some() {
+        ObjectShape::Ellipse { size }
+    } else if let Some(polygon) = child(object, "polygon") {
+        ObjectShape::Polygon(parse_points(polygon)?)
+    } else if let Some(polyline) = child(object, "polyline") {
+        ObjectShape::Polyline(parse_points(polyline)?)
+    } else {
+        ObjectShape::Rectangle { size }
+    };
+
+    Ok(ObjectData {
+        id: attribute(object, "id", 0)?,
+        name: object.attribute("name").unwrap_or_default().to_string(),
+        // Tiled 1.9 renamed the object type to class
+        class: object
+            .attribute("class")
+            .or_else(|| object.attribute("type"))
+            .unwrap_or_default()
+            .to_string(),
+        position: [attribute(object, "x", 0.0)?, attribute(object, "y", 0.0)?],
+        rotation: attribute(object, "rotation", 0.0)?,
+        visible: attribute(object, "visible", 1u32)? != 0,
+        gid: object.attribute("gid").map(parse).transpose()?,
+        shape,
+        properties: parse_properties(object)?,
+    })
+}
+
+fn parse_points(node: Node) -> Result<Vec<[f32; 2]>, TiledError> {
+    node.attribute("points")
+        .unwrap_or_default()
+        .split_whitespace()
+        .map(|point| match point.split_once(',') {
+            Some((x, y)) => Ok([parse(x)?, parse(y)?]),
+            None => Err(TiledError::Invalid(format!("{} is not a point", point))),
+        })
+        .collect()
+}
+
+fn parse_image(image: Node, dir: &Path) -> ImageData {
+    ImageData {
+        source: dir.join(image.attribute("source").unwrap_or_default()),
+        transparent: image.attribute("trans").map(str::to_string),
+    }
+}
+
+fn parse_properties(node: Node) -> Result<Properties, TiledError> {
+    let mut properties = Properties::new();
+
+    for property in child(node, "properties")
+        .into_iter()
+        .flat_map(|properties| children(properties, "property"))
+    {
+        let name = property.attribute("name").unwrap_or_default().to_string();
+        // multiline strings are written as text instead of a value
+        let value = property
+            .attribute("value")
+            .or_else(|| property.text())
+            .unwrap_or_default();
+
+        let value = match property.attribute("type").unwrap_or("string") {
+            "bool" => Property::Bool(value == "true"),
+            "int" => Property::Int(parse(value)?),
+            "float" => Property::Float(parse(value)?),
+            "color" => Property::Color(parse_color(value)?),
+            "file" => Property::File(value.to_string()),
+            "object" => Property::Object(parse(value)?),
+            "class" => Property::Class(parse_properties(property)?),
+            _ => Property::String(value.to_string()),
+        };
+        properties.insert(name, value);
+    }
+
+    Ok(properties)
+}
+
+fn child<'a, 'input>(node: Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>> {
+    node.children().find(|child| child.has_tag_name(name))
+}
+
+fn children<'a, 'input: 'a>(
+    node: Node<'a, 'input>,
+    name: &'a str,
+) -> impl Iterator<Item = Node<'a, 'input>> + 'a {
+    node.children()
+        .filter(move |child| child.has_tag_name(name))
+}
+
+fn attribute<T: FromStr>(node: Node, name: &str, default: T) -> Result<T, TiledError> {
+    node.attribute(name).map_or(Ok(default), parse)
+}
+
+fn required<T: FromStr>(node: Node, name: &str) -> Result<T, TiledError> {
+    match node.attribute(name) {
+        Some(value) => parse(value),
+        None => Err(TiledError::Invalid(format!(
+            "{} has no {} attribute",
+            node.tag_name().name(),
+            name
+        ))),
+    }
+}
+
+fn parse<T: FromStr>(value: &str) -> Result<T, TiledError> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| TiledError::Invalid(format!("unexpected value {}", value)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn map(body: &str) -> MapData {
+        let xml = format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+            <map orientation="orthogonal" width="2" height="2" tilewidth="16" tileheight="8">
+                {}
+            </map>"#,
+            body
+        );
+        parse_map(&xml, Path::new("")).unwrap()
+    }
+
+    fn objects(layer: &LayerData) -> &[ObjectData] {
+        match &layer.kind {
+            LayerKind::Objects(objects) => objects,
+            _ => panic!("{} is not an object layer", layer.name),
+        }
+    }
+
+    #[test]
+    fn reads_tile_layers() {
+        let map = map(r#"
+            <tileset firstgid="1" name="ground" tilewidth="16" tileheight="8">
+                <image source="ground.png" width="64" height="64"/>
+            </tileset>
+            <layer name="csv" width="2" height="2">
+                <data encoding="csv">1,2,0,2147483649</data>
+            </layer>
+            <layer name="xml" width="2" height="2">
+                <data><tile gid="3"/><tile/><tile/><tile gid="1"/></data>
+            </layer>"#);
+
+        assert_eq!(map.size, [2, 2]);
+        assert_eq!(map.tile_size, [16, 8]);
+        assert_eq!(map.tilesets[0].first_gid, 1);
+        assert_eq!(map.tilesets[0].tile_size, [16, 8]);
+
+        let gids = map
+            .layers
+            .iter()
+            .map(|layer| match &layer.kind {
+                LayerKind::Tiles { gids, .. } => gids.clone(),
+                _ => panic!("{} is not a tile layer", layer.name),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(gids, [vec![1, 2, 0, 0x8000_0001], vec![3, 0, 0, 1]]);
+    }
+
+    #[test]
+    fn flattens_groups_with_their_offsets() {
+        let map = map(r#"
+            <group name="outer" offsetx="10" offsety="5" parallaxx="0.5">
+                <group name="inner" offsetx="1" visible="0">
+                    <objectgroup name="things" offsetx="2" offsety="3"/>
+                </group>
+                <objectgroup name="shown"/>
+            </group>"#);
+
+        let names = map.layers.iter().map(|layer| layer.name.as_str());
+        assert_eq!(names.collect::<Vec<_>>(), ["things", "shown"]);
+
+        assert_eq!(map.layers[0].offset, [13.0, 8.0]);
+        assert_eq!(map.layers[0].parallax, [0.5, 1.0]);
+        assert!(!map.layers[0].visible);
+
+        assert_eq!(map.layers[1].offset, [10.0, 5.0]);
+        assert!(map.layers[1].visible);
+    }
+
+    #[test]
+    fn reads_class_or_type() {
+        let map = map(r#"
+            <objectgroup name="objects">
+                <object id="1" class="light" x="4" y="2"/>
+                <object id="2" type="door" x="0" y="0"/>
+                <object id="3" class="chest" type="door" x="0" y="0"/>
+            </objectgroup>"#);
+
+        let classes = objects(&map.layers[0])
+            .iter()
+            .map(|object| object.class.as_str());
+        assert_eq!(classes.collect::<Vec<_>>(), ["light", "door", "chest"]);
+    }
+
+    #[test]
+    fn reads_object_shapes() {
+        let map = map(r#"
+            <objectgroup name="objects">
+                <object id="1" x="1" y="2" width="3" height="4"/>
+                <object id="2" x="0" y="0" width="3" height="4"><ellipse/></object>
+                <object id="3" x="0" y="0"><point/></object>
+                <object id="4" x="0" y="0"><polygon points="0,0 4,0 4,2"/></object>
+                <object id="5" x="0" y="0" gid="2147483650" rotation="90"/>
+            </objectgroup>"#);
+
+        let objects = objects(&map.layers[0]);
+        assert_eq!(objects[0].position, [1.0, 2.0]);
+        assert_eq!(
+            objects[0].shape,
+            ObjectShape::Rectangle { size: [3.0, 4.0] }
+        );
+        assert_eq!(objects[1].shape, ObjectShape::Ellipse { size: [3.0, 4.0] });
+        assert_eq!(objects[2].shape, ObjectShape::Point);
+        assert_eq!(
+            objects[3].shape,
+            ObjectShape::Polygon(vec![[0.0, 0.0], [4.0, 0.0], [4.0, 2.0]])
+        );
+        assert_eq!(objects[4].gid, Some(0x8000_0002));
+        assert_eq!(objects[4].rotation, 90.0);
+    }
+
+    #[test]
+    fn reads_properties() {
+        let map = map(r##"
+            <properties>
+                <property name="name" value="cave"/>
+                <property name="dark" type="bool" value="true"/>
+                <property name="depth" type="int" value="-3"/>
+                <property name="tint" type="color" value="#ff00ff00"/>
+                <property name="notes">first line
+second line</property>
+                <property name="spawn" type="class" propertytype="Spawn">
+                    <properties>
+                        <property name="count" type="int" value="2"/>
+                        <property name="kind" value="bat"/>
+                    </properties>
+                </property>
+            </properties>"##);
+
+        let properties = &map.properties;
+        assert_eq!(properties["name"], Property::String("cave".to_string()));
+        assert_eq!(properties["dark"], Property::Bool(true));
+        assert_eq!(properties["depth"], Property::Int(-3));
+        assert_eq!(properties["tint"], Property::Color([0.0, 1.0, 0.0, 1.0]));
+        assert_eq!(
+            properties["notes"],
+            Property::String("first line\nsecond line".to_string())
+        );
+
+        let spawn = Properties::from([
+            ("count".to_string(), Property::Int(2)),
+            ("kind".to_string(), Property::String("bat".to_string())),
+        ]);
+        assert_eq!(properties["spawn"], Property::Class(spawn));
+    }
+
+    #[test]
+    fn refuses_templates() {
+        let xml = r#"<map width="1" height="1" tilewidth="8" tileheight="8">
+            <objectgroup><object id="1" template="chest.tx"/></objectgroup>
+        </map>"#;
+
+        assert!(matches!(
+            parse_map(xml, Path::new("")),
+            Err(TiledError::Unsupported(_))
+        ));
+    }
+}
@@ -0,0 +1,134 @@
+use std::time::Duration;
+
+/// Fixed-rate update clock. Real time is accumulated and consumed in whole
+/// steps, so the simulation always advances by the same `dt` no matter how
+/// fast frames are rendered.
+/// * If a frame took so long that more than `max_steps` steps are due, the
+///   rest is dropped. The simulation slows down instead of falling further
+///   behind trying to catch up.
+#[derive(Clone, Copy, Debug)]
+pub struct FixedTimestep {
+    step: Duration,
+    max_steps: u32,
+    accumulator: Duration,
+}
+
+impl Default for FixedTimestep {
+    fn default() -> FixedTimestep {
+        FixedTimestep::new(60)
+    }
+}
+
+impl FixedTimestep {
+    /// Steps `rate` times per second, catching up at most 5 steps per frame.
+    pub fn new(rate: u32) -> FixedTimestep {
+        FixedTimestep {
+            step: Duration::from_secs(1) / rate.max(1),
+            max_steps: 5,
+            accumulator: Duration::ZERO,
+        }
+    }
+
+    /// Sets how many steps a single frame may run to catch up.
+    pub fn with_max_steps(mut self, max_steps: u32) -> FixedTimestep {
+        self.max_steps = max_steps.max(1);
+        self
+    }
+
+    /// Length of one step.
+    pub fn step(&self) -> Duration {
+        self.step
+    }
+
+    /// Length of one step in seconds, as passed to `Game::update`.
+    pub fn dt(&self) -> f32 {
+        self.step.as_secs_f32()
+    }
+
+    /// Adds real time that has passed and returns how many steps to run.
+    pub fn advance(&mut self, elapsed: Duration) -> u32 {
+        self.accumulator += elapsed;
+
+        let mut steps = 0;
+        while self.accumulator >= self.step {
+            if steps == self.max_steps {
+                // spiral of death: drop the backlog, keep the partial step
+                self.accumulator = Duration::from_nanos(
+                    (self.accumulator.as_nanos() % self.step.as_nanos()) as u64,
+                );
+                break;
+            }
+
+            self.accumulator -= self.step;
+            steps += 1;
+        }
+
+        steps
+    }
+
+    /// How far real time is into the next step, from 0 to 1. Used to blend
+    /// between the previous and current simulation state when drawing.
+    pub fn alpha(&self) -> f32 {
+        self.accumulator.as_secs_f32() / self.step.as_secs_f32()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    #[test]
+    fn steps_at_the_given_rate() {
+        let timestep = FixedTimestep::new(50);
+        assert_eq!(timestep.step(), ms(20));
+        assert_eq!(timestep.dt(), 0.02);
+
+        // a rate of 0 is read as one step per second
+        assert_eq!(FixedTimestep::new(0).step(), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn runs_whole_steps_and_carries_the_rest() {
+        let mut timestep = FixedTimestep::new(50);
+
+        assert_eq!(timestep.advance(ms(10)), 0);
+        assert_eq!(timestep.advance(ms(10)), 1);
+        assert_eq!(timestep.advance(ms(45)), 2);
+        // 5ms left over from the last frame
+        assert_eq!(timestep.advance(ms(15)), 1);
+        assert_eq!(timestep.advance(ms(0)), 0);
+    }
+
+    #[test]
+    fn caps_steps_and_drops_the_backlog() {
+        let mut timestep = FixedTimestep::new(50).with_max_steps(3);
+
+        // 10 steps and a half are due, only 3 run
+        assert_eq!(timestep.advance(ms(210)), 3);
+        assert!((timestep.alpha() - 0.5).abs() < 1e-6);
+
+        // the dropped steps are not run later
+        assert_eq!(timestep.advance(ms(10)), 1);
+        assert_eq!(timestep.alpha(), 0.0);
+    }
+
+    #[test]
+    fn alpha_stays_below_one() {
+        let mut timestep = FixedTimestep::new(60);
+
+        for millis in [0, 1, 7, 16, 17, 33, 100, 250, 1000] {
+            timestep.advance(ms(millis));
+            let alpha = timestep.alpha();
+            assert!(
+                (0.0..1.0).contains(&alpha),
+                "alpha {} after {}ms",
+                alpha,
+                millis
+            );
+        }
+    }
+}
@@ -0,0 +1,116 @@
+use winit::dpi::{LogicalSize, PhysicalSize, Size};
+use winit::event_loop::EventLoopWindowTarget;
+use winit::monitor::{MonitorHandle, VideoMode};
+use winit::window::{Fullscreen, Icon, WindowBuilder};
+
+use super::renderer::texture::load_png;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FullscreenMode {
+    Windowed,
+    /// Window covering the whole monitor without changing its video mode.
+    Borderless,
+    /// Takes over the monitor, switching to the video mode closest to the
+    /// window size.
+    Exclusive,
+}
+
+/// Settings the window is created with.
+#[derive(Clone, Debug)]
+pub struct WindowConfig {
+    pub title: String,
+    /// Initial inner size in logical pixels.
+    pub size: [u32; 2],
+    pub min_size: Option<[u32; 2]>,
+    pub fullscreen: FullscreenMode,
+    pub resizable: bool,
+    /// Path to a PNG file used as the window icon.
+    pub icon: Option<String>,
+}
+
+impl Default for WindowConfig {
+    fn default() -> WindowConfig {
+        WindowConfig {
+            title: "Flintlock".to_string(),
+            size: [1280, 720],
+            min_size: None,
+            fullscreen: FullscreenMode::Windowed,
+            resizable: true,
+            icon: None,
+        }
+    }
+}
+
+impl WindowConfig {
+    pub(crate) fn builder<T>(&self, event_loop: &EventLoopWindowTarget<T>) -> WindowBuilder {
+        let mut builder = WindowBuilder::new()
+            .with_title(self.title.clone())
+            .with_inner_size(LogicalSize::new(self.size[0], self.size[1]))
+            .with_resizable(self.resizable)
+            .with_fullscreen(fullscreen(
+                self.fullscreen,
+                event_loop.primary_monitor(),
+                LogicalSize::new(self.size[0], self.size[1]).into(),
+            ))
+            .with_window_icon(self.icon.as_deref().and_then(load_icon));
+
+        if let Some(min_size) = self.min_size {
+            builder = builder.with_min_inner_size(LogicalSize::new(min_size[0], min_size[1]));
+        }
+
+        builder
+    }
+}
+
+/// Loads a PNG file as a window icon. Failures are reported and leave the
+/// default icon in place, as a missing icon is no reason not to start.
+pub fn load_icon(path: &str) -> Option<Icon> {
+    let (pixels, [width, height]) = match load_png(path) {
+        Ok(r) => r,
+        Err(err) => {
+            println!("Failed to load window icon {}: {}", path, err);
+            return None;
+        }
+    };
+
+    match Icon::from_rgba(pixels, width, height) {
+        Ok(icon) => Some(icon),
+        Err(err) => {
+            println!("Invalid window icon {}: {}", path, err);
+            None
+        }
+    }
+}
+
+/// Translates a fullscreen mode for winit on the given monitor. `size` is
+/// the window size an exclusive video mode is picked for.
+pub(crate) fn fullscreen(
+    mode: FullscreenMode,
+    monitor: Option<MonitorHandle>,
+    size: Size,
+) -> Option<Fullscreen> {
+    match mode {
+        FullscreenMode::Windowed => None,
+        FullscreenMode::Borderless => Some(Fullscreen::Borderless(monitor)),
+        FullscreenMode::Exclusive => match monitor.and_then(|m| closest_video_mode(&m, size)) {
+            Some(video_mode) => Some(Fullscreen::Exclusive(video_mode)),
+            // no video modes to pick from, e.g. on Wayland
+            None => Some(Fullscreen::Borderless(None)),
+        },
+    }
+}
+
+/// Picks the video mode nearest in size, preferring higher refresh rates.
+/// Video modes are in physical pixels, so a logical size is scaled by the
+/// monitor's scale factor first.
+fn closest_video_mode(monitor: &MonitorHandle, size: Size) -> Option<VideoMode> {
+    let size: PhysicalSize<u32> = size.to_physical(monitor.scale_factor());
+
+    monitor.video_modes().min_by_key(|mode| {
+        let mode_size = mode.size();
+        let distance = (mode_size.width as i64 - size.width as i64).abs()
+            + (mode_size.height as i64 - size.height as i64).abs();
+
+        (distance, std::cmp::Reverse(mode.refresh_rate_millihertz()))
+    })
+}
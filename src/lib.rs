@@ -0,0 +1,27 @@
+//! Flintlock is a 2D renderer and game framework on top of vulkano.
+//!
+//! Implement `Game` and hand it to `Application::run_game`, or build the
+//! game out of entities and systems in the `World`.
+
+pub mod application;
+
+pub use application::animation::{
+    AnimatedSprite, AnimationClip, AnimationEvent, PlayMode, SpriteSheet,
+};
+pub use application::ecs::{Entity, Schedule, World};
+pub use application::game::{Context, Game};
+pub use application::input::{ActionMap, Binding, GamepadAxis, GamepadButton, GamepadId, Input};
+pub use application::renderer::atlas::{AtlasBuilder, AtlasError, AtlasRegion, TextureAtlas};
+pub use application::renderer::config::RendererConfig;
+pub use application::renderer::surface::WindowSurface;
+pub use application::renderer::target::{RenderTarget, RenderTargetId, Target};
+pub use application::renderer::text::{Align, Font, FontError, Text, TextSpan};
+pub use application::renderer::tilemap::{
+    StaggerAxis, StaggerIndex, Tile, TileLayout, Tilemap, Tileset,
+};
+pub use application::renderer::view::View;
+pub use application::renderer::{Frame, Renderer};
+pub use application::tiled::{TiledError, TiledMap, TiledObject};
+pub use application::timestep::FixedTimestep;
+pub use application::window::{FullscreenMode, WindowConfig};
+pub use application::Application;